@@ -0,0 +1,71 @@
+//! `cargo bench` (add `--features bench` to include crossbeam's `ArrayQueue` as a baseline).
+//!
+//! Each sample streams `iters` values from the bench thread to a consumer thread, the same
+//! SPSC pattern as the binary, and Criterion times the whole transfer.
+use std::hint::black_box;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use lockfree_fifo::backoff::SpinBackoff;
+use lockfree_fifo::{
+    Fifo2, Fifo3, Fifo4, Fifo5, Fifo5FetchAdd, Fifo6, Fifo6a, FifoMpsc, SpscQueue,
+};
+
+const CAPACITIES: [usize; 3] = [1 << 10, 1 << 16, 1 << 20];
+
+fn transfer<Q>(queue: &Arc<Q>, iters: u64) -> Duration
+where
+    Q: SpscQueue<u64> + Send + Sync + 'static,
+{
+    let consumer = {
+        let queue = queue.clone();
+        thread::spawn(move || {
+            let mut backoff = SpinBackoff;
+            for _ in 0..iters {
+                black_box(queue.pop_spin(&mut backoff));
+            }
+        })
+    };
+
+    let start = Instant::now();
+    let mut backoff = SpinBackoff;
+    for i in 0..iters {
+        queue.push_spin(i, &mut backoff);
+    }
+    consumer.join().unwrap();
+    start.elapsed()
+}
+
+fn bench_queue<Q>(c: &mut Criterion, name: &str, new: impl Fn(usize) -> Q)
+where
+    Q: SpscQueue<u64> + Send + Sync + 'static,
+{
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(1));
+    for capacity in CAPACITIES {
+        // One queue per capacity, reused across samples; every sample drains it fully.
+        let queue = Arc::new(new(capacity));
+        group.bench_with_input(BenchmarkId::from_parameter(capacity), &queue, |b, queue| {
+            b.iter_custom(|iters| transfer(queue, iters))
+        });
+    }
+    group.finish();
+}
+
+fn throughput(c: &mut Criterion) {
+    bench_queue(c, "Fifo2", Fifo2::new);
+    bench_queue(c, "Fifo3", Fifo3::new);
+    bench_queue(c, "Fifo4", Fifo4::new);
+    bench_queue(c, "Fifo5", Fifo5::new);
+    bench_queue(c, "Fifo5FetchAdd", Fifo5FetchAdd::new);
+    bench_queue(c, "Fifo6", Fifo6::new);
+    bench_queue(c, "Fifo6a", Fifo6a::new);
+    bench_queue(c, "FifoMpsc", FifoMpsc::new);
+    #[cfg(feature = "bench")]
+    bench_queue(c, "ArrayQueue", crossbeam::queue::ArrayQueue::new);
+}
+
+criterion_group!(benches, throughput);
+criterion_main!(benches);
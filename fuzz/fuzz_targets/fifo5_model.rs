@@ -0,0 +1,60 @@
+//! Drives a `Fifo5` with an arbitrary sequence of pushes and pops and checks every result
+//! against a `VecDeque` of the same capacity. Run with:
+//!
+//! ```text
+//! cargo +nightly fuzz run fifo5_model
+//! ```
+//!
+//! A minimized failing input can be replayed as a plain test with `ScriptedFifo` in
+//! `tests/scripted.rs`.
+#![no_main]
+
+use std::collections::VecDeque;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use lockfree_fifo::Fifo5;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Push(u64),
+    Pop,
+}
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    capacity: u8,
+    // Start the cursors this far below usize::MAX (or at 0 when None), so short op
+    // sequences still cross the overflow point.
+    start_below_max: Option<u8>,
+    ops: Vec<Op>,
+}
+
+fuzz_target!(|input: Input| {
+    let mut capacity = usize::from(input.capacity).max(1);
+    let queue = match input.start_below_max {
+        // Crossing the wrap is only supported for power-of-two capacities (see Fifo5's docs).
+        Some(offset) => {
+            capacity = capacity.next_power_of_two();
+            Fifo5::with_start_cursor(capacity, usize::MAX - usize::from(offset))
+        }
+        None => Fifo5::new(capacity),
+    };
+    let mut model = VecDeque::with_capacity(capacity);
+
+    for op in input.ops {
+        match op {
+            Op::Push(value) => {
+                let result = queue.push(value);
+                if model.len() < capacity {
+                    assert_eq!(result, Ok(()), "push rejected with room left");
+                    model.push_back(value);
+                } else {
+                    assert_eq!(result, Err(value), "push accepted into a full queue");
+                }
+            }
+            Op::Pop => assert_eq!(queue.pop(), model.pop_front()),
+        }
+        assert_eq!(queue.len(), model.len());
+    }
+});
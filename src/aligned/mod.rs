@@ -0,0 +1,90 @@
+//! Over-aligned queue elements, for payloads that want wider alignment than their type has.
+//!
+//! The queues lay their slots out as a plain array of `T`, so aligning `T` aligns every
+//! slot: `Fifo5<Aligned<[f32; 8], A32>>` puts each vector on a 32-byte boundary, and the
+//! slices from [`Fifo5::peek_contiguous`](crate::Fifo5::peek_contiguous) are aligned too.
+//!
+//! The cost is padding. `Aligned<T, A>` is `size_of::<T>()` rounded up to the alignment, so
+//! `[f32; 8]` (32 bytes) at `A32` is free, while `[f32; 3]` (12 bytes) at `A32` spends 20
+//! bytes of every slot on padding and the ring is 32 / 12 times as large.
+
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Alignment marker for [`Aligned`]: one of [`A16`], [`A32`], [`A64`] or [`A128`].
+///
+/// `repr(align)` only takes a literal, so the alignment is picked by type rather than by a
+/// const generic.
+pub trait Alignment: sealed::Sealed + Copy {
+    const BYTES: usize;
+}
+
+macro_rules! alignments {
+    ($($name:ident = $bytes:literal),* $(,)?) => {
+        $(
+            #[doc = concat!("Aligns [`Aligned`] to ", stringify!($bytes), " bytes.")]
+            #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+            #[repr(align($bytes))]
+            pub struct $name;
+
+            impl sealed::Sealed for $name {}
+
+            impl Alignment for $name {
+                const BYTES: usize = $bytes;
+            }
+
+            const _: () = assert!(core::mem::align_of::<Aligned<u8, $name>>() == $bytes);
+        )*
+    };
+}
+
+alignments!(A16 = 16, A32 = 32, A64 = 64, A128 = 128);
+
+/// `T` aligned to at least `A::BYTES`. Derefs to `T`.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct Aligned<T, A: Alignment> {
+    // Zero-sized, but carries A's alignment into the struct.
+    _align: [A; 0],
+    value: T,
+}
+
+impl<T, A: Alignment> Aligned<T, A> {
+    pub const fn new(value: T) -> Aligned<T, A> {
+        Aligned { _align: [], value }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, A: Alignment> From<T> for Aligned<T, A> {
+    fn from(value: T) -> Aligned<T, A> {
+        Aligned::new(value)
+    }
+}
+
+impl<T, A: Alignment> Deref for Aligned<T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T, A: Alignment> DerefMut for Aligned<T, A> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: fmt::Debug, A: Alignment> fmt::Debug for Aligned<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
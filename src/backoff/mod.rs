@@ -0,0 +1,134 @@
+#[cfg(feature = "std")]
+use std::thread;
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+// Up to 2^SPIN_LIMIT spin_loop hints per snooze before escalating, as in crossbeam_utils.
+const SPIN_LIMIT: u32 = 6;
+// Number of yield_now rounds SleepBackoff tries before it starts sleeping.
+#[cfg(feature = "std")]
+const YIELD_LIMIT: u32 = 10;
+
+/// What to do while waiting for a full queue to drain or an empty one to fill.
+///
+/// Used by [`SpscQueue::push_spin`](crate::SpscQueue::push_spin) and
+/// [`SpscQueue::pop_spin`](crate::SpscQueue::pop_spin); the queues' own `push` / `pop`
+/// never wait.
+pub trait Backoff {
+    /// Waits after a failed attempt, escalating on repeated calls.
+    fn snooze(&mut self);
+    /// Called after a successful attempt so the next wait starts small again.
+    fn reset(&mut self);
+}
+
+/// One `spin_loop` hint per attempt. Lowest latency, but burns the core and starves the other
+/// side when threads outnumber cores.
+#[derive(Debug, Default, Clone)]
+pub struct SpinBackoff;
+
+impl Backoff for SpinBackoff {
+    fn snooze(&mut self) {
+        core::hint::spin_loop();
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Exponential spinning, then `thread::yield_now` once the spin budget is used up.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone)]
+pub struct YieldBackoff {
+    step: u32,
+}
+
+#[cfg(feature = "std")]
+impl Backoff for YieldBackoff {
+    fn snooze(&mut self) {
+        if self.step <= SPIN_LIMIT {
+            for _ in 0..1 << self.step {
+                core::hint::spin_loop();
+            }
+            self.step += 1;
+        } else {
+            thread::yield_now();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.step = 0;
+    }
+}
+
+/// Spins and yields like [`YieldBackoff`], then falls back to sleeping for a fixed interval.
+/// Cheapest on CPU for queues that sit idle for long stretches, at the cost of wake-up latency.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct SleepBackoff {
+    step: u32,
+    sleep: Duration,
+}
+
+#[cfg(feature = "std")]
+impl SleepBackoff {
+    pub fn new(sleep: Duration) -> SleepBackoff {
+        SleepBackoff { step: 0, sleep }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for SleepBackoff {
+    fn default() -> SleepBackoff {
+        SleepBackoff::new(Duration::from_micros(50))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Backoff for SleepBackoff {
+    fn snooze(&mut self) {
+        if self.step <= SPIN_LIMIT {
+            for _ in 0..1 << self.step {
+                core::hint::spin_loop();
+            }
+        } else if self.step <= SPIN_LIMIT + YIELD_LIMIT {
+            thread::yield_now();
+        } else {
+            thread::sleep(self.sleep);
+            return;
+        }
+        self.step += 1;
+    }
+
+    fn reset(&mut self) {
+        self.step = 0;
+    }
+}
+
+/// Backoff for the MPMC queues' internal retries (a lost `compare_exchange`, or waiting for a
+/// slot's turn): 1, 2, 4, ... spins up to 2^SPIN_LIMIT, then `yield_now` (with `std`;
+/// without it, it keeps spinning at the cap). One is created per call, so it starts over on
+/// the next operation.
+pub(crate) struct CasBackoff {
+    step: u32,
+}
+
+impl CasBackoff {
+    pub(crate) const fn new() -> CasBackoff {
+        CasBackoff { step: 0 }
+    }
+
+    pub(crate) fn spin(&mut self) {
+        if self.step <= SPIN_LIMIT {
+            for _ in 0..1 << self.step {
+                crate::sync::spin_loop();
+            }
+            self.step += 1;
+        } else {
+            #[cfg(all(feature = "std", not(loom)))]
+            thread::yield_now();
+            #[cfg(not(all(feature = "std", not(loom))))]
+            for _ in 0..1 << SPIN_LIMIT {
+                crate::sync::spin_loop();
+            }
+        }
+    }
+}
@@ -0,0 +1,593 @@
+use crossbeam::queue::ArrayQueue;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::SpscQueue;
+use crate::backoff::{Backoff, SpinBackoff};
+
+// Crossbeam's ArrayQueue is MPMC, but works fine for SPSC.
+impl<T> SpscQueue<T> for ArrayQueue<T> {
+    fn try_push(&self, item: T) -> Result<(), T> {
+        self.push(item)
+    }
+
+    fn try_pop(&self) -> Option<T> {
+        self.pop()
+    }
+
+    fn capacity(&self) -> usize {
+        ArrayQueue::capacity(self)
+    }
+}
+
+/// Pushes `0..iters` from the calling thread while a spawned consumer pops and checks the
+/// order. Prints `"{label} Time: ..."` and returns throughput in items per second.
+pub fn bench<Q>(label: &str, queue: Arc<Q>, iters: usize) -> f64
+where
+    Q: SpscQueue<usize> + Send + Sync + 'static,
+{
+    bench_with::<Q, SpinBackoff>(label, queue, iters)
+}
+
+/// Same as [`bench`], but both sides wait with a fresh `B` instead of a bare `spin_loop`.
+pub fn bench_with<Q, B>(label: &str, queue: Arc<Q>, iters: usize) -> f64
+where
+    Q: SpscQueue<usize> + Send + Sync + 'static,
+    B: Backoff + Default,
+{
+    let queue_consumer = queue.clone();
+    bench_fn::<_, _, B>(
+        label,
+        iters,
+        move |i| queue.try_push(i),
+        move || queue_consumer.try_pop(),
+    )
+}
+
+/// Which cores [`bench_pinned`] puts the two threads on, as indices into
+/// `core_affinity::get_core_ids()`.
+#[cfg(feature = "affinity")]
+#[derive(Debug, Clone, Copy)]
+pub struct Pinning {
+    pub producer: usize,
+    pub consumer: usize,
+}
+
+/// Same as [`bench`], with the producer and consumer pinned to the cores in `pinning`. The
+/// placement is appended to the label, e.g. `"Fifo3 [producer: core 0, consumer: core 2]"`.
+///
+/// The producer runs on its own thread too, so the caller's affinity is left alone. Panics
+/// if a core index is out of range or the OS refuses the pinning.
+#[cfg(feature = "affinity")]
+pub fn bench_pinned<Q>(label: &str, queue: Arc<Q>, iters: usize, pinning: Pinning) -> f64
+where
+    Q: SpscQueue<usize> + Send + Sync + 'static,
+{
+    let label = format!(
+        "{} [producer: core {}, consumer: core {}]",
+        label, pinning.producer, pinning.consumer
+    );
+    let queue_consumer = queue.clone();
+    let mut consumer_pinned = false;
+
+    thread::spawn(move || {
+        pin_current_thread(pinning.producer);
+        bench_fn::<_, _, SpinBackoff>(
+            &label,
+            iters,
+            move |i| queue.try_push(i),
+            move || {
+                // bench_fn owns the consumer thread, so pin it on its first pop.
+                if !consumer_pinned {
+                    pin_current_thread(pinning.consumer);
+                    consumer_pinned = true;
+                }
+                queue_consumer.try_pop()
+            },
+        )
+    })
+    .join()
+    .unwrap()
+}
+
+#[cfg(feature = "affinity")]
+fn pin_current_thread(core: usize) {
+    let cores = core_affinity::get_core_ids().expect("failed to query the CPU cores");
+    let id = *cores
+        .get(core)
+        .unwrap_or_else(|| panic!("core {core} out of range, {} available", cores.len()));
+    assert!(
+        core_affinity::set_for_current(id),
+        "failed to pin thread to core {core}"
+    );
+}
+
+/// The harness behind every benchmark, for queues that don't implement [`SpscQueue`].
+///
+/// `push` is called from the current thread with `0..iters` in order, retrying (with a
+/// `B` backoff) while it hands the value back. `pop` runs on a spawned consumer thread that
+/// checks every value arrives in order and that none are lost. Prints `"{label} Time: ..."`
+/// and returns throughput in items per second.
+pub fn bench_fn<P, C, B>(label: &str, iters: usize, mut push: P, mut pop: C) -> f64
+where
+    P: FnMut(usize) -> Result<(), usize>,
+    C: FnMut() -> Option<usize> + Send + 'static,
+    B: Backoff + Default,
+{
+    let done = Arc::new(AtomicBool::new(false));
+    let done_consumer = done.clone();
+
+    let consumer = thread::spawn(move || {
+        let mut backoff = B::default();
+        let mut expected = 0;
+        let check = |val: usize, expected: &mut usize| {
+            assert_eq!(val, *expected, "Consumer received out-of-order value");
+            *expected += 1;
+        };
+        loop {
+            if let Some(val) = pop() {
+                check(val, &mut expected);
+                backoff.reset();
+            } else if done_consumer.load(Ordering::Acquire) {
+                // The producer may have pushed more right before setting done, so only stop
+                // once a pop after seeing done comes back empty. Whatever it returns still
+                // has to go through the order check.
+                match pop() {
+                    Some(val) => check(val, &mut expected),
+                    None => break,
+                }
+            } else {
+                backoff.snooze();
+            }
+        }
+        assert_eq!(expected, iters, "Consumer lost values");
+    });
+
+    let start = Instant::now();
+
+    let mut backoff = B::default();
+    for i in 0..iters {
+        while push(i).is_err() {
+            backoff.snooze();
+        }
+        backoff.reset();
+    }
+
+    done.store(true, Ordering::Release);
+    consumer.join().unwrap();
+
+    let duration = start.elapsed();
+    let secs = duration.as_secs_f64();
+    println!("{} Time: {:.4}s, Iters: {}", label, secs, iters);
+
+    (iters as f64) / secs
+}
+
+/// Multi-producer multi-consumer run: `producers` threads push disjoint ranges of
+/// `0..iters` and `consumers` threads pop until the producers are done and the queue is
+/// drained. Per-value ordering only holds for SPSC, so instead we check that the total
+/// count and the sum of popped values match what was pushed. Prints
+/// `"{label} MPMC ({producers}P/{consumers}C) Time: ..."`.
+pub fn bench_mpmc<Q>(
+    label: &str,
+    queue: Arc<Q>,
+    iters: usize,
+    producers: usize,
+    consumers: usize,
+) -> f64
+where
+    Q: SpscQueue<usize> + Send + Sync + 'static,
+{
+    let done = Arc::new(AtomicBool::new(false));
+
+    let consumer_handles: Vec<_> = (0..consumers)
+        .map(|_| {
+            let queue = queue.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                let mut count = 0usize;
+                let mut sum = 0usize;
+                loop {
+                    if let Some(val) = queue.try_pop() {
+                        count += 1;
+                        sum = sum.wrapping_add(val);
+                    } else if done.load(Ordering::Acquire) {
+                        // Same as in bench_fn: a value found after seeing done still counts.
+                        match queue.try_pop() {
+                            Some(val) => {
+                                count += 1;
+                                sum = sum.wrapping_add(val);
+                            }
+                            None => break,
+                        }
+                    } else {
+                        std::hint::spin_loop();
+                    }
+                }
+                (count, sum)
+            })
+        })
+        .collect();
+
+    let start = Instant::now();
+
+    let per_producer = iters.div_ceil(producers);
+    let producer_handles: Vec<_> = (0..producers)
+        .map(|p| {
+            let queue = queue.clone();
+            let range = (p * per_producer).min(iters)..((p + 1) * per_producer).min(iters);
+            thread::spawn(move || {
+                for i in range {
+                    while queue.try_push(i).is_err() {
+                        std::hint::spin_loop();
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for handle in producer_handles {
+        handle.join().unwrap();
+    }
+    done.store(true, Ordering::Release);
+
+    let (mut count, mut sum) = (0usize, 0usize);
+    for handle in consumer_handles {
+        let (c, s) = handle.join().unwrap();
+        count += c;
+        sum = sum.wrapping_add(s);
+    }
+
+    let duration = start.elapsed();
+    assert_eq!(
+        count, iters,
+        "Consumers popped a different number of items than pushed"
+    );
+    let expected_sum = (0..iters).fold(0usize, |acc, i| acc.wrapping_add(i));
+    assert_eq!(
+        sum, expected_sum,
+        "Popped values don't match the pushed values"
+    );
+
+    let secs = duration.as_secs_f64();
+    println!(
+        "{} MPMC ({}P/{}C) Time: {:.4}s, Iters: {}",
+        label, producers, consumers, secs, iters
+    );
+
+    (iters as f64) / secs
+}
+
+/// How evenly `consumers` threads share the work: `producers` threads push `0..iters`,
+/// then one `usize::MAX` sentinel per consumer is pushed and each consumer stops at the
+/// first sentinel it pops. Takes `push` / `pop` as functions so queues whose `pop` waits
+/// (and so can't implement [`SpscQueue`]'s non-blocking `try_pop`) can be measured too.
+///
+/// Prints the spread of per-consumer counts and returns them.
+pub fn bench_fairness<Q>(
+    label: &str,
+    queue: Arc<Q>,
+    push: fn(&Q, usize) -> Result<(), usize>,
+    pop: fn(&Q) -> Option<usize>,
+    iters: usize,
+    producers: usize,
+    consumers: usize,
+) -> Vec<usize>
+where
+    Q: Send + Sync + 'static,
+{
+    let push_spin = move |queue: &Q, item: usize| {
+        while push(queue, item).is_err() {
+            std::hint::spin_loop();
+        }
+    };
+
+    let consumer_handles: Vec<_> = (0..consumers)
+        .map(|_| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                let mut count = 0usize;
+                loop {
+                    match pop(&queue) {
+                        Some(usize::MAX) => break,
+                        Some(_) => count += 1,
+                        None => std::hint::spin_loop(),
+                    }
+                }
+                count
+            })
+        })
+        .collect();
+
+    let start = Instant::now();
+
+    let per_producer = iters.div_ceil(producers);
+    let producer_handles: Vec<_> = (0..producers)
+        .map(|p| {
+            let queue = queue.clone();
+            let range = (p * per_producer).min(iters)..((p + 1) * per_producer).min(iters);
+            thread::spawn(move || {
+                for i in range {
+                    push_spin(&queue, i);
+                }
+            })
+        })
+        .collect();
+
+    for handle in producer_handles {
+        handle.join().unwrap();
+    }
+    for _ in 0..consumers {
+        push_spin(&queue, usize::MAX);
+    }
+
+    let counts: Vec<usize> = consumer_handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    let secs = start.elapsed().as_secs_f64();
+
+    assert_eq!(
+        counts.iter().sum::<usize>(),
+        iters,
+        "Consumers popped a different number of items than pushed"
+    );
+    let min = counts.iter().copied().min().unwrap_or(0);
+    let max = counts.iter().copied().max().unwrap_or(0);
+    println!(
+        "{} Fairness ({}P/{}C) Time: {:.4}s, per-consumer min {}, max {}, max/min {:.2}",
+        label,
+        producers,
+        consumers,
+        secs,
+        min,
+        max,
+        max as f64 / min.max(1) as f64
+    );
+
+    counts
+}
+
+/// Round-trip latency percentiles from [`bench_latency`], in nanoseconds.
+#[derive(Debug, Clone, Copy)]
+pub struct Latency {
+    pub p50: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub max: u64,
+}
+
+/// Times every push→pop round trip: each value pushed is the producer's timestamp (ns
+/// since a shared start `Instant`) and the consumer records `now - value` on pop.
+///
+/// The producer runs flat out, so once the ring fills up the numbers include the time
+/// spent queued behind up to `capacity` earlier items, not just the handoff cost. Samples
+/// go into a `Vec<u64>` pre-sized to `iters`, so nothing allocates on the hot path.
+pub fn bench_latency<Q>(label: &str, queue: Arc<Q>, iters: usize) -> Latency
+where
+    Q: SpscQueue<u64> + Send + Sync + 'static,
+{
+    assert!(iters > 0, "need at least one sample");
+    let origin = Instant::now();
+    let queue_consumer = queue.clone();
+
+    let consumer = thread::spawn(move || {
+        let mut samples = Vec::with_capacity(iters);
+        while samples.len() < iters {
+            if let Some(sent) = queue_consumer.try_pop() {
+                let now = origin.elapsed().as_nanos() as u64;
+                samples.push(now - sent);
+            } else {
+                std::hint::spin_loop();
+            }
+        }
+        samples
+    });
+
+    for _ in 0..iters {
+        // Stamp each attempt rather than the first one, so time spent spinning on a full
+        // queue isn't counted as latency.
+        while queue.try_push(origin.elapsed().as_nanos() as u64).is_err() {
+            std::hint::spin_loop();
+        }
+    }
+
+    let mut samples = consumer.join().unwrap();
+    samples.sort_unstable();
+    let percentile = |p: f64| samples[((samples.len() - 1) as f64 * p).round() as usize];
+    let latency = Latency {
+        p50: percentile(0.50),
+        p99: percentile(0.99),
+        p999: percentile(0.999),
+        max: samples[samples.len() - 1],
+    };
+    println!(
+        "{} Latency (ns): p50 {}, p99 {}, p99.9 {}, max {}",
+        label, latency.p50, latency.p99, latency.p999, latency.max
+    );
+
+    latency
+}
+
+/// Summary of the gaps between consecutive successful pops, see [`bench_arrivals`].
+pub struct Arrivals {
+    pub mean_gap: f64,
+    pub max_gap: u64,
+    /// Fraction of pops that came after at least one empty poll, i.e. the consumer had
+    /// caught up and was spinning.
+    pub after_spin: f64,
+    /// `(lower bound in ns, count)` for each non-empty power-of-two bucket: a gap `g` lands
+    /// in the bucket starting at the largest power of two `<= g` (0 for `g == 0`).
+    pub histogram: Vec<(u64, usize)>,
+}
+
+/// Measures how bursty the consumer's arrivals are: the producer pushes `0..iters` flat
+/// out while the consumer timestamps every successful pop and keeps the delta to the
+/// previous one. Throughput averages hide a consumer that alternates between draining a
+/// backlog and starving; a two-humped histogram with a high `after_spin` shows it.
+///
+/// Deltas go into a `Vec<u64>` pre-sized to `iters` and the histogram is only built after
+/// the run, so the consumer's extra work per pop is one clock read and one store.
+pub fn bench_arrivals<Q>(label: &str, queue: Arc<Q>, iters: usize) -> Arrivals
+where
+    Q: SpscQueue<usize> + Send + Sync + 'static,
+{
+    assert!(iters > 1, "need at least two pops for a gap");
+    let queue_consumer = queue.clone();
+
+    let consumer = thread::spawn(move || {
+        let mut gaps = Vec::with_capacity(iters - 1);
+        let mut after_spin = 0;
+        let mut spun = false;
+        let mut last = None;
+        while gaps.len() < iters - 1 {
+            if queue_consumer.try_pop().is_some() {
+                let now = Instant::now();
+                if let Some(last) = last {
+                    gaps.push(now.duration_since(last).as_nanos() as u64);
+                    after_spin += usize::from(spun);
+                }
+                last = Some(now);
+                spun = false;
+            } else {
+                spun = true;
+                std::hint::spin_loop();
+            }
+        }
+        (gaps, after_spin)
+    });
+
+    for i in 0..iters {
+        while queue.try_push(i).is_err() {
+            std::hint::spin_loop();
+        }
+    }
+
+    let (gaps, after_spin) = consumer.join().unwrap();
+    let mut buckets = [0usize; 64];
+    for &gap in &gaps {
+        buckets[gap.checked_ilog2().map_or(0, |bit| bit as usize + 1)] += 1;
+    }
+    let arrivals = Arrivals {
+        mean_gap: gaps.iter().sum::<u64>() as f64 / gaps.len() as f64,
+        max_gap: gaps.iter().copied().max().unwrap_or(0),
+        after_spin: after_spin as f64 / gaps.len() as f64,
+        histogram: buckets
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(bucket, &count)| (if bucket == 0 { 0 } else { 1 << (bucket - 1) }, count))
+            .collect(),
+    };
+
+    println!(
+        "{} Arrival gaps (ns): mean {:.1}, max {}, after a spin {:.1}%",
+        label,
+        arrivals.mean_gap,
+        arrivals.max_gap,
+        arrivals.after_spin * 100.0
+    );
+    for &(low, count) in &arrivals.histogram {
+        println!(
+            "  >= {:>10} ns: {:>10} ({:.2}%)",
+            low,
+            count,
+            count as f64 * 100.0 / gaps.len() as f64
+        );
+    }
+
+    arrivals
+}
+
+/// What a consumer's waiting strategy costs, from [`bench_wakeup`].
+#[derive(Debug, Clone, Copy)]
+pub struct Wakeup {
+    /// Push→pop latency over every item, in nanoseconds.
+    pub mean_latency: f64,
+    pub max_latency: u64,
+    /// CPU time the consumer thread used, from `/proc/thread-self/schedstat`. `None` where
+    /// that file doesn't exist (anything but Linux).
+    pub consumer_cpu: Option<Duration>,
+    pub elapsed: Duration,
+}
+
+/// Compares waiting strategies on a mostly idle queue, where they actually differ: the
+/// producer pushes `bursts` bursts of `burst` items with `push`, sleeping `gap` after each,
+/// and the consumer takes every item with `recv`, which decides how it waits. Items are
+/// the push time in ns since a shared start, so each pop yields its latency.
+///
+/// A spinning consumer has the lowest latency but burns its core through every gap; a
+/// parking one is nearly free on CPU but pays a wake-up per burst. `consumer_cpu` against
+/// `elapsed` shows the first, the latencies the second.
+pub fn bench_wakeup<Q, P, R>(
+    label: &str,
+    queue: Arc<Q>,
+    push: P,
+    recv: R,
+    bursts: usize,
+    burst: usize,
+    gap: Duration,
+) -> Wakeup
+where
+    Q: Send + Sync + 'static,
+    P: Fn(&Q, u64),
+    R: Fn(&Q) -> u64 + Send + 'static,
+{
+    let items = bursts * burst;
+    assert!(items > 0, "need at least one item");
+    let origin = Instant::now();
+    let queue_consumer = queue.clone();
+
+    let consumer = thread::spawn(move || {
+        let cpu_start = thread_cpu_time();
+        let mut samples = Vec::with_capacity(items);
+        for _ in 0..items {
+            let sent = recv(&queue_consumer);
+            samples.push(origin.elapsed().as_nanos() as u64 - sent);
+        }
+        let cpu = thread_cpu_time()
+            .zip(cpu_start)
+            .map(|(end, start)| end - start);
+        (samples, cpu)
+    });
+
+    for _ in 0..bursts {
+        for _ in 0..burst {
+            push(&queue, origin.elapsed().as_nanos() as u64);
+        }
+        thread::sleep(gap);
+    }
+
+    let (samples, consumer_cpu) = consumer.join().unwrap();
+    let wakeup = Wakeup {
+        mean_latency: samples.iter().sum::<u64>() as f64 / items as f64,
+        max_latency: samples.iter().copied().max().unwrap_or(0),
+        consumer_cpu,
+        elapsed: origin.elapsed(),
+    };
+    let cpu = wakeup.consumer_cpu.map_or("n/a".to_string(), |cpu| {
+        format!(
+            "{:.1}%",
+            cpu.as_secs_f64() * 100.0 / wakeup.elapsed.as_secs_f64()
+        )
+    });
+    println!(
+        "{} Wakeup: latency mean {:.0} ns, max {} ns, consumer CPU {} of {:.3}s",
+        label,
+        wakeup.mean_latency,
+        wakeup.max_latency,
+        cpu,
+        wakeup.elapsed.as_secs_f64()
+    );
+
+    wakeup
+}
+
+// Time the calling thread has spent on a CPU. The first field of schedstat is that, in ns.
+fn thread_cpu_time() -> Option<Duration> {
+    let stat = std::fs::read_to_string("/proc/thread-self/schedstat").ok()?;
+    let ns = stat.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_nanos(ns))
+}
@@ -0,0 +1,122 @@
+use crate::Fifo5;
+use crate::cache_padded::CACHE_LINE;
+use crate::error::FifoError;
+
+/// Capacity used by [`FifoBuilder::new`] and `Fifo5::default()`.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// Capacity used by `Fifo5::auto()`: 64K slots, enough to ride out consumer hiccups of a few
+/// milliseconds at typical message rates while staying a power of two.
+pub const AUTO_CAPACITY: usize = 1 << 16;
+
+/// Number of slots giving roughly `target_buffer_bytes` of buffering for messages of
+/// `msg_bytes` each: `target_buffer_bytes / msg_bytes` rounded up to a power of two, and at
+/// least one slot even if a single message is larger than the budget.
+///
+/// Fails with [`FifoError::ZeroMessageSize`] if `msg_bytes` is zero (e.g. `size_of` of a
+/// zero-sized type), or [`FifoError::CapacityOverflow`] if the rounding overflows.
+pub fn capacity_for_message_size(
+    msg_bytes: usize,
+    target_buffer_bytes: usize,
+) -> Result<usize, FifoError> {
+    let slots = target_buffer_bytes
+        .checked_div(msg_bytes)
+        .ok_or(FifoError::ZeroMessageSize)?;
+    slots
+        .max(1)
+        .checked_next_power_of_two()
+        .ok_or(FifoError::CapacityOverflow(slots))
+}
+
+/// The capacity a queue's `new` actually uses for a `requested` one: rounded up to the next
+/// power of two for the variants that index with a mask (`require_pow2`), unchanged for the
+/// rest. Panics if the rounding overflows `usize`, like any other impossible allocation.
+pub(crate) fn normalize_capacity(requested: usize, require_pow2: bool) -> usize {
+    if !require_pow2 {
+        return requested;
+    }
+    requested
+        .checked_next_power_of_two()
+        .unwrap_or_else(|| panic!("{}", FifoError::CapacityOverflow(requested)))
+}
+
+/// The capacity of a turn-based (Vyukov) ring for a `requested` one, rounded up to a power
+/// of two like [`normalize_capacity`]. These rings need at least two slots: with one,
+/// "filled this lap" (turn = pos + 1) and "free next lap" (turn = pos + capacity) are the
+/// same value, so a push would overwrite an unread item.
+pub(crate) fn turn_ring_capacity(requested: usize) -> Result<usize, FifoError> {
+    match requested {
+        0 => Err(FifoError::ZeroCapacity),
+        1 => Err(FifoError::CapacityTooSmall {
+            capacity: requested,
+            min: 2,
+        }),
+        _ => requested
+            .checked_next_power_of_two()
+            .ok_or(FifoError::CapacityOverflow(requested)),
+    }
+}
+
+/// Validated construction for [`Fifo5`]; unlike `new`, `build` reports bad settings as a
+/// [`FifoError`], e.g. `FifoBuilder::new().capacity(1000).power_of_two(true).build::<u64>()`.
+#[derive(Debug, Clone, Copy)]
+pub struct FifoBuilder {
+    capacity: usize,
+    power_of_two: bool,
+    cache_line: Option<usize>,
+}
+
+impl FifoBuilder {
+    pub fn new() -> FifoBuilder {
+        FifoBuilder {
+            capacity: DEFAULT_CAPACITY,
+            power_of_two: false,
+            cache_line: None,
+        }
+    }
+
+    /// Number of slots. Must be non-zero.
+    pub fn capacity(mut self, capacity: usize) -> FifoBuilder {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Round the capacity up to the next power of two, so the index math can use a mask.
+    pub fn power_of_two(mut self, round_up: bool) -> FifoBuilder {
+        self.power_of_two = round_up;
+        self
+    }
+
+    /// Cache line size the caller expects the cursors to be padded to. The padding itself
+    /// is fixed at compile time, so this only checks that the build matches.
+    pub fn cache_line(mut self, bytes: usize) -> FifoBuilder {
+        self.cache_line = Some(bytes);
+        self
+    }
+
+    pub fn build<T>(&self) -> Result<Fifo5<T>, FifoError> {
+        if let Some(requested) = self.cache_line
+            && requested != CACHE_LINE
+        {
+            return Err(FifoError::CacheLineMismatch {
+                requested,
+                compiled: CACHE_LINE,
+            });
+        }
+
+        let capacity = match self.capacity {
+            0 => return Err(FifoError::ZeroCapacity),
+            n if self.power_of_two => n
+                .checked_next_power_of_two()
+                .ok_or(FifoError::CapacityOverflow(n))?,
+            n => n,
+        };
+        Ok(Fifo5::new(capacity))
+    }
+}
+
+impl Default for FifoBuilder {
+    fn default() -> FifoBuilder {
+        FifoBuilder::new()
+    }
+}
@@ -0,0 +1,56 @@
+/// Alignment in bytes used by [`CachePadded`].
+///
+/// Defaults to 128: Apple Silicon has 128-byte lines, and x86 parts prefetch adjacent
+/// 64-byte lines in pairs, so 128 keeps two hot fields apart on both. On targets with plain
+/// 64-byte lines (most other ARM cores) enable the `cache_line_64` feature to halve the
+/// padding and the memory footprint of every padded field.
+#[cfg(not(feature = "cache_line_64"))]
+pub const CACHE_LINE: usize = 128;
+#[cfg(feature = "cache_line_64")]
+pub const CACHE_LINE: usize = 64;
+
+/// Wrapper that forces the value onto its own cache line, preventing "False Sharing" where
+/// writes to nearby memory invalidate this cache line.
+#[cfg_attr(not(feature = "cache_line_64"), repr(align(128)))]
+#[cfg_attr(feature = "cache_line_64", repr(align(64)))]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+// `repr(align)` only takes a literal, so make sure the two cfg branches stay in sync.
+const _: () = assert!(core::mem::align_of::<CachePadded<u8>>() == CACHE_LINE);
+
+/// Compile-time check that each listed field of `$ty` sits on cache lines no other listed
+/// field touches. `CachePadded` fields get that from their alignment alone; the check
+/// catches a field being moved into a padded group or the padding being weakened.
+macro_rules! assert_own_lines {
+    ($ty:ty: $($field:ident),+ $(,)?) => {
+        const _: () = {
+            // (first line, last line) of each field, as offsets into the struct. The struct
+            // is aligned to at least a line, so offsets map to lines one to one.
+            let lines = [$({
+                let start = core::mem::offset_of!($ty, $field);
+                let size = $crate::cache_padded::field_size(|s: &$ty| &s.$field);
+                let line = $crate::cache_padded::CACHE_LINE;
+                (start / line, (start + size - 1) / line)
+            }),+];
+            assert!(core::mem::align_of::<$ty>() >= $crate::cache_padded::CACHE_LINE);
+            let mut i = 0;
+            while i < lines.len() {
+                let mut j = i + 1;
+                while j < lines.len() {
+                    assert!(
+                        lines[i].1 < lines[j].0 || lines[j].1 < lines[i].0,
+                        "fields share a cache line"
+                    );
+                    j += 1;
+                }
+                i += 1;
+            }
+        };
+    };
+}
+pub(crate) use assert_own_lines;
+
+// `size_of` for a field, which `offset_of!` has no counterpart for.
+pub(crate) const fn field_size<S, F>(_: fn(&S) -> &F) -> usize {
+    core::mem::size_of::<F>()
+}
@@ -0,0 +1,221 @@
+//! A `std::sync::mpsc`-style facade over [`Fifo4`] for code that wants `send` / `recv` and
+//! disconnect-on-drop instead of a shared `done` flag.
+//!
+//! Unlike std's channels there is exactly one [`Sender`] (it isn't `Clone`), and `send`
+//! never blocks: a full queue hands the item back in the [`SendError`].
+
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::Fifo4;
+use crate::parker::Parker;
+
+struct Shared<T> {
+    queue: Fifo4<T>,
+    sender_alive: AtomicBool,
+    receiver_alive: AtomicBool,
+    // Where `recv` parks; `send` and the disconnect only unpark it while it is waiting.
+    receiver: Parker,
+}
+
+/// Creates a channel backed by a [`Fifo4`] of `capacity` slots.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: Fifo4::new(capacity),
+        sender_alive: AtomicBool::new(true),
+        receiver_alive: AtomicBool::new(true),
+        receiver: Parker::new(),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+            _not_sync: PhantomData,
+        },
+        Receiver {
+            shared,
+            _not_sync: PhantomData,
+        },
+    )
+}
+
+/// The sending half of a [`channel`]. Dropping it disconnects the channel.
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+    // Send but not Sync, like Fifo4's split handles.
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends `item` without blocking. Fails, returning the item, if the queue is full or
+    /// the [`Receiver`] has been dropped.
+    pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+        if !self.shared.receiver_alive.load(Ordering::Acquire) {
+            return Err(SendError(item));
+        }
+        self.shared.queue.push(item).map_err(SendError)?;
+        self.shared.receiver.unpark();
+        Ok(())
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.shared.sender_alive.store(false, Ordering::Release);
+        self.shared.receiver.unpark();
+    }
+}
+
+/// The receiving half of a [`channel`].
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> Receiver<T> {
+    /// Blocks until an item arrives. Once the [`Sender`] is dropped, the remaining items are
+    /// still delivered, then `Err(RecvError)` is returned.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        loop {
+            match self.try_recv() {
+                Ok(item) => return Ok(item),
+                Err(TryRecvError::Disconnected) => return Err(RecvError),
+                Err(TryRecvError::Empty) => {}
+            }
+
+            self.shared.receiver.prepare();
+            match self.try_recv() {
+                Ok(item) => {
+                    self.shared.receiver.cancel();
+                    return Ok(item);
+                }
+                Err(TryRecvError::Disconnected) => {
+                    self.shared.receiver.cancel();
+                    return Err(RecvError);
+                }
+                Err(TryRecvError::Empty) => self.shared.receiver.park(),
+            }
+        }
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        if let Some(item) = self.shared.queue.pop() {
+            return Ok(item);
+        }
+        if self.shared.sender_alive.load(Ordering::Acquire) {
+            return Err(TryRecvError::Empty);
+        }
+        // The sender's last push happens before its disconnect store, so one more pop after
+        // seeing the disconnect is guaranteed to find anything it left behind.
+        self.shared.queue.pop().ok_or(TryRecvError::Disconnected)
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Blocking iterator over the received items; ends once the [`Sender`] is dropped and
+    /// everything it sent has been yielded.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { receiver: self }
+    }
+}
+
+/// Borrowing iterator returned by [`Receiver::iter`].
+pub struct Iter<'a, T> {
+    receiver: &'a Receiver<T>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Owning iterator, so `for item in receiver { ... }` runs until the sender is gone.
+pub struct IntoIter<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl<T> IntoIterator for Receiver<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { receiver: self }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.shared.receiver_alive.store(false, Ordering::Release);
+    }
+}
+
+/// Returned by [`Sender::send`] with the item that could not be sent.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct SendError<T>(pub T);
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sending on a full or disconnected channel")
+    }
+}
+
+impl<T> std::error::Error for SendError<T> {}
+
+/// Returned by [`Receiver::recv`] once the channel is empty and disconnected.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RecvError;
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "receiving on an empty and disconnected channel")
+    }
+}
+
+impl std::error::Error for RecvError {}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "receiving on an empty channel"),
+            TryRecvError::Disconnected => {
+                write!(f, "receiving on an empty and disconnected channel")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TryRecvError {}
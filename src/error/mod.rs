@@ -0,0 +1,120 @@
+use core::fmt;
+
+/// Why a queue could not be constructed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FifoError {
+    /// A queue needs at least one slot.
+    ZeroCapacity,
+    /// The queue type needs more slots than requested, e.g. `Fifo6` needs two to tell a
+    /// full slot from a free one.
+    CapacityTooSmall { capacity: usize, min: usize },
+    /// Rounding the capacity up to a power of two would overflow `usize`.
+    CapacityOverflow(usize),
+    /// Sizing by message size needs a message of at least one byte.
+    ZeroMessageSize,
+    /// A resize asked for fewer slots than there are items queued.
+    BelowLen { capacity: usize, len: usize },
+    /// The requested cache line size differs from the one the crate was compiled with
+    /// (see [`CACHE_LINE`](crate::cache_padded::CACHE_LINE)).
+    CacheLineMismatch { requested: usize, compiled: usize },
+}
+
+impl fmt::Display for FifoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FifoError::ZeroCapacity => write!(f, "capacity must be at least 1"),
+            FifoError::CapacityTooSmall { capacity, min } => {
+                write!(f, "capacity {capacity} is below the minimum of {min}")
+            }
+            FifoError::CapacityOverflow(capacity) => {
+                write!(
+                    f,
+                    "capacity {capacity} has no power of two above it in usize"
+                )
+            }
+            FifoError::ZeroMessageSize => write!(f, "message size must be at least 1 byte"),
+            FifoError::BelowLen { capacity, len } => {
+                write!(f, "capacity {capacity} cannot hold the {len} queued items")
+            }
+            FifoError::CacheLineMismatch {
+                requested,
+                compiled,
+            } => write!(
+                f,
+                "cache line of {requested} bytes requested, but padding is compiled for {compiled} \
+                 (toggle the `cache_line_64` feature)"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for FifoError {}
+
+/// Returned by `Fifo5::pop_timeout` when nothing arrived before the deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopTimeout {
+    Timeout,
+}
+
+impl fmt::Display for PopTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PopTimeout::Timeout => write!(f, "timed out waiting for an item"),
+        }
+    }
+}
+
+impl core::error::Error for PopTimeout {}
+
+/// Returned by `Fifo5::try_push`. The queue is only full for now; retry once the consumer
+/// has caught up.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TryPushError<T> {
+    /// The queue is full. Holds the item that was not pushed.
+    Full(T),
+}
+
+impl<T> TryPushError<T> {
+    /// Gives back the item that could not be pushed.
+    pub fn into_inner(self) -> T {
+        match self {
+            TryPushError::Full(item) => item,
+        }
+    }
+}
+
+// Manual impl so the item doesn't need to be Debug, as with std's mpsc::TrySendError.
+impl<T> fmt::Debug for TryPushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryPushError::Full(_) => write!(f, "Full(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for TryPushError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryPushError::Full(_) => write!(f, "pushing onto a full queue"),
+        }
+    }
+}
+
+impl<T> core::error::Error for TryPushError<T> {}
+
+/// Returned by `Fifo5::try_pop`. The queue is only empty for now; retry once the producer
+/// has pushed more.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryPopError {
+    Empty,
+}
+
+impl fmt::Display for TryPopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryPopError::Empty => write!(f, "popping from an empty queue"),
+        }
+    }
+}
+
+impl core::error::Error for TryPopError {}
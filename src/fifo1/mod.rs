@@ -1,7 +1,10 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "bench")]
 use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::Instant;
+
+#[cfg(feature = "bench")]
+use crate::backoff::SpinBackoff;
 
 pub struct Fifo1<T> {
     capacity: usize,
@@ -11,6 +14,7 @@ pub struct Fifo1<T> {
 }
 
 impl<T> Fifo1<T> {
+    /// `capacity` is used as given; any non-zero size works.
     pub fn new(capacity: usize) -> Fifo1<T> {
         let mut ring = Vec::with_capacity(capacity);
         for _ in 0..capacity {
@@ -24,6 +28,10 @@ impl<T> Fifo1<T> {
         }
     }
 
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     pub fn pop(&mut self) -> Option<T> {
         if self.size() == 0 {
             return None;
@@ -34,19 +42,20 @@ impl<T> Fifo1<T> {
         value
     }
 
-    pub fn push(&mut self, item: T) -> bool {
+    /// Returns `Err(item)` if the queue is full.
+    pub fn push(&mut self, item: T) -> Result<(), T> {
         if self.is_full() {
-            return false;
+            return Err(item);
         };
         let loc = self.push_cursor % self.capacity;
         self.ring[loc] = Some(item);
         self.push_cursor += 1;
-        return true;
+        Ok(())
     }
 
     pub fn size(&self) -> usize {
         // In a circular buffer where push and pop are monotonic, push >= pop is invariant.
-        assert!(self.push_cursor >= self.pop_cursor);
+        debug_assert!(self.push_cursor >= self.pop_cursor);
         self.push_cursor - self.pop_cursor
     }
 
@@ -59,78 +68,31 @@ impl<T> Fifo1<T> {
     }
 }
 
+impl<T: fmt::Debug> fmt::Debug for Fifo1<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Pushes and pops take `&mut self`, so nothing can change the slots while we read them.
+        let items: Vec<&T> = (self.pop_cursor..self.push_cursor)
+            .filter_map(|pos| self.ring[pos % self.capacity].as_ref())
+            .collect();
+        f.debug_struct("Fifo1")
+            .field("capacity", &self.capacity)
+            .field("len", &self.size())
+            .field("items", &items)
+            .finish()
+    }
+}
+
+#[cfg(feature = "bench")]
 pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
     let queue = Arc::new(Mutex::new(Fifo1::<usize>::new(capacity)));
-    let done = Arc::new(AtomicBool::new(false));
     let queue_consumer = queue.clone();
-    let done_consumer = done.clone();
-
-    // Consumer Thread
-    let consumer = thread::spawn(move || {
-        let mut expected = 0;
-        while !done_consumer.load(Ordering::Acquire) {
-            loop {
-                // Try to pop; if empty, we spin (or yield)
-                let mut guard = queue_consumer.lock().unwrap();
-                if let Some(val) = guard.pop() {
-                    assert_eq!(val, expected, "Consumer received out-of-order value");
-                    expected += 1;
-                    break;
-                }
-                drop(guard);
-                // In a real lock-free queue, we might cpu_relax here.
-                // With a Mutex, we rely on unlock to yield.
-            }
-        }
-
-        // Final drain after producer is done
-        loop {
-            let mut guard = queue_consumer.lock().unwrap();
-            if let Some(val) = guard.pop() {
-                assert_eq!(val, expected, "Drain received out-of-order value");
-                expected += 1;
-            } else {
-                break;
-            }
-        }
-    });
-
-    let start = Instant::now();
-
-    // Producer (Main Thread)
-    for i in 0..iters {
-        loop {
-            let mut guard = queue.lock().unwrap();
-            if guard.push(i) {
-                break;
-            }
-            drop(guard);
-            // Spin if full
-        }
-    }
-
-    // Signal consumer to stop waiting for new data once empty
-    // But we need to make sure the consumer knows we are done purely producing.
-    // The consumer loop condition !done might exit early if we set it here and the queue is not empty yet?
-    // No, the consumer loop checks !done. If done is true, it exits the main loop.
-    // However, the queue might still have items. The "Final drain" handles that.
-    // Crucially, we must ensure all items pushed are consumed.
-    // The current logic:
-    // 1. Producer pushes all items.
-    // 2. Producer sets `done = true`.
-    // 3. Consumer sees `done = true`, exits main loop.
-    // 4. Consumer enters drain loop.
-    // Race condition: Producer sets done=true. Consumer checks done=true BEFORE popping the last item in the main loop.
-    // Consumer exits main loop. Consumer enters drain loop. Drain loop pops remaining items.
-    // This seems correct.
-
-    done.store(true, Ordering::Release);
-
-    consumer.join().unwrap();
-
-    let duration = start.elapsed();
-    let secs = duration.as_secs_f64();
-    println!("Time: {:.4}s, Iters: {}", secs, iters);
 
-    (iters as f64) / secs
+    // Each side holds the lock for a single attempt only, so the consumer can't starve the
+    // producer (or vice versa) by spinning on an empty queue with the lock held.
+    crate::bench::bench_fn::<_, _, SpinBackoff>(
+        "Fifo1",
+        iters,
+        move |i| queue.lock().unwrap().push(i),
+        move || queue_consumer.lock().unwrap().pop(),
+    )
 }
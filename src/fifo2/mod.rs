@@ -1,8 +1,13 @@
-use std::cell::UnsafeCell;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "bench")]
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::thread;
-use std::time::Instant;
+
+use crate::queue::debug_queue;
+use crate::sync::{AtomicUsize, Ordering, UnsafeCell};
+
+#[cfg(feature = "serde")]
+mod serde;
 
 /// A Lock-Free SPSC FIFO queue for `usize` values.
 /// This implementation is 100% SAFE Rust (no `unsafe` blocks) because it uses
@@ -24,7 +29,15 @@ unsafe impl<T: Send> Sync for Fifo2<T> {}
 unsafe impl<T: Send> Send for Fifo2<T> {}
 
 impl<T> Fifo2<T> {
+    /// Takes `capacity` as is, without rounding. Slots are found with `%`, so any size
+    /// works, though only a power of two survives the cursors wrapping at `usize::MAX`.
     pub fn new(capacity: usize) -> Fifo2<T> {
+        Fifo2::with_start_cursor(capacity, 0)
+    }
+
+    /// Starts both cursors at `start`, for tests that cross the `usize::MAX` wrap.
+    #[doc(hidden)]
+    pub fn with_start_cursor(capacity: usize, start: usize) -> Fifo2<T> {
         let mut ring = Vec::with_capacity(capacity);
         for _ in 0..capacity {
             ring.push(UnsafeCell::new(None));
@@ -32,11 +45,80 @@ impl<T> Fifo2<T> {
         Fifo2 {
             capacity,
             ring,
-            push_cursor: AtomicUsize::new(0),
-            pop_cursor: AtomicUsize::new(0),
+            push_cursor: AtomicUsize::new(start),
+            pop_cursor: AtomicUsize::new(start),
         }
     }
 
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns a reference to the front element without consuming it, or `None` if empty.
+    ///
+    /// Consumer-side only, like `pop`. The producer never writes to a slot between
+    /// `pop_cursor` and `push_cursor`, so the returned reference stays valid until the
+    /// consumer itself pops; the borrow checker cannot enforce that with `&self`, so
+    /// calling `peek` from any thread other than the single consumer is a data race.
+    pub fn peek(&self) -> Option<&T> {
+        self.peek_at(0)
+    }
+
+    /// `VecDeque`-style name for [`peek`](Self::peek), with the same consumer-only rule.
+    #[inline]
+    pub fn front(&self) -> Option<&T> {
+        self.peek()
+    }
+
+    /// Returns a reference to the element `offset` places behind the front (0 is the front),
+    /// or `None` if fewer than `offset + 1` items are queued. Nothing is popped, so together
+    /// with [`size`](Self::size) this gives bounded lookahead.
+    ///
+    /// Same rules as [`peek`](Self::peek): consumer thread only, and the reference is valid
+    /// until the consumer pops.
+    pub fn peek_at(&self, offset: usize) -> Option<&T> {
+        let push_val = self.push_cursor.load(Ordering::Acquire);
+        let pop_val = self.pop_cursor.load(Ordering::Relaxed);
+
+        if offset >= push_val.wrapping_sub(pop_val) {
+            return None;
+        }
+
+        // SAFETY: Same reasoning as `pop`: every slot between the cursors is published and
+        // only the consumer touches it until `pop_cursor` advances past it.
+        self.ring[pop_val.wrapping_add(offset) % self.capacity]
+            .with(|slot| unsafe { (*slot).as_ref() })
+    }
+
+    /// Clones the queued items, front first, into a `Vec` without popping them. Meant for
+    /// debugging and tests.
+    ///
+    /// Call it with the producer quiesced (or from the consumer thread with no push in
+    /// flight); a concurrent push or pop would race with the clone.
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.queued().cloned().collect()
+    }
+
+    /// The queued items between the cursors, front first. Same quiescence rules as `snapshot`.
+    pub(crate) fn queued(&self) -> impl Iterator<Item = &T> + Clone {
+        self.slots()
+            .map(|slot| slot.expect("slots between the cursors are occupied"))
+    }
+
+    // The slots between the cursors, front first; `None` where a racing pop has already
+    // emptied one.
+    fn slots(&self) -> impl Iterator<Item = Option<&T>> + Clone {
+        let pop_val = self.pop_cursor.load(Ordering::Acquire);
+        let push_val = self.push_cursor.load(Ordering::Acquire);
+        (0..push_val.wrapping_sub(pop_val)).map(move |i| {
+            self.ring[pop_val.wrapping_add(i) % self.capacity]
+                .with(|slot| unsafe { (*slot).as_ref() })
+        })
+    }
+
     pub fn pop(&self) -> Option<T> {
         // Load push_cursor with Acquire to ensure we see the data writes from the producer
         let push_val = self.push_cursor.load(Ordering::Acquire);
@@ -47,93 +129,91 @@ impl<T> Fifo2<T> {
         }
 
         let loc = pop_val % self.capacity;
-        // SAFETY: We checked that push_val > pop_val, so data is available.
+        // SAFETY: We checked that push_val != pop_val, so data is available.
         // Only one consumer accesses ring[loc] at this time.
         // We take the value out, leaving None.
-        let value = unsafe { (*self.ring[loc].get()).take() };
+        let value = self.ring[loc].with_mut(|slot| unsafe { (*slot).take() });
 
         // Release the slot *after* reading
-        self.pop_cursor.store(pop_val + 1, Ordering::Release);
+        self.pop_cursor
+            .store(pop_val.wrapping_add(1), Ordering::Release);
         value
     }
 
-    pub fn push(&self, item: T) -> bool {
+    /// Pops the front element only if `pred` returns `true` for it; otherwise leaves the
+    /// queue untouched and returns `None` (as it also does when the queue is empty).
+    ///
+    /// Consumer thread only. The producer never touches the front slot, so the element
+    /// `pred` sees is the one that gets popped.
+    pub fn pop_if<F: FnOnce(&T) -> bool>(&self, pred: F) -> Option<T> {
+        if !pred(self.peek()?) {
+            return None;
+        }
+        self.pop()
+    }
+
+    /// Swaps `new` into the front slot and returns the element it replaces, without moving
+    /// either cursor: the next `pop` yields `new`. Returns `None` if the queue is empty, in
+    /// which case `new` is dropped rather than stored.
+    ///
+    /// Consumer thread only. Like `pop`, it ends any reference obtained from `peek`.
+    pub fn front_replace(&self, new: T) -> Option<T> {
+        let push_val = self.push_cursor.load(Ordering::Acquire);
+        let pop_val = self.pop_cursor.load(Ordering::Relaxed);
+
+        if push_val == pop_val {
+            return None;
+        }
+
+        let loc = pop_val % self.capacity;
+        // SAFETY: As in `pop`, the slot is published and only the consumer touches it until
+        // `pop_cursor` moves past it, which this call never does.
+        self.ring[loc].with_mut(|slot| unsafe { (*slot).replace(new) })
+    }
+
+    /// Pushes `item` onto the back of the queue.
+    ///
+    /// Returns `Err(item)` when the queue is full so the caller keeps ownership of the
+    /// value; this means "queue full, try again" (same convention as crossbeam's
+    /// `ArrayQueue::push`).
+    pub fn push(&self, item: T) -> Result<(), T> {
         let push_val = self.push_cursor.load(Ordering::Relaxed); // We own push_cursor
         let pop_val = self.pop_cursor.load(Ordering::Acquire); // Read consumer's progress
 
-        // size = push - pop. If size == capacity, full.
-        if push_val >= pop_val + self.capacity {
-            return false;
+        // size = push - pop (mod 2^usize::BITS, so it survives the cursors wrapping).
+        // If size == capacity, full.
+        if push_val.wrapping_sub(pop_val) >= self.capacity {
+            return Err(item);
         }
 
         let loc = push_val % self.capacity;
         // SAFETY: We checked space is available. Only one producer accesses this slot.
-        unsafe { *self.ring[loc].get() = Some(item) };
+        self.ring[loc].with_mut(|slot| unsafe { *slot = Some(item) });
 
         // Commit the push *after* writing data
-        self.push_cursor.store(push_val + 1, Ordering::Release);
-        return true;
+        self.push_cursor
+            .store(push_val.wrapping_add(1), Ordering::Release);
+        Ok(())
     }
 
     pub fn size(&self) -> usize {
-        let push_val = self.push_cursor.load(Ordering::Relaxed);
-        let pop_val = self.pop_cursor.load(Ordering::Relaxed);
-
-        if push_val < pop_val {
-            return 0; // Should not happen in strict SPSC
-        }
-        push_val - pop_val
+        // Pop first, with Acquire, so the push cursor we read next is at least as new and
+        // the wrapping difference can't go "negative".
+        let pop_val = self.pop_cursor.load(Ordering::Acquire);
+        let push_val = self.push_cursor.load(Ordering::Acquire);
+        push_val.wrapping_sub(pop_val).min(self.capacity)
     }
 }
 
-pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
-    let queue = Arc::new(Fifo2::<usize>::new(capacity));
-    let done = Arc::new(AtomicBool::new(false));
-    let queue_consumer = queue.clone();
-    let done_consumer = done.clone();
-
-    // Consumer Thread
-    let consumer = thread::spawn(move || {
-        let mut expected = 0;
-
-        // Loop until done signal AND queue is empty
-        loop {
-            if let Some(val) = queue_consumer.pop() {
-                assert_eq!(val, expected, "Consumer received out-of-order value");
-                expected += 1;
-            } else {
-                // Queue is empty. Check if we are done.
-                if done_consumer.load(Ordering::Acquire) {
-                    // Double check queue is empty to avoid race where item was pushed
-                    // right before we checked done.
-                    if queue_consumer.pop().is_none() {
-                        break;
-                    }
-                } else {
-                    std::hint::spin_loop();
-                }
-            }
-        }
-    });
-
-    let start = Instant::now();
-
-    // Producer (Main Thread)
-    for i in 0..iters {
-        loop {
-            if queue.push(i) {
-                break;
-            }
-            std::hint::spin_loop();
-        }
+impl<T> fmt::Debug for Fifo2<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pop_val = self.pop_cursor.load(Ordering::Acquire);
+        let push_val = self.push_cursor.load(Ordering::Acquire);
+        debug_queue(f, "Fifo2", Some(self.capacity), pop_val, push_val)
     }
+}
 
-    done.store(true, Ordering::Release);
-    consumer.join().unwrap();
-
-    let duration = start.elapsed();
-    let secs = duration.as_secs_f64();
-    println!("Fifo2 Time: {:.4}s, Iters: {}", secs, iters);
-
-    (iters as f64) / secs
+#[cfg(feature = "bench")]
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    crate::bench::bench("Fifo2", Arc::new(Fifo2::<usize>::new(capacity)), iters)
 }
@@ -0,0 +1,30 @@
+use alloc::vec::Vec;
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use super::Fifo2;
+
+/// Serializes the queued items, front first, as a sequence. Empty ring slots are skipped.
+///
+/// The queue must be quiesced: an item popped or pushed concurrently would be read while
+/// the other side is moving it.
+impl<T: Serialize> Serialize for Fifo2<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.queued())
+    }
+}
+
+/// Rebuilds a queue from a sequence, with the item count rounded up to a power of two as
+/// the capacity.
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Fifo2<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Fifo2<T>, D::Error> {
+        let items = Vec::<T>::deserialize(deserializer)?;
+        let queue = Fifo2::new(items.len().max(1).next_power_of_two());
+        for item in items {
+            if queue.push(item).is_err() {
+                unreachable!("capacity covers every item");
+            }
+        }
+        Ok(queue)
+    }
+}
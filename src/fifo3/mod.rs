@@ -1,21 +1,19 @@
-use std::cell::UnsafeCell;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "bench")]
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::thread;
-use std::time::Instant;
 
-/// Wrapper to force alignment to 128 bytes (common cache line size is 64, but 128 is safer).
-/// This ensures that the wrapped value sits on its own cache line,
-/// preventing "False Sharing" where writes to nearby memory invalidates this cache line.
-#[repr(align(128))]
-struct CachePadded<T>(T);
+use crate::cache_padded::CachePadded;
+use crate::queue::debug_queue;
 
 pub struct Fifo3<T> {
     capacity: usize,
     ring: Vec<UnsafeCell<Option<T>>>,
     // PADDING HERE:
     // push_cursor and pop_cursor are now wrapped in CachePadded.
-    // They will be at least 128 bytes apart.
+    // They will be at least `CACHE_LINE` bytes apart.
     push_cursor: CachePadded<AtomicUsize>,
     pop_cursor: CachePadded<AtomicUsize>,
 }
@@ -24,7 +22,14 @@ unsafe impl<T: Send> Sync for Fifo3<T> {}
 unsafe impl<T: Send> Send for Fifo3<T> {}
 
 impl<T> Fifo3<T> {
+    /// Takes `capacity` as is, like [`Fifo2::new`](crate::Fifo2::new).
     pub fn new(capacity: usize) -> Fifo3<T> {
+        Fifo3::with_start_cursor(capacity, 0)
+    }
+
+    /// Starts both cursors at `start`, for tests that cross the `usize::MAX` wrap.
+    #[doc(hidden)]
+    pub fn with_start_cursor(capacity: usize, start: usize) -> Fifo3<T> {
         let mut ring = Vec::with_capacity(capacity);
         for _ in 0..capacity {
             ring.push(UnsafeCell::new(None));
@@ -32,11 +37,15 @@ impl<T> Fifo3<T> {
         Fifo3 {
             capacity,
             ring,
-            push_cursor: CachePadded(AtomicUsize::new(0)),
-            pop_cursor: CachePadded(AtomicUsize::new(0)),
+            push_cursor: CachePadded(AtomicUsize::new(start)),
+            pop_cursor: CachePadded(AtomicUsize::new(start)),
         }
     }
 
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     pub fn pop(&self) -> Option<T> {
         // Access inner via .0
         let push_val = self.push_cursor.0.load(Ordering::Acquire);
@@ -49,67 +58,54 @@ impl<T> Fifo3<T> {
         let loc = pop_val % self.capacity;
         let value = unsafe { (*self.ring[loc].get()).take() };
 
-        self.pop_cursor.0.store(pop_val + 1, Ordering::Release);
+        self.pop_cursor
+            .0
+            .store(pop_val.wrapping_add(1), Ordering::Release);
         value
     }
 
-    pub fn push(&self, item: T) -> bool {
+    /// Returns a reference to the front element without popping it, like `VecDeque::front`.
+    ///
+    /// Consumer thread only: the producer doesn't write a slot between the cursors, so the
+    /// reference stays valid until the consumer itself pops.
+    pub fn front(&self) -> Option<&T> {
+        let push_val = self.push_cursor.0.load(Ordering::Acquire);
+        let pop_val = self.pop_cursor.0.load(Ordering::Relaxed);
+
+        if push_val == pop_val {
+            return None;
+        }
+        unsafe { (*self.ring[pop_val % self.capacity].get()).as_ref() }
+    }
+
+    /// Returns `Err(item)` if the queue is full.
+    pub fn push(&self, item: T) -> Result<(), T> {
         let push_val = self.push_cursor.0.load(Ordering::Relaxed);
         let pop_val = self.pop_cursor.0.load(Ordering::Acquire);
 
-        if push_val >= pop_val + self.capacity {
-            return false;
+        if push_val.wrapping_sub(pop_val) >= self.capacity {
+            return Err(item);
         }
 
         let loc = push_val % self.capacity;
         unsafe { *self.ring[loc].get() = Some(item) };
 
-        self.push_cursor.0.store(push_val + 1, Ordering::Release);
-        return true;
+        self.push_cursor
+            .0
+            .store(push_val.wrapping_add(1), Ordering::Release);
+        Ok(())
     }
 }
 
-pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
-    let queue = Arc::new(Fifo3::<usize>::new(capacity));
-    let done = Arc::new(AtomicBool::new(false));
-    let queue_consumer = queue.clone();
-    let done_consumer = done.clone();
-
-    let consumer = thread::spawn(move || {
-        let mut expected = 0;
-        loop {
-            if let Some(val) = queue_consumer.pop() {
-                assert_eq!(val, expected);
-                expected += 1;
-            } else {
-                if done_consumer.load(Ordering::Acquire) {
-                    if queue_consumer.pop().is_none() {
-                        break;
-                    }
-                } else {
-                    std::hint::spin_loop();
-                }
-            }
-        }
-    });
-
-    let start = Instant::now();
-
-    for i in 0..iters {
-        loop {
-            if queue.push(i) {
-                break;
-            }
-            std::hint::spin_loop();
-        }
+impl<T> fmt::Debug for Fifo3<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pop_val = self.pop_cursor.0.load(Ordering::Acquire);
+        let push_val = self.push_cursor.0.load(Ordering::Acquire);
+        debug_queue(f, "Fifo3", Some(self.capacity), pop_val, push_val)
     }
+}
 
-    done.store(true, Ordering::Release);
-    consumer.join().unwrap();
-
-    let duration = start.elapsed();
-    let secs = duration.as_secs_f64();
-    println!("Fifo3 Time: {:.4}s, Iters: {}", secs, iters);
-
-    (iters as f64) / secs
+#[cfg(feature = "bench")]
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    crate::bench::bench("Fifo3", Arc::new(Fifo3::<usize>::new(capacity)), iters)
 }
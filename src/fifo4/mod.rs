@@ -1,20 +1,43 @@
-use std::cell::UnsafeCell;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread;
-use std::time::Instant;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use core::marker::PhantomData;
+#[cfg(feature = "progress")]
+use core::sync::atomic::AtomicU64;
 
-/// Wrapper to force alignment to 128 bytes (Apple Silicon / standard cache line).
-#[repr(align(128))]
-struct CachePadded<T>(T);
+use crate::cache_padded::{CachePadded, assert_own_lines};
+use crate::queue::debug_queue;
+#[cfg(feature = "stats")]
+use crate::stats::{FifoStats, SideCounters};
+use crate::sync::{AtomicUsize, UnsafeCell, order};
+
+mod round_robin;
+
+pub use round_robin::RoundRobin;
 
 /// Fields exclusive to the Producer thread.
 struct ProducerFields {
     push_cursor: AtomicUsize,
+    // Where the producer writes next. Equal to push_cursor except while `push_deferred`
+    // holds written but unpublished items.
+    local_push: UnsafeCell<usize>,
     // A local copy of the consumer's pop cursor.
     // This allows the producer to check for space *without* reading the shared atomic
     // pop_cursor variables (which causes cache coherence traffic) until necessary.
     cached_pop: UnsafeCell<usize>,
+    // Last answer of `backpressure_state`: true while engaged.
+    backpressure: UnsafeCell<bool>,
+}
+
+/// Answer of [`Fifo4::backpressure_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backpressure {
+    /// The queue reached the high watermark and hasn't drained to the low one yet: the
+    /// producer should hold off.
+    Engaged,
+    /// Produce as usual.
+    Relieved,
 }
 
 /// Fields exclusive to the Consumer thread.
@@ -26,131 +49,588 @@ struct ConsumerFields {
 
 pub struct Fifo4<T> {
     capacity: usize,
+    // `push_deferred` publishes once this many items are pending; see `with_flush_threshold`.
+    flush_threshold: usize,
+    // (high, low) for `backpressure_state`, see `set_watermarks`.
+    watermarks: (usize, usize),
+    // Live `ProducerGuard`s, see `producers_alive`. Only written when a guard is created or
+    // dropped, so it can share the read-mostly line with `capacity`.
+    producers: AtomicUsize,
     ring: Vec<UnsafeCell<Option<T>>>,
     // Grouping mutable fields that are accessed together to maximize cache locality
     // and minimize False Sharing between producer and consumer.
     producer: CachePadded<ProducerFields>,
     consumer: CachePadded<ConsumerFields>,
+    // Bumped once per successful push / pop for `progress`. Padded separately so a watchdog
+    // polling them doesn't pull the cursor lines away from the two sides.
+    #[cfg(feature = "progress")]
+    producer_epoch: CachePadded<AtomicU64>,
+    #[cfg(feature = "progress")]
+    consumer_epoch: CachePadded<AtomicU64>,
+    // Kept off the cursor lines; see `stats`.
+    #[cfg(feature = "stats")]
+    push_stats: CachePadded<SideCounters>,
+    #[cfg(feature = "stats")]
+    pop_stats: CachePadded<SideCounters>,
+    // Set while a push is writing its slot, so debug builds can catch two at once.
+    #[cfg(all(debug_assertions, feature = "std", not(loom)))]
+    pushing: OverlapCheck,
 }
 
+// As in Fifo5: the read-mostly ring handle, the two cursor groups and the epochs each on
+// lines of their own.
+#[cfg(feature = "progress")]
+assert_own_lines!(Fifo4<u8>: ring, producer, consumer, producer_epoch, consumer_epoch);
+#[cfg(not(feature = "progress"))]
+assert_own_lines!(Fifo4<u8>: ring, producer, consumer);
+
 // SAFETY: SPSC only.
 unsafe impl<T: Send> Sync for Fifo4<T> {}
 unsafe impl<T: Send> Send for Fifo4<T> {}
 
 impl<T> Fifo4<T> {
+    /// Takes `capacity` as is, like [`Fifo2::new`](crate::Fifo2::new).
     pub fn new(capacity: usize) -> Fifo4<T> {
+        Fifo4::with_start_cursor(capacity, 0)
+    }
+
+    /// Starts the cursors and their shadow copies at `start`, for tests that cross the
+    /// `usize::MAX` wrap.
+    #[doc(hidden)]
+    pub fn with_start_cursor(capacity: usize, start: usize) -> Fifo4<T> {
         let mut ring = Vec::with_capacity(capacity);
         for _ in 0..capacity {
             ring.push(UnsafeCell::new(None));
         }
         Fifo4 {
             capacity,
+            flush_threshold: 1,
+            watermarks: (capacity, capacity),
+            producers: AtomicUsize::new(0),
             ring,
             producer: CachePadded(ProducerFields {
-                push_cursor: AtomicUsize::new(0),
-                cached_pop: UnsafeCell::new(0),
+                push_cursor: AtomicUsize::new(start),
+                local_push: UnsafeCell::new(start),
+                cached_pop: UnsafeCell::new(start),
+                backpressure: UnsafeCell::new(false),
             }),
             consumer: CachePadded(ConsumerFields {
-                pop_cursor: AtomicUsize::new(0),
-                cached_push: UnsafeCell::new(0),
+                pop_cursor: AtomicUsize::new(start),
+                cached_push: UnsafeCell::new(start),
             }),
+            #[cfg(feature = "progress")]
+            producer_epoch: CachePadded(AtomicU64::new(0)),
+            #[cfg(feature = "progress")]
+            consumer_epoch: CachePadded(AtomicU64::new(0)),
+            #[cfg(feature = "stats")]
+            push_stats: CachePadded(SideCounters::default()),
+            #[cfg(feature = "stats")]
+            pop_stats: CachePadded(SideCounters::default()),
+            #[cfg(all(debug_assertions, feature = "std", not(loom)))]
+            pushing: OverlapCheck::new(),
         }
     }
 
+    /// Makes [`push_deferred`](Self::push_deferred) publish once every `threshold` items
+    /// instead of after each one. The default of 1 publishes every push; 0 is treated as 1.
+    ///
+    /// A larger threshold saves `Release` stores on the shared `push_cursor` (and the cache
+    /// line transfers they cause), at the cost of latency: the consumer sees nothing of a
+    /// batch until it is published, so a producer that stops mid-batch must call
+    /// [`flush`](Self::flush).
+    pub fn with_flush_threshold(mut self, threshold: usize) -> Fifo4<T> {
+        self.flush_threshold = threshold.max(1);
+        self
+    }
+
+    /// Sets the watermarks for [`backpressure_state`](Self::backpressure_state): it engages
+    /// once `high` items are queued and stays engaged until the queue has drained to `low`.
+    /// The gap between the two keeps a producer hovering around one level from toggling on
+    /// every push and pop. The default is `high = low = capacity`, engaged only while full.
+    ///
+    /// Panics if `low > high`.
+    pub fn set_watermarks(&mut self, high: usize, low: usize) {
+        assert!(
+            low <= high,
+            "Fifo4: low watermark {low} is above the high one {high}"
+        );
+        self.watermarks = (high, low);
+    }
+
+    /// Whether the producer should throttle, with hysteresis between the watermarks set by
+    /// [`set_watermarks`](Self::set_watermarks). Producer thread only; items written by
+    /// `push_deferred` but not yet flushed count as queued.
+    ///
+    /// Cheap to poll: while relieved and the cached pop cursor already puts the queue below
+    /// `high`, it doesn't touch the consumer's cache line at all.
+    pub fn backpressure_state(&self) -> Backpressure {
+        let producer = &self.producer.0;
+        let (high, low) = self.watermarks;
+        let push_val = producer.local_push.with(|p| unsafe { *p });
+        let engaged = producer.backpressure.with(|p| unsafe { *p });
+
+        // The cached pop cursor lags, so this is an upper bound on the length.
+        let cached_len = push_val.wrapping_sub(producer.cached_pop.with(|p| unsafe { *p }));
+        if !engaged && cached_len < high {
+            return Backpressure::Relieved;
+        }
+
+        let actual_pop = self.consumer.0.pop_cursor.load(order::ACQUIRE);
+        producer.cached_pop.with_mut(|p| unsafe { *p = actual_pop });
+        let len = push_val.wrapping_sub(actual_pop);
+        let engaged = if engaged { len > low } else { len >= high };
+        producer.backpressure.with_mut(|p| unsafe { *p = engaged });
+        if engaged {
+            Backpressure::Engaged
+        } else {
+            Backpressure::Relieved
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     pub fn pop(&self) -> Option<T> {
         let consumer = &self.consumer.0;
-        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+        let pop_val = consumer.pop_cursor.load(order::RELAXED);
 
         // Read our cached view of the producer
         // Safe because only Consumer calls pop, so only Consumer mutates cached_push
-        let mut cached_push = unsafe { *consumer.cached_push.get() };
+        let mut cached_push = consumer.cached_push.with(|p| unsafe { *p });
 
         // If it looks empty, check the REAL push cursor
-        if pop_val >= cached_push {
-            let actual_push = self.producer.0.push_cursor.load(Ordering::Acquire);
+        if pop_val == cached_push {
+            let actual_push = self.producer.0.push_cursor.load(order::ACQUIRE);
             // Update our cache
-            unsafe { *consumer.cached_push.get() = actual_push };
+            consumer
+                .cached_push
+                .with_mut(|p| unsafe { *p = actual_push });
             cached_push = actual_push;
 
-            if pop_val >= cached_push {
+            if pop_val == cached_push {
+                #[cfg(feature = "stats")]
+                self.pop_stats.0.record_failed();
                 return None; // Really empty
             }
         }
+        debug_assert!(
+            cached_push.wrapping_sub(pop_val) <= self.capacity,
+            "Fifo4: push cursor {cached_push} is not within capacity {} of pop cursor {pop_val}; \
+             is the queue shared by more than one producer or consumer?",
+            self.capacity,
+        );
 
         let loc = pop_val % self.capacity;
-        let value = unsafe { (*self.ring[loc].get()).take() };
+        let value = self.ring[loc].with_mut(|slot| unsafe { (*slot).take() });
 
-        consumer.pop_cursor.store(pop_val + 1, Ordering::Release);
+        consumer
+            .pop_cursor
+            .store(pop_val.wrapping_add(1), order::RELEASE);
+        #[cfg(feature = "progress")]
+        bump(&self.consumer_epoch.0);
+        #[cfg(feature = "stats")]
+        self.pop_stats.0.record_done();
         value
     }
 
-    pub fn push(&self, item: T) -> bool {
+    /// Returns a reference to the front element without popping it, like `VecDeque::front`.
+    /// Items left pending by [`push_deferred`](Self::push_deferred) aren't visible yet.
+    ///
+    /// Consumer thread only; the reference is valid until the consumer pops (or a
+    /// [`force_push`](Self::force_push) evicts the item).
+    pub fn front(&self) -> Option<&T> {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(order::RELAXED);
+
+        // Same shadow-cursor check as `pop`.
+        if pop_val == consumer.cached_push.with(|p| unsafe { *p }) {
+            let actual_push = self.producer.0.push_cursor.load(order::ACQUIRE);
+            consumer
+                .cached_push
+                .with_mut(|p| unsafe { *p = actual_push });
+            if pop_val == actual_push {
+                return None;
+            }
+        }
+        self.ring[pop_val % self.capacity].with(|slot| unsafe { (*slot).as_ref() })
+    }
+
+    /// Returns the item back as `Err(item)` when the ring is really full. Publishes right
+    /// away, together with anything left pending by [`push_deferred`](Self::push_deferred).
+    ///
+    /// Debug builds check the cursors on every push and pop, and (with `std`) panic if two
+    /// pushes overlap, catching a second producer. Handing the producer role from one
+    /// thread to another between pushes is fine.
+    pub fn push(&self, item: T) -> Result<(), T> {
+        let push_val = self.write(item)?;
+        self.publish(push_val.wrapping_add(1));
+        Ok(())
+    }
+
+    /// Like [`push`](Self::push), but the item only becomes visible to the consumer once
+    /// the flush threshold (see [`with_flush_threshold`](Self::with_flush_threshold)) is
+    /// reached or [`flush`](Self::flush) is called. Producer thread only.
+    ///
+    /// A full ring publishes whatever is pending before returning `Err(item)`, so the
+    /// consumer can always drain it and make room.
+    pub fn push_deferred(&self, item: T) -> Result<(), T> {
+        let producer = &self.producer.0;
+        let push_val = match self.write(item) {
+            Ok(push_val) => push_val,
+            Err(item) => {
+                self.flush();
+                return Err(item);
+            }
+        };
+        let published = producer.push_cursor.load(order::RELAXED);
+        if push_val.wrapping_add(1).wrapping_sub(published) >= self.flush_threshold {
+            self.publish(push_val.wrapping_add(1));
+        }
+        Ok(())
+    }
+
+    /// Publishes the items written by [`push_deferred`](Self::push_deferred) so far.
+    /// Producer thread only; a no-op if nothing is pending.
+    pub fn flush(&self) {
+        let producer = &self.producer.0;
+        let local = producer.local_push.with(|p| unsafe { *p });
+        if producer.push_cursor.load(order::RELAXED) != local {
+            self.publish(local);
+        }
+    }
+
+    // Producer side of a push up to, but not including, publishing: finds room and writes
+    // the slot at `local_push`. Returns the position written.
+    fn write(&self, item: T) -> Result<usize, T> {
+        #[cfg(all(debug_assertions, feature = "std", not(loom)))]
+        let _pushing = self.pushing.enter();
         let producer = &self.producer.0;
-        let push_val = producer.push_cursor.load(Ordering::Relaxed);
+        let push_val = producer.local_push.with(|p| unsafe { *p });
 
         // Read our cached view of the consumer
-        let mut cached_pop = unsafe { *producer.cached_pop.get() };
+        let mut cached_pop = producer.cached_pop.with(|p| unsafe { *p });
 
         // If it looks full, check the REAL pop cursor
-        if push_val >= cached_pop + self.capacity {
-            let actual_pop = self.consumer.0.pop_cursor.load(Ordering::Acquire);
-            unsafe { *producer.cached_pop.get() = actual_pop };
+        if push_val.wrapping_sub(cached_pop) >= self.capacity {
+            let actual_pop = self.consumer.0.pop_cursor.load(order::ACQUIRE);
+            producer.cached_pop.with_mut(|p| unsafe { *p = actual_pop });
             cached_pop = actual_pop;
 
-            if push_val >= cached_pop + self.capacity {
-                return false; // Really full
+            if push_val.wrapping_sub(cached_pop) >= self.capacity {
+                debug_assert!(
+                    push_val.wrapping_sub(cached_pop) == self.capacity,
+                    "Fifo4: push cursor {push_val} is not within capacity {} of pop cursor \
+                     {cached_pop}; is the queue shared by more than one producer or consumer?",
+                    self.capacity,
+                );
+                #[cfg(feature = "stats")]
+                self.push_stats.0.record_failed();
+                return Err(item); // Really full
             }
         }
 
         let loc = push_val % self.capacity;
-        unsafe { *self.ring[loc].get() = Some(item) };
+        self.ring[loc].with_mut(|slot| unsafe { *slot = Some(item) });
+        producer
+            .local_push
+            .with_mut(|p| unsafe { *p = push_val.wrapping_add(1) });
 
-        producer.push_cursor.store(push_val + 1, Ordering::Release);
-        return true;
+        #[cfg(feature = "progress")]
+        bump(&self.producer_epoch.0);
+        #[cfg(feature = "stats")]
+        self.push_stats.0.record_done();
+        Ok(push_val)
     }
-}
 
-pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
-    let queue = Arc::new(Fifo4::<usize>::new(capacity));
-    let done = Arc::new(AtomicBool::new(false));
-    let queue_consumer = queue.clone();
-    let done_consumer = done.clone();
-
-    let consumer = thread::spawn(move || {
-        let mut expected = 0;
-        loop {
-            if let Some(val) = queue_consumer.pop() {
-                assert_eq!(val, expected);
-                expected += 1;
-            } else {
-                if done_consumer.load(Ordering::Acquire) {
-                    if queue_consumer.pop().is_none() {
-                        break;
-                    }
-                } else {
-                    std::hint::spin_loop();
-                }
-            }
+    // Makes every slot before `to` visible to the consumer.
+    fn publish(&self, to: usize) {
+        self.producer.0.push_cursor.store(to, order::RELEASE);
+    }
+
+    /// Pushes `item`, evicting the oldest element if the queue is full, so the newest
+    /// values always make it in (e.g. telemetry). Returns the evicted element, if any.
+    ///
+    /// # Safety
+    ///
+    /// Evicting advances `pop_cursor`, which is otherwise owned by the consumer. The consumer
+    /// must be quiesced (not inside `pop`, and holding no reference from
+    /// [`front`](Self::front)) for the duration of the call, otherwise both sides race on the
+    /// oldest slot.
+    pub unsafe fn force_push(&self, item: T) -> Option<T> {
+        let item = match self.push(item) {
+            Ok(()) => return None,
+            Err(item) => item,
+        };
+
+        // Really full: drop the oldest slot on the consumer's behalf. Publish anything left
+        // pending by push_deferred first, so the evicted slot is never ahead of push_cursor.
+        self.flush();
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(order::RELAXED);
+        let loc = pop_val % self.capacity;
+        let evicted = self.ring[loc].with_mut(|slot| unsafe { (*slot).take() });
+        consumer
+            .pop_cursor
+            .store(pop_val.wrapping_add(1), order::RELEASE);
+        // The consumer's cached push cursor may still point at the slot we just evicted; pop
+        // treats `pop == cached_push` as the only "go reload" signal, so a cache left behind
+        // the new pop cursor would let it read past push_cursor. Bring it up to date.
+        let push_val = self.producer.0.push_cursor.load(order::RELAXED);
+        consumer.cached_push.with_mut(|p| unsafe { *p = push_val });
+        // Count the eviction as a pop so total_pushed - total_popped still matches len().
+        #[cfg(feature = "stats")]
+        self.pop_stats.0.record_done();
+
+        if self.push(item).is_err() {
+            unreachable!("a slot was just freed");
         }
-    });
+        evicted
+    }
 
-    let start = Instant::now();
+    /// Number of items currently queued.
+    ///
+    /// This is only a snapshot and may be stale the moment it returns, since the other side
+    /// keeps pushing or popping. It reads the shared cursors rather than the shadow caches,
+    /// so it can be called from either thread.
+    pub fn len(&self) -> usize {
+        // Load pop first: the push cursor read afterwards can only be newer, so the difference
+        // never underflows. It can overshoot if the consumer races ahead in between, hence the clamp.
+        let pop = self.consumer.0.pop_cursor.load(order::ACQUIRE);
+        let push = self.producer.0.push_cursor.load(order::ACQUIRE);
+        push.wrapping_sub(pop).min(self.capacity)
+    }
 
-    for i in 0..iters {
-        loop {
-            if queue.push(i) {
-                break;
-            }
-            std::hint::spin_loop();
+    /// Snapshot check for emptiness; see [`len`](Self::len) for the staleness caveat.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many full laps the producer has made around the ring: published pushes divided
+    /// by the capacity. Together with [`len`](Self::len) that is the queue's whole push
+    /// history, read off the cursor without an extra counter. Callable from either thread.
+    ///
+    /// The cursor wraps at `usize::MAX` and the lap count drops back to 0 with it. That takes
+    /// 2^64 pushes on 64-bit targets but only 2^32 on 32-bit ones, where two samples should
+    /// be compared with `wrapping_sub` (exact across the wrap only for power-of-two
+    /// capacities, which divide 2^32).
+    pub fn lap_count(&self) -> usize {
+        self.producer.0.push_cursor.load(order::RELAXED) / self.capacity
+    }
+
+    /// Heartbeat counters `(producer_epoch, consumer_epoch)`: each advances by one on every
+    /// successful push / pop (evictions by `force_push` don't move the consumer's).
+    ///
+    /// Meant for a watchdog that samples them periodically: if the producer epoch stops
+    /// advancing while the queue isn't full, the producer is idle or gone rather than
+    /// blocked on the consumer. The counters wrap, so compare two samples with
+    /// `wrapping_sub`, not `<`.
+    ///
+    /// Only with the `progress` feature: keeping the counters costs every push and pop an
+    /// extra load and store on a line of its own.
+    #[cfg(feature = "progress")]
+    pub fn progress(&self) -> (u64, u64) {
+        (
+            self.producer_epoch
+                .0
+                .load(core::sync::atomic::Ordering::Relaxed),
+            self.consumer_epoch
+                .0
+                .load(core::sync::atomic::Ordering::Relaxed),
+        )
+    }
+
+    /// Snapshot of the push/pop counters. Only available with the `stats` feature; without
+    /// it the counters and their increments are compiled out.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> FifoStats {
+        use core::sync::atomic::Ordering::Relaxed;
+        FifoStats {
+            push_full_count: self.push_stats.0.failed.load(Relaxed),
+            pop_empty_count: self.pop_stats.0.failed.load(Relaxed),
+            total_pushed: self.push_stats.0.done.load(Relaxed),
+            total_popped: self.pop_stats.0.done.load(Relaxed),
+        }
+    }
+
+    /// Returns a producer handle that counts towards [`producers_alive`](Self::producers_alive)
+    /// until it is dropped, so the consumer can tell when nothing more will arrive without
+    /// a separate `done` flag. The queue is still single-producer: only one guard may push
+    /// at a time.
+    pub fn producer(self: &Arc<Self>) -> ProducerGuard<T> {
+        self.producers.fetch_add(1, order::RELAXED);
+        ProducerGuard {
+            queue: self.clone(),
+            _not_sync: PhantomData,
         }
     }
 
-    done.store(true, Ordering::Release);
-    consumer.join().unwrap();
+    /// Number of [`ProducerGuard`]s still alive.
+    ///
+    /// Once it reads 0 every guard's pushes are published, so a consumer that sees 0 and
+    /// then finds the queue empty is done; pop once more after seeing 0 before giving up.
+    ///
+    /// Before the first guard is created it reads 0 as well, so create the guards before
+    /// starting the consumer.
+    pub fn producers_alive(&self) -> usize {
+        self.producers.load(order::ACQUIRE)
+    }
+
+    /// Splits the queue into its two halves, turning the SPSC contract into a type-level
+    /// guarantee: neither handle is `Clone` or `Sync`, so there is exactly one pusher and
+    /// one popper, each of which may be moved to its own thread.
+    pub fn split(self) -> (Producer<T>, Consumer<T>) {
+        let queue = Arc::new(self);
+        (
+            Producer {
+                queue: queue.clone(),
+                _not_sync: PhantomData,
+            },
+            Consumer {
+                queue,
+                _not_sync: PhantomData,
+            },
+        )
+    }
+}
+
+// Each epoch has a single writer, so a plain load + store is enough (and cheaper than a
+// locked fetch_add); readers only need to see it move eventually.
+#[cfg(feature = "progress")]
+fn bump(epoch: &AtomicU64) {
+    use core::sync::atomic::Ordering::Relaxed;
+    epoch.store(epoch.load(Relaxed).wrapping_add(1), Relaxed);
+}
 
-    let duration = start.elapsed();
-    let secs = duration.as_secs_f64();
-    println!("Fifo4 Time: {:.4}s, Iters: {}", secs, iters);
+// Flags a push in progress and panics if another one starts before it ends. Tying the check
+// to overlap rather than to a thread id lets a producer move between threads, as `split`
+// and `producer` allow, and can't be fooled by a dead thread's id being reused.
+#[cfg(all(debug_assertions, feature = "std", not(loom)))]
+struct OverlapCheck(core::sync::atomic::AtomicBool);
 
-    (iters as f64) / secs
+#[cfg(all(debug_assertions, feature = "std", not(loom)))]
+impl OverlapCheck {
+    const fn new() -> OverlapCheck {
+        OverlapCheck(core::sync::atomic::AtomicBool::new(false))
+    }
+
+    fn enter(&self) -> OverlapToken<'_> {
+        use core::sync::atomic::Ordering::Acquire;
+        assert!(
+            !self.0.swap(true, Acquire),
+            "Fifo4: overlapping pushes; the queue is single-producer"
+        );
+        OverlapToken(&self.0)
+    }
+}
+
+// Clears the flag when the push returns, including by unwinding.
+#[cfg(all(debug_assertions, feature = "std", not(loom)))]
+struct OverlapToken<'a>(&'a core::sync::atomic::AtomicBool);
+
+#[cfg(all(debug_assertions, feature = "std", not(loom)))]
+impl Drop for OverlapToken<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, core::sync::atomic::Ordering::Release);
+    }
+}
+
+/// The pushing half of a split [`Fifo4`].
+pub struct Producer<T> {
+    queue: Arc<Fifo4<T>>,
+    // `Cell` is `Send` but not `Sync`: the handle can move threads but can't be shared.
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> Producer<T> {
+    pub fn push(&self, item: T) -> Result<(), T> {
+        self.queue.push(item)
+    }
+
+    /// See [`Fifo4::push_deferred`].
+    pub fn push_deferred(&self, item: T) -> Result<(), T> {
+        self.queue.push_deferred(item)
+    }
+
+    pub fn flush(&self) {
+        self.queue.flush()
+    }
+}
+
+/// Producer handle from [`Fifo4::producer`]. Dropping it publishes anything left pending
+/// by [`push_deferred`](Self::push_deferred) and then leaves the producer count.
+pub struct ProducerGuard<T> {
+    queue: Arc<Fifo4<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> ProducerGuard<T> {
+    pub fn push(&self, item: T) -> Result<(), T> {
+        self.queue.push(item)
+    }
+
+    /// See [`Fifo4::push_deferred`].
+    pub fn push_deferred(&self, item: T) -> Result<(), T> {
+        self.queue.push_deferred(item)
+    }
+
+    pub fn flush(&self) {
+        self.queue.flush()
+    }
+}
+
+impl<T> Drop for ProducerGuard<T> {
+    fn drop(&mut self) {
+        self.queue.flush();
+        // Release: a consumer that reads the lower count also sees everything published.
+        self.queue.producers.fetch_sub(1, order::RELEASE);
+    }
+}
+
+/// The popping half of a split [`Fifo4`].
+pub struct Consumer<T> {
+    queue: Arc<Fifo4<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<T> Consumer<T> {
+    pub fn pop(&self) -> Option<T> {
+        self.queue.pop()
+    }
+}
+
+// Shows the published items only: anything `push_deferred` wrote but hasn't flushed is
+// still private to the producer, and `len` doesn't count it either.
+impl<T> fmt::Debug for Fifo4<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pop_val = self.consumer.0.pop_cursor.load(order::ACQUIRE);
+        let push_val = self.producer.0.push_cursor.load(order::ACQUIRE);
+        debug_queue(f, "Fifo4", Some(self.capacity), pop_val, push_val)
+    }
+}
+
+#[cfg(feature = "bench")]
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    crate::bench::bench("Fifo4", Arc::new(Fifo4::<usize>::new(capacity)), iters)
+}
+
+/// Same as [`run_benchmark`], but pushing with [`Fifo4::push_deferred`] and publishing once
+/// every `threshold` items.
+#[cfg(feature = "bench")]
+pub fn run_deferred_benchmark(iters: usize, capacity: usize, threshold: usize) -> f64 {
+    let queue = Arc::new(Fifo4::<usize>::new(capacity).with_flush_threshold(threshold));
+    let consumer = queue.clone();
+    crate::bench::bench_fn::<_, _, crate::backoff::SpinBackoff>(
+        &alloc::format!("Fifo4 deferred (N={threshold})"),
+        iters,
+        move |i| {
+            let pushed = queue.push_deferred(i);
+            // The last batch may be short of the threshold.
+            if i + 1 == iters {
+                queue.flush();
+            }
+            pushed
+        },
+        move || consumer.pop(),
+    )
 }
@@ -0,0 +1,35 @@
+use super::Fifo4;
+
+/// Fan-in over several [`Fifo4`]s from one consumer thread, e.g. one queue per producer.
+///
+/// Each call to `next` tries the queues in rotation, starting just after the one it last
+/// popped from, so a busy queue can't starve the others. It yields `(index, item)` and only
+/// returns `None` when a full sweep found every queue empty; producers may of course refill
+/// them, so calling `next` again later can yield more.
+///
+/// The calling thread must be the sole consumer of every queue in the set.
+pub struct RoundRobin<'a, T> {
+    queues: &'a [&'a Fifo4<T>],
+    next: usize,
+}
+
+impl<'a, T> RoundRobin<'a, T> {
+    pub fn new(queues: &'a [&'a Fifo4<T>]) -> RoundRobin<'a, T> {
+        RoundRobin { queues, next: 0 }
+    }
+}
+
+impl<T> Iterator for RoundRobin<'_, T> {
+    type Item = (usize, T);
+
+    fn next(&mut self) -> Option<(usize, T)> {
+        for _ in 0..self.queues.len() {
+            let index = self.next;
+            self.next = (index + 1) % self.queues.len();
+            if let Some(item) = self.queues[index].pop() {
+                return Some((index, item));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,174 @@
+use std::time::{Duration, Instant};
+
+use super::Fifo5;
+use crate::error::PopTimeout;
+
+impl<T> Fifo5<T> {
+    /// Pushes `item`, parking the producer thread while the queue is full instead of
+    /// spinning.
+    ///
+    /// The consumer is only woken by `pop_blocking`, so pair the two: a plain `pop` frees
+    /// space without unparking a blocked producer.
+    pub fn push_blocking(&self, mut item: T) {
+        loop {
+            match self.push(item) {
+                Ok(()) => {
+                    self.consumer_parker.unpark();
+                    #[cfg(feature = "futures")]
+                    self.notify_readiness();
+                    return;
+                }
+                Err(rejected) => item = rejected,
+            }
+
+            self.producer_parker.prepare();
+            match self.push(item) {
+                Ok(()) => {
+                    self.producer_parker.cancel();
+                    self.consumer_parker.unpark();
+                    #[cfg(feature = "futures")]
+                    self.notify_readiness();
+                    return;
+                }
+                Err(rejected) => item = rejected,
+            }
+            self.producer_parker.park();
+        }
+    }
+
+    /// Pops an item, parking the consumer thread while the queue is empty. Pair with
+    /// `push_blocking` on the producer side, see above.
+    pub fn pop_blocking(&self) -> T {
+        loop {
+            if let Some(value) = self.pop() {
+                self.producer_parker.unpark();
+                return value;
+            }
+
+            self.consumer_parker.prepare();
+            if let Some(value) = self.pop() {
+                self.consumer_parker.cancel();
+                self.producer_parker.unpark();
+                return value;
+            }
+            self.consumer_parker.park();
+        }
+    }
+
+    /// Pops an item, polling up to `spins` times (with a `spin_loop` hint in between)
+    /// before parking like `pop_blocking`. An item that arrives within the spin window is
+    /// picked up without the wake-up latency of a park; a queue that stays empty costs only
+    /// the window's worth of CPU before the thread sleeps.
+    ///
+    /// Size `spins` to cover the typical gap between items: a poll is a few nanoseconds,
+    /// so a few thousand spins is on the order of microseconds. `0` is `pop_blocking`. The
+    /// park phase is woken by `push_blocking`, as for `pop_blocking`.
+    pub fn recv_adaptive(&self, spins: u32) -> T {
+        for _ in 0..spins {
+            if let Some(value) = self.pop() {
+                self.producer_parker.unpark();
+                return value;
+            }
+            core::hint::spin_loop();
+        }
+        self.pop_blocking()
+    }
+
+    /// Parks like `pop_blocking` until at least one item is queued, then moves everything
+    /// available (up to `max`) into `out` with one [`pop_batch`](Self::pop_batch). Returns
+    /// the count, which is only 0 for `max == 0`. One wakeup drains a whole burst.
+    pub fn recv_many(&self, out: &mut Vec<T>, max: usize) -> usize {
+        if max == 0 {
+            return 0;
+        }
+        loop {
+            let n = self.pop_batch(out, max);
+            if n > 0 {
+                self.producer_parker.unpark();
+                return n;
+            }
+
+            self.consumer_parker.prepare();
+            let n = self.pop_batch(out, max);
+            if n > 0 {
+                self.consumer_parker.cancel();
+                self.producer_parker.unpark();
+                return n;
+            }
+            self.consumer_parker.park();
+        }
+    }
+
+    /// Parks the consumer thread until the queue has at least one item, without popping it,
+    /// e.g. to look at a whole burst with `as_slices` before deciding what to take. Woken by
+    /// `push_blocking`, like `pop_blocking`.
+    ///
+    /// Consumer thread only: the item is still there on return because nobody else pops.
+    pub fn wait_not_empty(&self) {
+        while self.is_empty() {
+            self.consumer_parker.prepare();
+            // A push between the check above and `prepare` would not have unparked us.
+            if !self.is_empty() {
+                self.consumer_parker.cancel();
+                return;
+            }
+            self.consumer_parker.park();
+        }
+    }
+
+    /// Parks the producer thread until there is room for at least one push, without pushing.
+    /// Woken by `pop_blocking` (and the other blocking pops), like `push_blocking`.
+    ///
+    /// Producer thread only, for the same reason as `wait_not_empty`.
+    pub fn wait_not_full(&self) {
+        while self.free_slots() == 0 {
+            self.producer_parker.prepare();
+            if self.free_slots() != 0 {
+                self.producer_parker.cancel();
+                return;
+            }
+            self.producer_parker.park();
+        }
+    }
+
+    /// Like `pop_blocking`, but gives up once `dur` has passed without an item, in the
+    /// spirit of `mpsc::Receiver::recv_timeout`. The thread parks with a timeout rather than
+    /// spinning; as with `pop_blocking`, only `push_blocking` unparks it early.
+    pub fn pop_timeout(&self, dur: Duration) -> Result<T, PopTimeout> {
+        // A deadline past what Instant can represent is as good as no deadline.
+        let Some(deadline) = Instant::now().checked_add(dur) else {
+            return Ok(self.pop_blocking());
+        };
+        loop {
+            if let Some(value) = self.pop() {
+                self.producer_parker.unpark();
+                return Ok(value);
+            }
+
+            self.consumer_parker.prepare();
+            if let Some(value) = self.pop() {
+                self.consumer_parker.cancel();
+                self.producer_parker.unpark();
+                return Ok(value);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                self.consumer_parker.cancel();
+                return Err(PopTimeout::Timeout);
+            }
+            self.consumer_parker.park_timeout(deadline - now);
+        }
+    }
+
+    /// How many times the blocking methods have unparked the `(producer, consumer)`. Only
+    /// a side that announced it was about to park gets unparked, so traffic on a queue
+    /// whose threads never wait leaves both at zero.
+    #[doc(hidden)]
+    pub fn unpark_counts(&self) -> (usize, usize) {
+        (
+            self.producer_parker.unparks(),
+            self.consumer_parker.unparks(),
+        )
+    }
+}
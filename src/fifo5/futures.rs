@@ -0,0 +1,97 @@
+use core::future::{Future, poll_fn};
+use core::sync::atomic::{Ordering, fence};
+use core::task::{Poll, Waker};
+
+use super::Fifo5;
+
+impl<T> Fifo5<T> {
+    /// Pushes `item`, waiting without blocking the thread while the queue is full.
+    ///
+    /// The task is woken by `pop_async`, so pair the two: a plain `pop` frees space
+    /// without waking a pending producer. Dropping the future before it completes drops
+    /// `item` without enqueueing it.
+    pub fn push_async(&self, item: T) -> impl Future<Output = ()> + '_ {
+        let mut item = Some(item);
+        poll_fn(move |cx| {
+            let value = item.take().expect("push_async polled after completion");
+            let value = match self.push(value) {
+                Ok(()) => {
+                    self.consumer_waker.wake();
+                    self.notify_readiness();
+                    return Poll::Ready(());
+                }
+                Err(value) => value,
+            };
+
+            // Register before re-checking so a pop that lands in between still wakes us.
+            self.producer_waker.register(cx.waker());
+            match self.push(value) {
+                Ok(()) => {
+                    self.consumer_waker.wake();
+                    self.notify_readiness();
+                    Poll::Ready(())
+                }
+                Err(value) => {
+                    item = Some(value);
+                    Poll::Pending
+                }
+            }
+        })
+    }
+
+    /// Pops an item, waiting without blocking the thread while the queue is empty.
+    ///
+    /// Cancellation-safe: an item is only taken out of the ring by the poll that returns
+    /// it, so dropping a pending future never loses data.
+    pub fn pop_async(&self) -> impl Future<Output = T> + '_ {
+        poll_fn(move |cx| {
+            if let Some(value) = self.pop() {
+                self.producer_waker.wake();
+                return Poll::Ready(value);
+            }
+
+            self.consumer_waker.register(cx.waker());
+            match self.pop() {
+                Some(value) => {
+                    self.producer_waker.wake();
+                    Poll::Ready(value)
+                }
+                None => Poll::Pending,
+            }
+        })
+    }
+
+    /// Arms a one-shot readiness notification: `waker` is woken by the next
+    /// [`push_async`](Self::push_async) or `push_blocking`, i.e. when the queue goes from
+    /// empty to non-empty. If the queue already has items, it is woken right away.
+    ///
+    /// The other push methods don't notify: that would take a full fence on every push
+    /// whether or not anything is registered. A producer feeding an event loop should push
+    /// through one of the two notifying methods.
+    ///
+    /// This is the building block for multiplexing several queues in one event loop: on
+    /// finding a queue empty, register with it, and drain it when woken. Each call replaces
+    /// the previously registered waker, and a wake disarms it, so register again after each
+    /// one. Consumer side only, like `pop`.
+    pub fn register_readiness(&self, waker: Waker) {
+        self.readiness.register(&waker);
+        self.readiness_armed.store(true, Ordering::Relaxed);
+        // Pairs with the fence in `notify_readiness`: either the producer sees the flag,
+        // or we see its push in the check below.
+        fence(Ordering::SeqCst);
+        if !self.is_empty() {
+            self.notify_readiness();
+        }
+    }
+
+    // Called by push_async and push_blocking after they publish.
+    #[inline]
+    pub(super) fn notify_readiness(&self) {
+        fence(Ordering::SeqCst);
+        if self.readiness_armed.load(Ordering::Relaxed)
+            && self.readiness_armed.swap(false, Ordering::Relaxed)
+        {
+            self.readiness.wake();
+        }
+    }
+}
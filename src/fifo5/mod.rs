@@ -1,13 +1,29 @@
-use std::cell::UnsafeCell;
-use std::mem::MaybeUninit;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+#[cfg(feature = "futures")]
+use atomic_waker::AtomicWaker;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::iter::Peekable;
+use core::mem::{self, MaybeUninit};
+use core::ptr;
+#[cfg(feature = "futures")]
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "bench")]
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::thread;
-use std::time::Instant;
 
-/// Wrapper to force alignment to 128 bytes.
-#[repr(align(128))]
-struct CachePadded<T>(T);
+use crate::builder::{AUTO_CAPACITY, DEFAULT_CAPACITY, capacity_for_message_size};
+use crate::cache_padded::{CachePadded, assert_own_lines};
+use crate::error::{FifoError, TryPopError, TryPushError};
+#[cfg(feature = "std")]
+use crate::parker::Parker;
+use crate::queue::debug_queue;
+
+#[cfg(feature = "std")]
+mod blocking;
+#[cfg(feature = "futures")]
+mod futures;
 
 struct ProducerFields {
     push_cursor: AtomicUsize,
@@ -19,24 +35,61 @@ struct ConsumerFields {
     cached_push: UnsafeCell<usize>,
 }
 
+/// SPSC queue over a ring of uninitialized slots, with shadow copies of the other side's
+/// cursor so most operations touch only their own cache line.
+///
+/// The cursors count every push and pop and wrap around at `usize::MAX`; the occupancy is
+/// always their wrapping difference. The slot for a cursor is `cursor % capacity`, which
+/// stays continuous across that wrap only when the capacity is a power of two, so a queue
+/// expected to outlive 2^usize::BITS operations (think 32-bit targets) should use one.
+///
+/// There is no separate queue for `Copy` items: a pop is a plain `ptr::read` of the slot,
+/// and every drop-related path is behind `needs_drop::<T>()`, which is decided at compile
+/// time, so `Fifo5<u64>` carries no drop bookkeeping.
 pub struct Fifo5<T> {
     capacity: usize,
     // Raw uninitialized memory. No Option<T> overhead.
-    // We treat this as a circular buffer of T.
-    ring: Box<[MaybeUninit<T>]>,
+    // We treat this as a circular buffer of T. The UnsafeCell is what allows writing
+    // through `&self`: pointers derived from it carry write permission.
+    ring: Box<[UnsafeCell<MaybeUninit<T>>]>,
     producer: CachePadded<ProducerFields>,
     consumer: CachePadded<ConsumerFields>,
+    // Threads that block in push_blocking / pop_blocking. Only those two touch them: each
+    // checks the other side's `waiting` flag after it publishes, while plain push and pop
+    // never look at the parkers.
+    #[cfg(feature = "std")]
+    producer_parker: Parker,
+    #[cfg(feature = "std")]
+    consumer_parker: Parker,
+    // Tasks waiting in push_async / pop_async.
+    #[cfg(feature = "futures")]
+    producer_waker: AtomicWaker,
+    #[cfg(feature = "futures")]
+    consumer_waker: AtomicWaker,
+    // Edge-triggered readiness for event loops, see `register_readiness`. The flag lets
+    // push_async / push_blocking skip the waker entirely while nobody is waiting.
+    #[cfg(feature = "futures")]
+    readiness: AtomicWaker,
+    #[cfg(feature = "futures")]
+    readiness_armed: AtomicBool,
+    // Peak occupancy, see `high_water_mark`. Written by the producer only.
+    #[cfg(feature = "stats")]
+    high_water: CachePadded<AtomicUsize>,
 }
 
+// The ring handle is read on every operation by both sides, so it must not share a line
+// with either cursor group. The layout doesn't depend on `T`; `u8` stands in for all.
+assert_own_lines!(Fifo5<u8>: ring, producer, consumer);
+
 unsafe impl<T: Send> Sync for Fifo5<T> {}
 unsafe impl<T: Send> Send for Fifo5<T> {}
 
 impl<T> Fifo5<T> {
+    /// Takes `capacity` as is; see the type docs for why powers of two are still the safe
+    /// choice for queues that live long enough to wrap their cursors.
     pub fn new(capacity: usize) -> Fifo5<T> {
         // Allocate raw memory.
-        let mut ring = Vec::with_capacity(capacity);
-        ring.resize_with(capacity, MaybeUninit::uninit);
-        let ring = ring.into_boxed_slice();
+        let ring = Self::alloc_ring(capacity);
 
         Fifo5 {
             capacity,
@@ -49,125 +102,1116 @@ impl<T> Fifo5<T> {
                 pop_cursor: AtomicUsize::new(0),
                 cached_push: UnsafeCell::new(0),
             }),
+            #[cfg(feature = "std")]
+            producer_parker: Parker::new(),
+            #[cfg(feature = "std")]
+            consumer_parker: Parker::new(),
+            #[cfg(feature = "futures")]
+            producer_waker: AtomicWaker::new(),
+            #[cfg(feature = "futures")]
+            consumer_waker: AtomicWaker::new(),
+            #[cfg(feature = "futures")]
+            readiness: AtomicWaker::new(),
+            #[cfg(feature = "futures")]
+            readiness_armed: AtomicBool::new(false),
+            #[cfg(feature = "stats")]
+            high_water: CachePadded(AtomicUsize::new(0)),
         }
     }
 
-    pub fn pop(&self) -> Option<T> {
-        let consumer = &self.consumer.0;
-        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+    /// Starts all four cursors at `start` instead of 0, so tests and the fuzz target can
+    /// drive them across `usize::MAX` without pushing 2^64 items first.
+    #[doc(hidden)]
+    pub fn with_start_cursor(capacity: usize, start: usize) -> Fifo5<T> {
+        let mut queue = Fifo5::new(capacity);
+        *queue.producer.0.push_cursor.get_mut() = start;
+        *queue.producer.0.cached_pop.get_mut() = start;
+        *queue.consumer.0.pop_cursor.get_mut() = start;
+        *queue.consumer.0.cached_push.get_mut() = start;
+        queue
+    }
 
-        let mut cached_push = unsafe { *consumer.cached_push.get() };
+    /// Creates a queue with [`AUTO_CAPACITY`](crate::builder::AUTO_CAPACITY) slots, a
+    /// reasonable default when there's no better figure to go on.
+    pub fn auto() -> Fifo5<T> {
+        Fifo5::new(AUTO_CAPACITY)
+    }
 
-        if pop_val >= cached_push {
-            let actual_push = self.producer.0.push_cursor.load(Ordering::Acquire);
-            unsafe { *consumer.cached_push.get() = actual_push };
-            cached_push = actual_push;
+    /// Creates a queue holding roughly `target_buffer_bytes` worth of `msg_bytes`-sized
+    /// messages, e.g. `Fifo5::for_message_size(40, 1 << 20)` for 1 MiB of 40-byte messages.
+    /// See [`capacity_for_message_size`] for the rounding and the errors.
+    pub fn for_message_size(
+        msg_bytes: usize,
+        target_buffer_bytes: usize,
+    ) -> Result<Fifo5<T>, FifoError> {
+        capacity_for_message_size(msg_bytes, target_buffer_bytes).map(Fifo5::new)
+    }
 
-            if pop_val >= cached_push {
-                return None;
-            }
+    /// Creates a queue and fills it from `iter` before any other thread can see it.
+    ///
+    /// If `iter` yields more than `capacity` items, the full queue is returned in `Err`
+    /// together with the rest of the iterator, nothing lost.
+    // The Err side hands the whole queue back by design, so it is as large as Ok.
+    #[allow(clippy::result_large_err, clippy::type_complexity)]
+    pub fn new_filled<I>(
+        capacity: usize,
+        iter: I,
+    ) -> Result<Fifo5<T>, (Fifo5<T>, Peekable<I::IntoIter>)>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut queue = Fifo5::new(capacity);
+        let mut iter = iter.into_iter().peekable();
+        let mut len = 0;
+        while len < capacity {
+            let Some(item) = iter.next() else {
+                return Ok(queue);
+            };
+            // SAFETY: We own the queue, so the slot is free and nobody else can observe it.
+            // The cursor is bumped per item so Drop stays correct if the iterator panics.
+            unsafe { queue.slot_ptr(len).write(item) };
+            len += 1;
+            *queue.producer.0.push_cursor.get_mut() = len;
+        }
+
+        if iter.peek().is_some() {
+            Err((queue, iter))
+        } else {
+            Ok(queue)
         }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Same as [`try_pop`](Self::try_pop), with the error folded into `None`.
+    #[inline]
+    pub fn pop(&self) -> Option<T> {
+        self.try_pop().ok()
+    }
+
+    /// Pops the next item, or fails with [`TryPopError::Empty`] if there is none yet.
+    #[inline]
+    pub fn try_pop(&self) -> Result<T, TryPopError> {
+        let pop_val = self.front_cursor().ok_or(TryPopError::Empty)?;
 
         let loc = pop_val % self.capacity;
         // SAFETY:
-        // 1. We checked push > pop, so data exists.
+        // 1. We checked push != pop, so data exists.
         // 2. We are the only consumer.
         // 3. We read using ptr::read (memcpy effectively)
         // 4. We do NOT write back to the slot (saving a write vs Option::take).
         // 5. The slot is logically "uninit" for us now, but physically contains old bytes.
-        let value = unsafe { self.ring[loc].as_ptr().read() };
+        let value = unsafe { self.slot_ptr(loc).read() };
+        // Streaming consumers touch the next slot right after this one; start loading it.
+        #[cfg(feature = "prefetch")]
+        prefetch_read(self.slot_ptr(if loc + 1 == self.capacity { 0 } else { loc + 1 }));
+
+        self.consumer
+            .0
+            .pop_cursor
+            .store(pop_val.wrapping_add(1), Ordering::Release);
+        Ok(value)
+    }
+
+    /// Pops the front element and returns `f` applied to it, for a pipeline stage that
+    /// converts every item as it takes it. The slot is released before `f` runs, so a slow
+    /// or panicking `f` neither holds up the producer nor leaves the element queued.
+    ///
+    /// Same as `pop().map(f)`. Being inlined, the compiler can feed `f` from the bytes just
+    /// read out of the slot instead of moving them through an `Option<T>` first.
+    #[inline]
+    pub fn pop_map<U>(&self, f: impl FnOnce(T) -> U) -> Option<U> {
+        self.pop().map(f)
+    }
+
+    /// Moves the front element straight into `*dst` and returns `true`, or returns `false`
+    /// and leaves `dst` alone if the queue is empty. Saves the move through an `Option<T>`
+    /// when the destination is foreign memory.
+    ///
+    /// # Safety
+    ///
+    /// `dst` must be valid for writes and aligned for `T`. It is treated as uninitialized:
+    /// the element is written over it without dropping whatever was there. Consumer thread
+    /// only, like `pop`.
+    pub unsafe fn pop_into(&self, dst: *mut T) -> bool {
+        let Some(pop_val) = self.front_cursor() else {
+            return false;
+        };
+
+        // SAFETY: The slot holds a published value owned by the consumer (as in `try_pop`),
+        // and the caller guarantees `dst` is writable and can't overlap the ring.
+        unsafe { ptr::copy_nonoverlapping(self.slot_ptr(pop_val % self.capacity), dst, 1) };
+
+        self.consumer
+            .0
+            .pop_cursor
+            .store(pop_val.wrapping_add(1), Ordering::Release);
+        true
+    }
+
+    // Consumer-side: the pop cursor if there is an element to pop there, refreshing the
+    // cached push cursor only when the cached one says empty.
+    #[inline]
+    fn front_cursor(&self) -> Option<usize> {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+
+        let cached_push = unsafe { *consumer.cached_push.get() };
+        if pop_val != cached_push {
+            return Some(pop_val);
+        }
+        self.refresh_cached_push(pop_val)
+    }
+
+    // The slow halves of the shadow-cursor checks: reload the other side's cursor, update
+    // our copy, and check again. `#[cold]` makes the compiler lay them out after the fast
+    // path, so a push or pop that the cached cursor already allows runs straight through
+    // without a taken branch. They are a few instructions each, so they still get inlined;
+    // `#[inline(never)]` measured worse, adding a call and register spills to the fast path.
+    #[cold]
+    fn refresh_cached_push(&self, pop_val: usize) -> Option<usize> {
+        let actual_push = self.producer.0.push_cursor.load(Ordering::Acquire);
+        unsafe { *self.consumer.0.cached_push.get() = actual_push };
+        (pop_val != actual_push).then_some(pop_val)
+    }
+
+    #[cold]
+    fn refresh_cached_pop(&self, push_val: usize) -> bool {
+        let actual_pop = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+        unsafe { *self.producer.0.cached_pop.get() = actual_pop };
+        push_val.wrapping_sub(actual_pop) < self.capacity
+    }
+
+    /// Returns `Err(item)` if the queue is full; the item is handed back untouched.
+    #[inline]
+    pub fn push(&self, item: T) -> Result<(), T> {
+        self.try_push(item).map_err(TryPushError::into_inner)
+    }
+
+    /// Pushes `item`, or fails with [`TryPushError::Full`] carrying it back if the queue is
+    /// full.
+    #[inline]
+    pub fn try_push(&self, item: T) -> Result<(), TryPushError<T>> {
+        let producer = &self.producer.0;
+        let push_val = producer.push_cursor.load(Ordering::Relaxed);
+
+        let cached_pop = unsafe { *producer.cached_pop.get() };
+        if push_val.wrapping_sub(cached_pop) >= self.capacity && !self.refresh_cached_pop(push_val)
+        {
+            return Err(TryPushError::Full(item));
+        }
+
+        let loc = push_val % self.capacity;
+        // SAFETY: Slot is free and owned by the producer via SPSC logic. Write data directly.
+        unsafe { self.slot_ptr(loc).write(item) };
+
+        producer
+            .push_cursor
+            .store(push_val.wrapping_add(1), Ordering::Release);
+        #[cfg(feature = "stats")]
+        self.record_high_water(push_val.wrapping_add(1));
+        Ok(())
+    }
+
+    /// Reserves the next free slot so a value can be constructed in place, or returns
+    /// `None` if the queue is full.
+    ///
+    /// Nothing is published until [`WriteSlot::commit`]; dropping the guard instead leaves
+    /// `push_cursor` where it was, so the slot is simply reused by the next push (a value
+    /// already written into it is leaked, not dropped). Producer thread only, and only one
+    /// guard may be outstanding at a time.
+    pub fn reserve(&self) -> Option<WriteSlot<'_, T>> {
+        let producer = &self.producer.0;
+        let push_val = producer.push_cursor.load(Ordering::Relaxed);
+
+        let cached_pop = unsafe { *producer.cached_pop.get() };
+        if push_val.wrapping_sub(cached_pop) >= self.capacity && !self.refresh_cached_pop(push_val)
+        {
+            return None;
+        }
+
+        Some(WriteSlot {
+            queue: self,
+            pos: push_val,
+        })
+    }
+
+    /// Hands `f` the next free slot to fill in place, then publishes it and returns `f`'s
+    /// result, or returns `None` without calling `f` if the queue is full. A closure form of
+    /// [`reserve`](Self::reserve) + [`WriteSlot::commit`]. Producer thread only.
+    ///
+    /// The consumer sees the item once `f` has returned, not before. If `f` panics nothing
+    /// is published and the slot stays free.
+    ///
+    /// # Safety
+    ///
+    /// `f` must leave a valid `T` in the slot. Whatever bytes it finds there belong to an
+    /// item that was already popped, so treat them as uninitialized, never as a `T` to reuse.
+    pub unsafe fn with_next_slot<R>(&self, f: impl FnOnce(&mut MaybeUninit<T>) -> R) -> Option<R> {
+        let mut slot = self.reserve()?;
+        // SAFETY: The reserved slot is free and only the producer touches it until commit.
+        let result = f(unsafe { &mut *slot.as_mut_ptr().cast::<MaybeUninit<T>>() });
+        // SAFETY: The caller guarantees `f` initialized the slot.
+        unsafe { slot.commit() };
+        Some(result)
+    }
+
+    /// Number of items currently queued.
+    ///
+    /// This is only a snapshot and may be stale the moment it returns, since the other side
+    /// keeps pushing or popping. It reads the shared cursors rather than the shadow caches,
+    /// so it can be called from either thread.
+    pub fn len(&self) -> usize {
+        // Load pop first: the push cursor read afterwards can only be newer, so the difference
+        // never underflows. It can overshoot if the consumer races ahead in between, hence the clamp.
+        let pop = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+        let push = self.producer.0.push_cursor.load(Ordering::Acquire);
+        push.wrapping_sub(pop).min(self.capacity)
+    }
+
+    /// Snapshot check for emptiness; see [`len`](Self::len) for the staleness caveat.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// How many items the producer could push right now, e.g. to size a
+    /// [`push_batch`](Self::push_batch) so it doesn't come up short.
+    ///
+    /// A lower bound: the consumer may free more slots at any moment, but only the producer
+    /// can use them up, so the answer stays valid until the next push. Producer thread only
+    /// (it also refreshes the producer's cached copy of the pop cursor).
+    pub fn free_slots(&self) -> usize {
+        let producer = &self.producer.0;
+        let push_val = producer.push_cursor.load(Ordering::Relaxed);
+        let pop_val = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+        unsafe { *producer.cached_pop.get() = pop_val };
+        self.capacity - push_val.wrapping_sub(pop_val)
+    }
+
+    /// The most items that were ever queued at once (since creation or the last
+    /// [`resize`](Self::resize)), as seen by the producer right after each push. Requires
+    /// the `stats` feature.
+    ///
+    /// Measured against a possibly stale view of the consumer's cursor, so it can overstate
+    /// the true peak slightly, never understate it.
+    #[cfg(feature = "stats")]
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water.0.load(Ordering::Relaxed)
+    }
 
-        consumer.pop_cursor.store(pop_val + 1, Ordering::Release);
-        Some(value)
+    /// [`high_water_mark`](Self::high_water_mark) as a fraction of the capacity, from 0.0
+    /// to 1.0: how full the queue has ever been, for sizing it. Requires `stats`.
+    #[cfg(feature = "stats")]
+    pub fn occupancy_ratio(&self) -> f64 {
+        self.high_water_mark() as f64 / self.capacity as f64
     }
 
-    pub fn push(&self, item: T) -> bool {
+    // Producer side, after publishing up to `push_val`. The real pop cursor is a read of the
+    // consumer's line that the shadow cursor otherwise saves us, which is why this only
+    // exists with `stats`. It can't be older than `cached_pop`, so the result fits the ring.
+    #[cfg(feature = "stats")]
+    fn record_high_water(&self, push_val: usize) {
+        let pop_val = self.consumer.0.pop_cursor.load(Ordering::Relaxed);
+        self.high_water
+            .0
+            .fetch_max(push_val.wrapping_sub(pop_val), Ordering::Relaxed);
+    }
+
+    /// Moves as many items as fit from the front of `items` into the ring and publishes
+    /// them with a single `Release` store on `push_cursor`.
+    ///
+    /// Returns how many items were enqueued; those are removed from `items` and the
+    /// rest are shifted down to the front of the `Vec`, preserving their order.
+    pub fn push_batch(&self, items: &mut Vec<T>) -> usize {
         let producer = &self.producer.0;
         let push_val = producer.push_cursor.load(Ordering::Relaxed);
 
         let mut cached_pop = unsafe { *producer.cached_pop.get() };
 
-        if push_val >= cached_pop + self.capacity {
-            let actual_pop = self.consumer.0.pop_cursor.load(Ordering::Acquire);
-            unsafe { *producer.cached_pop.get() = actual_pop };
-            cached_pop = actual_pop;
+        // Only go to the shared pop cursor if the cached view can't take the whole batch.
+        if push_val.wrapping_sub(cached_pop) + items.len() > self.capacity {
+            cached_pop = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+            unsafe { *producer.cached_pop.get() = cached_pop };
+        }
+
+        let count = (self.capacity - push_val.wrapping_sub(cached_pop)).min(items.len());
+        if count == 0 {
+            return 0;
+        }
+
+        // SAFETY: The `count` slots starting at push_val are free and owned by the producer.
+        // Ownership of the first `count` items moves into the ring, so they are removed from
+        // `items` without being dropped; the tail is shifted down to close the gap.
+        unsafe {
+            self.write_run(push_val, items.as_ptr(), count);
+            let remaining = items.len() - count;
+            items.set_len(0);
+            ptr::copy(items.as_ptr().add(count), items.as_mut_ptr(), remaining);
+            items.set_len(remaining);
+        }
+
+        producer
+            .push_cursor
+            .store(push_val.wrapping_add(count), Ordering::Release);
+        #[cfg(feature = "stats")]
+        self.record_high_water(push_val.wrapping_add(count));
+        count
+    }
+
+    /// Pushes items from `iter` until the queue is full, publishing them with a single
+    /// `Release` store, and hands back the iterator with whatever didn't fit.
+    ///
+    /// Only as many items as there is room for are pulled from the iterator, so nothing is
+    /// lost at the boundary: the first item of the remainder is the first one not queued.
+    /// Producer thread only. If `iter` panics, the items it already yielded are leaked.
+    pub fn try_extend<I: IntoIterator<Item = T>>(&self, iter: I) -> I::IntoIter {
+        let mut iter = iter.into_iter();
+        let producer = &self.producer.0;
+        let push_val = producer.push_cursor.load(Ordering::Relaxed);
+
+        // Take the real pop cursor up front: the room can only grow while we're filling it.
+        let pop_val = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+        unsafe { *producer.cached_pop.get() = pop_val };
+        let free = self.capacity - push_val.wrapping_sub(pop_val);
+
+        let mut count = 0;
+        for item in iter.by_ref().take(free) {
+            // SAFETY: The `free` slots from push_val on are unpublished and owned by the producer.
+            unsafe {
+                self.slot_ptr(push_val.wrapping_add(count) % self.capacity)
+                    .write(item)
+            };
+            count += 1;
+        }
+
+        if count > 0 {
+            producer
+                .push_cursor
+                .store(push_val.wrapping_add(count), Ordering::Release);
+            #[cfg(feature = "stats")]
+            self.record_high_water(push_val.wrapping_add(count));
+        }
+        iter
+    }
+
+    /// Pops up to `max` items into `out` and releases them with a single store on
+    /// `pop_cursor`. Returns how many items were appended to `out`.
+    pub fn pop_batch(&self, out: &mut Vec<T>, max: usize) -> usize {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+
+        let mut cached_push = unsafe { *consumer.cached_push.get() };
+
+        if cached_push.wrapping_sub(pop_val) < max {
+            cached_push = self.producer.0.push_cursor.load(Ordering::Acquire);
+            unsafe { *consumer.cached_push.get() = cached_push };
+        }
+
+        let count = cached_push.wrapping_sub(pop_val).min(max);
+        if count == 0 {
+            return 0;
+        }
+
+        out.reserve(count);
+        // SAFETY: The `count` slots starting at pop_val hold published values owned by the
+        // consumer. They are moved into the spare capacity of `out` and treated as
+        // uninitialized in the ring from now on.
+        unsafe {
+            let len = out.len();
+            self.read_run(pop_val, out.as_mut_ptr().add(len), count);
+            out.set_len(len + count);
+        }
+
+        consumer
+            .pop_cursor
+            .store(pop_val.wrapping_add(count), Ordering::Release);
+        count
+    }
+
+    /// Moves every item currently queued into `dst`, as far as it has room, and returns how
+    /// many were moved. When `dst` fills up first, the rest stay at the front of this queue
+    /// in order.
+    ///
+    /// The items are copied ring to ring (at most two runs each side) and published with
+    /// one store per cursor. The calling thread must be this queue's consumer and `dst`'s
+    /// producer.
+    pub fn drain_into(&self, dst: &Fifo5<T>) -> usize {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Acquire);
+        unsafe { *consumer.cached_push.get() = push_val };
+
+        let dst_producer = &dst.producer.0;
+        let dst_push = dst_producer.push_cursor.load(Ordering::Relaxed);
+        let dst_pop = dst.consumer.0.pop_cursor.load(Ordering::Acquire);
+        unsafe { *dst_producer.cached_pop.get() = dst_pop };
+
+        let free = dst.capacity - dst_push.wrapping_sub(dst_pop);
+        let count = push_val.wrapping_sub(pop_val).min(free);
+        if count == 0 {
+            return 0;
+        }
+
+        let ((loc, first), rest) = self.runs(pop_val, count);
+        // SAFETY: The source slots are published and owned by us as the consumer, the `count`
+        // destination slots are free and owned by us as `dst`'s producer. They can't overlap
+        // even if `dst` is `self`, since `count` is at most the free space. Ownership moves
+        // with the bytes: the source slots count as uninitialized once pop_cursor moves.
+        unsafe {
+            dst.write_run(dst_push, self.slot_ptr(loc), first);
+            dst.write_run(dst_push.wrapping_add(first), self.slot_ptr(0), rest);
+        }
+
+        dst_producer
+            .push_cursor
+            .store(dst_push.wrapping_add(count), Ordering::Release);
+        #[cfg(feature = "stats")]
+        dst.record_high_water(dst_push.wrapping_add(count));
+        consumer
+            .pop_cursor
+            .store(pop_val.wrapping_add(count), Ordering::Release);
+        count
+    }
+
+    /// Pops the run of items at the front for which `pred` returns `true` into `out`,
+    /// stopping at the first one that fails (which stays queued) or when the queue is empty.
+    /// Returns how many items were appended; `pop_cursor` moves by exactly that many.
+    ///
+    /// `pred` only sees references: the whole run is checked before anything is moved out,
+    /// so a panicking predicate leaves the queue as it was. Consumer thread only.
+    pub fn pop_while<F: FnMut(&T) -> bool>(&self, mut pred: F, out: &mut Vec<T>) -> usize {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Acquire);
+        unsafe { *consumer.cached_push.get() = push_val };
+
+        // SAFETY: Every slot between the cursors is published and only the consumer touches
+        // it until pop_cursor moves, as in `peek_contiguous`.
+        let count = (0..push_val.wrapping_sub(pop_val))
+            .take_while(|&i| {
+                pred(unsafe { &*self.slot_ptr(pop_val.wrapping_add(i) % self.capacity) })
+            })
+            .count();
+        if count == 0 {
+            return 0;
+        }
+
+        out.reserve(count);
+        // SAFETY: Same as `pop_batch`: the run is moved into the spare capacity of `out` and
+        // the slots count as uninitialized once pop_cursor moves past them.
+        unsafe {
+            let len = out.len();
+            self.read_run(pop_val, out.as_mut_ptr().add(len), count);
+            out.set_len(len + count);
+        }
+
+        consumer
+            .pop_cursor
+            .store(pop_val.wrapping_add(count), Ordering::Release);
+        count
+    }
 
-            if push_val >= cached_pop + self.capacity {
-                return false;
+    /// Pops up to `out.len()` items into the front of `out` without allocating and releases
+    /// them with a single store on `pop_cursor`. Returns how many slots of `out` were written.
+    ///
+    /// The values previously in those slots of `out` are dropped. Consumer thread only.
+    pub fn try_pop_n(&self, out: &mut [T]) -> usize {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+
+        let mut cached_push = unsafe { *consumer.cached_push.get() };
+
+        if cached_push.wrapping_sub(pop_val) < out.len() {
+            cached_push = self.producer.0.push_cursor.load(Ordering::Acquire);
+            unsafe { *consumer.cached_push.get() = cached_push };
+        }
+
+        let count = cached_push.wrapping_sub(pop_val).min(out.len());
+        if count == 0 {
+            return 0;
+        }
+
+        if !core::mem::needs_drop::<T>() {
+            // SAFETY: Nothing in `out` needs dropping, so the published values can be moved
+            // straight over it; the ring slots count as uninitialized once pop_cursor moves.
+            unsafe { self.read_run(pop_val, out.as_mut_ptr(), count) };
+            consumer
+                .pop_cursor
+                .store(pop_val.wrapping_add(count), Ordering::Release);
+            return count;
+        }
+
+        // Swap the queued values into `out`, which parks the old contents of `out` in the
+        // ring slots. Those still belong to us, so drop them before handing the slots back.
+        for (i, dst) in out[..count].iter_mut().enumerate() {
+            // SAFETY: Slot pop_val + i holds a published value owned by the consumer.
+            unsafe {
+                ptr::swap_nonoverlapping(
+                    self.slot_ptr(pop_val.wrapping_add(i) % self.capacity),
+                    dst,
+                    1,
+                )
+            };
+        }
+        // If one of those drops panics, the guard still releases the slots: the remaining old
+        // values are leaked instead of being dropped a second time by `Drop for Fifo5`.
+        let _release = ReleaseOnDrop {
+            cursor: &consumer.pop_cursor,
+            to: pop_val.wrapping_add(count),
+        };
+        for i in 0..count {
+            unsafe {
+                self.slot_ptr(pop_val.wrapping_add(i) % self.capacity)
+                    .drop_in_place()
+            };
+        }
+        count
+    }
+
+    /// Moves up to `out.len()` items into the front of `out` and releases them with a single
+    /// store on `pop_cursor`. Returns the count and whether the batch wrapped, i.e. came out
+    /// of the ring as two runs (the tail of the buffer, then its start).
+    ///
+    /// Never allocates, and `out` is taken as uninitialized: nothing in it is dropped, and
+    /// the caller owns the `count` items written to its front. In `out` they are always
+    /// contiguous; the flag is for callers whose cost depends on the ring layout, e.g. a
+    /// loop that expects one run per cycle and wants to notice when it isn't. Consumer
+    /// thread only.
+    pub fn pop_batch_into(&self, out: &mut [MaybeUninit<T>]) -> (usize, bool) {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+
+        let mut cached_push = unsafe { *consumer.cached_push.get() };
+
+        if cached_push.wrapping_sub(pop_val) < out.len() {
+            cached_push = self.producer.0.push_cursor.load(Ordering::Acquire);
+            unsafe { *consumer.cached_push.get() = cached_push };
+        }
+
+        let count = cached_push.wrapping_sub(pop_val).min(out.len());
+        if count == 0 {
+            return (0, false);
+        }
+
+        // SAFETY: The `count` slots starting at pop_val hold published values owned by the
+        // consumer; they are moved out, and count as uninitialized in the ring from now on.
+        unsafe { self.read_run(pop_val, out.as_mut_ptr().cast(), count) };
+        consumer
+            .pop_cursor
+            .store(pop_val.wrapping_add(count), Ordering::Release);
+        (count, self.runs(pop_val, count).1 > 0)
+    }
+
+    /// Returns the queued items from the front of the queue up to the end of the ring buffer,
+    /// without popping them. Like `VecDeque::as_slices`, a run that wraps is cut at the end of
+    /// the buffer; after [`consume`](Self::consume) the next call returns the rest.
+    ///
+    /// Consumer thread only. The slice is valid until the next `consume` or pop.
+    pub fn peek_contiguous(&self) -> &[T] {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Acquire);
+        unsafe { *consumer.cached_push.get() = push_val };
+
+        let loc = pop_val % self.capacity;
+        let len = push_val.wrapping_sub(pop_val).min(self.capacity - loc);
+        // SAFETY: The slots loc..loc + len are published, contiguous, and owned by the
+        // consumer; the producer won't touch them until pop_cursor moves past them.
+        unsafe { core::slice::from_raw_parts(self.slot_ptr(loc), len) }
+    }
+
+    /// All queued items as two slices, front first, like `VecDeque::as_slices`: the run up to
+    /// the end of the ring buffer and the wrapped-around rest (empty if nothing wraps).
+    ///
+    /// Consumer thread only. Items the producer pushes afterwards aren't included, and the
+    /// slices are valid until the next pop or `consume`.
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Acquire);
+        unsafe { *consumer.cached_push.get() = push_val };
+
+        let (front, back) = self.runs(pop_val, push_val.wrapping_sub(pop_val));
+        // SAFETY: Both runs are published slots owned by the consumer, as in
+        // `peek_contiguous`.
+        unsafe {
+            (
+                core::slice::from_raw_parts(self.slot_ptr(front.0), front.1),
+                core::slice::from_raw_parts(self.slot_ptr(0), back),
+            )
+        }
+    }
+
+    /// Mutable [`as_slices`](Self::as_slices). `&mut self` rules out a concurrent push or
+    /// pop, so both sides must be quiesced (as with `resize`).
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let pop_val = *self.consumer.0.pop_cursor.get_mut();
+        let push_val = *self.producer.0.push_cursor.get_mut();
+
+        let (front, back) = self.runs(pop_val, push_val.wrapping_sub(pop_val));
+        // SAFETY: As above, and the two runs never overlap: together they are at most
+        // `capacity` slots, the first ending at the end of the ring, the second starting at 0.
+        unsafe {
+            (
+                core::slice::from_raw_parts_mut(self.slot_ptr(front.0), front.1),
+                core::slice::from_raw_parts_mut(self.slot_ptr(0), back),
+            )
+        }
+    }
+
+    // Splits `len` items starting at cursor `pos` into (start slot, length) of the run up to
+    // the end of the ring, and the length of the part that wraps to slot 0.
+    fn runs(&self, pos: usize, len: usize) -> ((usize, usize), usize) {
+        let loc = pos % self.capacity;
+        let first = len.min(self.capacity - loc);
+        ((loc, first), len - first)
+    }
+
+    /// Returns a reference to the front element without popping it, like `VecDeque::front`.
+    ///
+    /// Consumer thread only; the reference is valid until the consumer pops.
+    pub fn front(&self) -> Option<&T> {
+        self.peek_contiguous().first()
+    }
+
+    /// Runs `f` on the front element in place, without popping it, and returns its result;
+    /// `None` if the queue is empty. Useful to read or reset a large item (a buffer from a
+    /// pool, say) where it sits instead of moving it out. Consumer thread only.
+    ///
+    /// Changes stay in the slot: a later [`pop`](Self::pop) returns the modified item.
+    ///
+    /// # Safety
+    ///
+    /// No reference obtained from [`front`](Self::front),
+    /// [`peek_contiguous`](Self::peek_contiguous) or [`as_slices`](Self::as_slices) may be
+    /// alive during the call, since `f` gets a `&mut` to the same element.
+    pub unsafe fn with_front<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let pop_val = self.front_cursor()?;
+        // SAFETY: The front slot holds a published item that only the consumer may touch
+        // until pop_cursor moves, and the caller rules out shared references to it.
+        Some(f(unsafe { &mut *self.slot_ptr(pop_val % self.capacity) }))
+    }
+
+    /// Drops the first `n` queued items and releases their slots to the producer, typically
+    /// after handling a slice from [`peek_contiguous`](Self::peek_contiguous).
+    ///
+    /// Panics if fewer than `n` items are queued. Consumer thread only.
+    pub fn consume(&self, n: usize) {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Acquire);
+        assert!(
+            n <= push_val.wrapping_sub(pop_val),
+            "consume({n}) past the queued items"
+        );
+        // `pop` takes any pop cursor short of the cached push cursor as non-empty, so the
+        // cache must not fall behind the cursor we are about to store.
+        unsafe { *consumer.cached_push.get() = push_val };
+
+        let _release = ReleaseOnDrop {
+            cursor: &consumer.pop_cursor,
+            to: pop_val.wrapping_add(n),
+        };
+        if core::mem::needs_drop::<T>() {
+            for i in 0..n {
+                unsafe {
+                    self.slot_ptr(pop_val.wrapping_add(i) % self.capacity)
+                        .drop_in_place()
+                };
             }
         }
+    }
 
-        let loc = push_val % self.capacity;
-        // SAFETY: Slot is free. Write data content directly.
-        // We cast the const pointer to mutable because we know we own this slot via SPSC logic.
+    /// Writes a byte to every page of the free part of the ring, so the first lap of pushes
+    /// doesn't take a page fault (and TLB miss) each time it reaches a fresh page. Call it
+    /// once before going live: it costs a pass over the whole ring at startup in exchange
+    /// for steady latencies afterwards.
+    ///
+    /// Only free slots are touched, and only as raw bytes: queued items, the cursors and
+    /// what the queue considers initialized are all unchanged. Producer thread only (or
+    /// before the queue is shared), since the free slots are the producer's to write; the
+    /// consumer may keep popping meanwhile.
+    pub fn warmup(&self) {
+        // The smallest common page size; larger pages just get touched more than once.
+        const PAGE: usize = 4096;
+
+        // Acquire, as in `push`: the consumer is done reading every slot before pop_val.
+        let pop_val = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Relaxed);
+        let free = self.capacity - push_val.wrapping_sub(pop_val);
+        let ((loc, first), rest) = self.runs(push_val, free);
+
+        for (start, count) in [(loc, first), (0, rest)] {
+            let bytes = count * mem::size_of::<T>();
+            if bytes == 0 {
+                continue;
+            }
+            let base = self.slot_ptr(start).cast::<u8>();
+            // Every PAGE bytes plus the last byte, which may sit on a page of its own.
+            for offset in (0..bytes).step_by(PAGE).chain([bytes - 1]) {
+                // SAFETY: The byte lies in a free slot, which holds no item and which only the
+                // producer (us) writes. Volatile so the store isn't optimized out.
+                unsafe { ptr::write_volatile(base.add(offset), 0) };
+            }
+        }
+    }
+
+    /// Moves the queued items into a freshly allocated ring of `new_capacity` slots, front
+    /// first, so a queue that keeps filling up can grow (or shrink back down).
+    ///
+    /// `&mut self` guarantees neither side is mid-operation. Fails without touching the
+    /// queue if `new_capacity` is zero or smaller than [`len`](Self::len).
+    pub fn resize(&mut self, new_capacity: usize) -> Result<(), FifoError> {
+        let pop_val = *self.consumer.0.pop_cursor.get_mut();
+        let push_val = *self.producer.0.push_cursor.get_mut();
+        let len = push_val.wrapping_sub(pop_val);
+        if new_capacity == 0 {
+            return Err(FifoError::ZeroCapacity);
+        }
+        if new_capacity < len {
+            return Err(FifoError::BelowLen {
+                capacity: new_capacity,
+                len,
+            });
+        }
+
+        let ring = Self::alloc_ring(new_capacity);
+        // SAFETY: pop..push are the live items, moved (not copied) to 0..len of the new ring;
+        // read_run splits a run that wraps around the old buffer. The old ring is dropped as
+        // plain MaybeUninit memory, so nothing is dropped twice.
+        unsafe { self.read_run(pop_val, UnsafeCell::raw_get(ring.as_ptr()).cast(), len) };
+        self.ring = ring;
+        self.capacity = new_capacity;
+
+        *self.consumer.0.pop_cursor.get_mut() = 0;
+        *self.consumer.0.cached_push.get_mut() = len;
+        *self.producer.0.push_cursor.get_mut() = len;
+        *self.producer.0.cached_pop.get_mut() = 0;
+        // The old peak may not fit the new ring; start over from what is queued now.
+        #[cfg(feature = "stats")]
+        {
+            *self.high_water.0.get_mut() = len;
+        }
+        Ok(())
+    }
+
+    /// Shrinks the ring to `new_capacity` slots to give memory back after a burst, like
+    /// `Vec::shrink_to` but exact. Never grows: a `new_capacity` at or above the current
+    /// capacity leaves the queue alone.
+    ///
+    /// Fails like [`resize`](Self::resize), and without touching the queue, if the queued
+    /// items don't fit in `new_capacity`.
+    pub fn shrink_to(&mut self, new_capacity: usize) -> Result<(), FifoError> {
+        if new_capacity >= self.capacity {
+            return Ok(());
+        }
+        self.resize(new_capacity)
+    }
+
+    /// Consumes the queue, returning the queued items in FIFO order. The inverse of
+    /// collecting into a `Fifo5`.
+    pub fn into_vec(mut self) -> Vec<T> {
+        let pop_val = *self.consumer.0.pop_cursor.get_mut();
+        let push_val = *self.producer.0.push_cursor.get_mut();
+        let len = push_val.wrapping_sub(pop_val);
+
+        let mut items = Vec::with_capacity(len);
+        // SAFETY: pop..push are the live items; they are moved into the Vec's spare capacity,
+        // then the cursors are closed up so our Drop sees an empty queue and drops nothing.
         unsafe {
-            let slot_ptr = self.ring.as_ptr().add(loc) as *mut MaybeUninit<T>;
-            slot_ptr.write(MaybeUninit::new(item));
+            self.read_run(pop_val, items.as_mut_ptr(), len);
+            items.set_len(len);
         }
+        *self.consumer.0.pop_cursor.get_mut() = push_val;
+        items
+    }
 
-        producer.push_cursor.store(push_val + 1, Ordering::Release);
-        return true;
+    /// Returns an iterator that pops everything queued at the time of the call.
+    ///
+    /// The push cursor is snapshotted up front, so a live producer can't keep the iteration
+    /// going forever. Consumer thread only, like `pop`.
+    pub fn drain(&self) -> Drain<'_, T> {
+        let pop_val = self.consumer.0.pop_cursor.load(Ordering::Relaxed);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Acquire);
+        Drain {
+            queue: self,
+            remaining: push_val.wrapping_sub(pop_val),
+        }
+    }
+
+    /// Drops everything queued at the time of the call and releases the slots to the producer.
+    ///
+    /// Consumer thread only (or with both sides quiesced). Items pushed concurrently after
+    /// the push cursor is read are left in the queue.
+    pub fn clear(&self) {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Acquire);
+
+        if core::mem::needs_drop::<T>() {
+            for i in 0..push_val.wrapping_sub(pop_val) {
+                // SAFETY: Every slot in pop..push holds a published value owned by the consumer.
+                unsafe {
+                    self.slot_ptr(pop_val.wrapping_add(i) % self.capacity)
+                        .drop_in_place()
+                };
+            }
+        }
+
+        unsafe { *consumer.cached_push.get() = push_val };
+        consumer.pop_cursor.store(push_val, Ordering::Release);
+    }
+
+    fn alloc_ring(capacity: usize) -> Box<[UnsafeCell<MaybeUninit<T>>]> {
+        let mut ring = Vec::with_capacity(capacity);
+        ring.resize_with(capacity, || UnsafeCell::new(MaybeUninit::uninit()));
+        ring.into_boxed_slice()
+    }
+
+    /// Pointer to slot `loc`, derived from the whole ring rather than `&self.ring[loc]`, so
+    /// `write_run` / `read_run` may run past it into the following slots. Which side may
+    /// access a slot is decided by the SPSC protocol, not by this pointer.
+    fn slot_ptr(&self, loc: usize) -> *mut T {
+        debug_assert!(loc < self.capacity);
+        unsafe { UnsafeCell::raw_get(self.ring.as_ptr().add(loc)).cast() }
+    }
+
+    /// Copies `count` values from `src` into the ring starting at cursor `pos`. A run that
+    /// crosses the end of the ring is split into two `copy_nonoverlapping` calls.
+    unsafe fn write_run(&self, pos: usize, src: *const T, count: usize) {
+        let loc = pos % self.capacity;
+        let first = count.min(self.capacity - loc);
+        unsafe {
+            ptr::copy_nonoverlapping(src, self.slot_ptr(loc), first);
+            ptr::copy_nonoverlapping(src.add(first), self.slot_ptr(0), count - first);
+        }
+    }
+
+    /// Copies `count` values out of the ring starting at cursor `pos` into `dst`,
+    /// handling the wrap the same way as `write_run`.
+    unsafe fn read_run(&self, pos: usize, dst: *mut T, count: usize) {
+        let loc = pos % self.capacity;
+        let first = count.min(self.capacity - loc);
+        unsafe {
+            ptr::copy_nonoverlapping(self.slot_ptr(loc), dst, first);
+            ptr::copy_nonoverlapping(self.slot_ptr(0), dst.add(first), count - first);
+        }
     }
 }
 
-// Drop glue: We must drop elements strictly remaining in the queue.
-impl<T> Drop for Fifo5<T> {
+// Hints the CPU to pull `ptr`'s cache line into L1. Only a hint: it never faults, and the
+// line may hold anything (here, possibly an unpublished slot) since nothing is read from it.
+// A no-op on targets without a stable prefetch instruction.
+#[cfg(feature = "prefetch")]
+#[inline(always)]
+fn prefetch_read<T>(ptr: *const T) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        use core::arch::x86_64::{_MM_HINT_T0, _mm_prefetch};
+        // SAFETY: SSE is part of the x86_64 baseline, and prefetching any address is sound.
+        #[allow(unused_unsafe)]
+        unsafe {
+            _mm_prefetch::<_MM_HINT_T0>(ptr.cast())
+        };
+    }
+    #[cfg(target_arch = "aarch64")]
+    // SAFETY: PRFM is a hint and doesn't fault, whatever the address.
+    unsafe {
+        core::arch::asm!(
+            "prfm pldl1keep, [{0}]",
+            in(reg) ptr,
+            options(nostack, readonly, preserves_flags)
+        );
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let _ = ptr;
+}
+
+// Stores `to` into `cursor` with Release when dropped, including during unwinding.
+struct ReleaseOnDrop<'a> {
+    cursor: &'a AtomicUsize,
+    to: usize,
+}
+
+impl Drop for ReleaseOnDrop<'_> {
     fn drop(&mut self) {
-        let pop = self.consumer.0.pop_cursor.load(Ordering::Relaxed);
-        let push = self.producer.0.push_cursor.load(Ordering::Relaxed);
+        self.cursor.store(self.to, Ordering::Release);
+    }
+}
 
-        // In a real implementation we would drop items from pop..push
-        // For benchmarking usize, it's a no-op, but for correctness with T it is required.
-        if std::mem::needs_drop::<T>() {
-            for i in pop..push {
-                let loc = i % self.capacity;
-                unsafe { self.ring[loc].as_mut_ptr().drop_in_place() };
-            }
+impl<T> Default for Fifo5<T> {
+    fn default() -> Fifo5<T> {
+        Fifo5::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// Collects into a queue whose capacity is the item count rounded up to a power of two.
+impl<T> FromIterator<T> for Fifo5<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Fifo5<T> {
+        let items: Vec<T> = iter.into_iter().collect();
+        let capacity = items.len().max(1).next_power_of_two();
+        match Fifo5::new_filled(capacity, items) {
+            Ok(queue) => queue,
+            Err(_) => unreachable!("capacity covers every item"),
         }
     }
 }
 
-pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
-    let queue = Arc::new(Fifo5::<usize>::new(capacity));
-    let done = Arc::new(AtomicBool::new(false));
-    let queue_consumer = queue.clone();
-    let done_consumer = done.clone();
-
-    let consumer = thread::spawn(move || {
-        let mut expected = 0;
-        loop {
-            if let Some(val) = queue_consumer.pop() {
-                assert_eq!(val, expected);
-                expected += 1;
-            } else {
-                if done_consumer.load(Ordering::Acquire) {
-                    if queue_consumer.pop().is_none() {
-                        break;
-                    }
-                } else {
-                    std::hint::spin_loop();
-                }
+/// Blocking bulk push: spins until every item is in, so it needs a consumer popping on
+/// another thread once the queue fills up. Implemented for `&Fifo5` since a consumer can't be
+/// running while someone holds `&mut Fifo5`. Use [`Fifo5::try_extend`] to stop at full instead.
+impl<T> Extend<T> for &Fifo5<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut iter = iter.into_iter().peekable();
+        while iter.peek().is_some() {
+            iter = self.try_extend(iter);
+            if iter.peek().is_some() {
+                core::hint::spin_loop();
             }
         }
-    });
+    }
+}
+
+/// Iterator returned by [`Fifo5::drain`].
+///
+/// It only holds a shared borrow, but every `next` pops from the queue, so it must only be
+/// iterated on the consumer thread.
+pub struct Drain<'a, T> {
+    queue: &'a Fifo5<T>,
+    remaining: usize,
+}
 
-    let start = Instant::now();
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        self.queue.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Drain<'_, T> {}
+
+/// Guard returned by [`Fifo5::reserve`] pointing at an unpublished slot.
+pub struct WriteSlot<'a, T> {
+    queue: &'a Fifo5<T>,
+    pos: usize,
+}
+
+impl<T> WriteSlot<'_, T> {
+    /// Pointer to the reserved slot. The memory is uninitialized until written.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.queue.slot_ptr(self.pos % self.queue.capacity)
+    }
+
+    /// Publishes the slot to the consumer with a `Release` store on `push_cursor`.
+    ///
+    /// # Safety
+    ///
+    /// A valid `T` must have been written through [`as_mut_ptr`](Self::as_mut_ptr); the
+    /// consumer will read it as initialized.
+    pub unsafe fn commit(self) {
+        self.queue
+            .producer
+            .0
+            .push_cursor
+            .store(self.pos.wrapping_add(1), Ordering::Release);
+        #[cfg(feature = "stats")]
+        self.queue.record_high_water(self.pos.wrapping_add(1));
+    }
+}
 
-    for i in 0..iters {
-        loop {
-            if queue.push(i) {
-                break;
+// Drop glue: We must drop elements strictly remaining in the queue.
+impl<T> Drop for Fifo5<T> {
+    fn drop(&mut self) {
+        let pop = self.consumer.0.pop_cursor.load(Ordering::Relaxed);
+        let push = self.producer.0.push_cursor.load(Ordering::Relaxed);
+
+        // Drop whatever is still queued. `needs_drop` is a compile-time constant, so for
+        // `Copy` payloads like `usize` the whole loop compiles away and this only frees the
+        // ring.
+        if core::mem::needs_drop::<T>() {
+            for i in 0..push.wrapping_sub(pop) {
+                let loc = pop.wrapping_add(i) % self.capacity;
+                unsafe { self.slot_ptr(loc).drop_in_place() };
             }
-            std::hint::spin_loop();
         }
     }
+}
+
+// Only the slots between the cursors hold values; the rest of the ring is uninitialized
+// and must not be read.
+impl<T> fmt::Debug for Fifo5<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pop_val = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Acquire);
+        debug_queue(f, "Fifo5", Some(self.capacity), pop_val, push_val)
+    }
+}
+
+#[cfg(feature = "bench")]
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    crate::bench::bench("Fifo5", Arc::new(Fifo5::<usize>::new(capacity)), iters)
+}
 
-    done.store(true, Ordering::Release);
-    consumer.join().unwrap();
+/// [`run_benchmark`] with 128-byte items, so a large ring no longer fits in cache and the
+/// consumer streams from memory. Compare builds with and without the `prefetch` feature.
+#[cfg(feature = "bench")]
+pub fn run_large_payload_benchmark(iters: usize, capacity: usize) -> f64 {
+    let queue = Arc::new(Fifo5::<[usize; 16]>::new(capacity));
+    let consumer = queue.clone();
+    let label = if cfg!(feature = "prefetch") {
+        "Fifo5 128-byte items (prefetch)"
+    } else {
+        "Fifo5 128-byte items"
+    };
+    crate::bench::bench_fn::<_, _, crate::backoff::SpinBackoff>(
+        label,
+        iters,
+        move |i| queue.push([i; 16]).map_err(|item| item[0]),
+        move || consumer.pop().map(|item| item[15]),
+    )
+}
 
-    let duration = start.elapsed();
-    let secs = duration.as_secs_f64();
-    println!("Fifo5 Time: {:.4}s, Iters: {}", secs, iters);
+/// A bursty, mostly idle workload consumed three ways: pure spinning on `pop`, parking in
+/// `pop_blocking`, and `recv_adaptive(spins)`. See [`crate::bench::bench_wakeup`].
+#[cfg(feature = "bench")]
+pub fn run_wakeup_benchmark(capacity: usize, spins: u32) -> [crate::bench::Wakeup; 3] {
+    use std::time::Duration;
 
-    (iters as f64) / secs
+    let run = |label: &str, recv: fn(&Fifo5<u64>, u32) -> u64| {
+        crate::bench::bench_wakeup(
+            label,
+            Arc::new(Fifo5::new(capacity)),
+            |queue: &Fifo5<u64>, item| queue.push_blocking(item),
+            move |queue| recv(queue, spins),
+            2_000,
+            16,
+            Duration::from_micros(100),
+        )
+    };
+    [
+        run("Fifo5 spin", |queue, _| {
+            loop {
+                if let Some(item) = queue.pop() {
+                    break item;
+                }
+                core::hint::spin_loop();
+            }
+        }),
+        run("Fifo5 park", |queue, _| queue.pop_blocking()),
+        run(
+            &format!("Fifo5 adaptive ({spins} spins)"),
+            |queue, spins| queue.recv_adaptive(spins),
+        ),
+    ]
 }
@@ -0,0 +1,147 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "bench")]
+use std::sync::Arc;
+
+use crate::cache_padded::CachePadded;
+use crate::queue::debug_queue;
+
+struct ProducerFields {
+    push_cursor: AtomicUsize,
+    cached_pop: UnsafeCell<usize>,
+}
+
+struct ConsumerFields {
+    pop_cursor: AtomicUsize,
+    cached_push: UnsafeCell<usize>,
+}
+
+/// [`Fifo5`](crate::Fifo5)'s push / pop with the cursors advanced by `fetch_add(1, Release)`
+/// instead of a `load` + `store`, to measure what the read-modify-write costs.
+///
+/// Each cursor still has a single writer, so the atomic RMW buys nothing for correctness;
+/// the question is only whether a target executes it as cheaply as the plain store. On
+/// x86-64 it compiles to a `lock`-prefixed add, which is also a full barrier, where the
+/// `Release` store is a plain `mov`. Everything else (shadow cursors, padding, the ring)
+/// matches Fifo5, so the benchmark difference is the cursor update alone.
+pub struct Fifo5FetchAdd<T> {
+    capacity: usize,
+    ring: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    producer: CachePadded<ProducerFields>,
+    consumer: CachePadded<ConsumerFields>,
+}
+
+unsafe impl<T: Send> Sync for Fifo5FetchAdd<T> {}
+unsafe impl<T: Send> Send for Fifo5FetchAdd<T> {}
+
+impl<T> Fifo5FetchAdd<T> {
+    /// Takes `capacity` as is, like [`Fifo5::new`](crate::Fifo5::new).
+    pub fn new(capacity: usize) -> Fifo5FetchAdd<T> {
+        let mut ring = Vec::with_capacity(capacity);
+        ring.resize_with(capacity, || UnsafeCell::new(MaybeUninit::uninit()));
+
+        Fifo5FetchAdd {
+            capacity,
+            ring: ring.into_boxed_slice(),
+            producer: CachePadded(ProducerFields {
+                push_cursor: AtomicUsize::new(0),
+                cached_pop: UnsafeCell::new(0),
+            }),
+            consumer: CachePadded(ConsumerFields {
+                pop_cursor: AtomicUsize::new(0),
+                cached_push: UnsafeCell::new(0),
+            }),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns `Err(item)` if the queue is full. Producer thread only.
+    #[inline]
+    pub fn push(&self, item: T) -> Result<(), T> {
+        let producer = &self.producer.0;
+        let push_val = producer.push_cursor.load(Ordering::Relaxed);
+
+        let cached_pop = unsafe { *producer.cached_pop.get() };
+        if push_val.wrapping_sub(cached_pop) >= self.capacity && !self.refresh_cached_pop(push_val)
+        {
+            return Err(item);
+        }
+
+        // SAFETY: The slot is free and owned by the producer, as in Fifo5.
+        unsafe { self.slot_ptr(push_val % self.capacity).write(item) };
+        producer.push_cursor.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns `None` if the queue is empty. Consumer thread only.
+    #[inline]
+    pub fn pop(&self) -> Option<T> {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+
+        let cached_push = unsafe { *consumer.cached_push.get() };
+        if pop_val == cached_push && !self.refresh_cached_push(pop_val) {
+            return None;
+        }
+
+        // SAFETY: The slot holds a published value owned by the consumer, as in Fifo5.
+        let value = unsafe { self.slot_ptr(pop_val % self.capacity).read() };
+        consumer.pop_cursor.fetch_add(1, Ordering::Release);
+        Some(value)
+    }
+
+    #[cold]
+    fn refresh_cached_push(&self, pop_val: usize) -> bool {
+        let actual_push = self.producer.0.push_cursor.load(Ordering::Acquire);
+        unsafe { *self.consumer.0.cached_push.get() = actual_push };
+        pop_val != actual_push
+    }
+
+    #[cold]
+    fn refresh_cached_pop(&self, push_val: usize) -> bool {
+        let actual_pop = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+        unsafe { *self.producer.0.cached_pop.get() = actual_pop };
+        push_val.wrapping_sub(actual_pop) < self.capacity
+    }
+
+    fn slot_ptr(&self, loc: usize) -> *mut T {
+        UnsafeCell::raw_get(&self.ring[loc]).cast()
+    }
+}
+
+impl<T> Drop for Fifo5FetchAdd<T> {
+    fn drop(&mut self) {
+        if core::mem::needs_drop::<T>() {
+            let mut pos = *self.consumer.0.pop_cursor.get_mut();
+            let push = *self.producer.0.push_cursor.get_mut();
+            while pos != push {
+                unsafe { self.slot_ptr(pos % self.capacity).drop_in_place() };
+                pos = pos.wrapping_add(1);
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for Fifo5FetchAdd<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pop_val = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Acquire);
+        debug_queue(f, "Fifo5FetchAdd", Some(self.capacity), pop_val, push_val)
+    }
+}
+
+#[cfg(feature = "bench")]
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    crate::bench::bench(
+        "Fifo5FetchAdd",
+        Arc::new(Fifo5FetchAdd::<usize>::new(capacity)),
+        iters,
+    )
+}
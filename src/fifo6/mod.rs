@@ -1,23 +1,69 @@
-use std::cell::UnsafeCell;
-use std::mem::MaybeUninit;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "bench")]
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::thread;
-use std::time::Instant;
 
-/// Wrapper to force alignment to 128 bytes.
-#[repr(align(128))]
-struct CachePadded<T>(T);
+use crate::cache_padded::CachePadded;
+use crate::error::FifoError;
+use crate::queue::debug_queue;
 
-struct Slot<T> {
-    turn: AtomicUsize,
-    data: UnsafeCell<MaybeUninit<T>>,
+/// One ring entry: the value plus the turn counter that says which lap it belongs to.
+///
+/// Only public so rings can be placed in caller-provided memory, see
+/// [`Fifo6::from_raw_slots`] and [`Fifo6::from_raw_parts`]. `repr(C)`: the turn (an
+/// `AtomicUsize`) is at offset 0 and the value follows at the next multiple of `T`'s
+/// alignment, so the layout is the same in every build for the same target and `T`.
+#[repr(C)]
+pub struct Slot<T> {
+    pub(crate) turn: AtomicUsize,
+    pub(crate) data: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> Slot<T> {
+    /// An empty slot, usable in `static` initializers. The turn is reset by the queue that
+    /// takes ownership of it.
+    pub const fn new() -> Slot<T> {
+        Slot {
+            turn: AtomicUsize::new(0),
+            data: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+impl<T> Default for Slot<T> {
+    fn default() -> Slot<T> {
+        Slot::new()
+    }
+}
+
+// Who owns the ring and the cursors, which decides what `drop` cleans up.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Backing {
+    // `new`: a leaked `Box` we free, after dropping the queued items.
+    Heap,
+    // `from_raw_slots`: the caller's `'static` slots; we drop the items but not the memory.
+    Static,
+    // `from_raw_parts`: memory other views may share; we leave both items and memory alone.
+    Raw,
 }
 
 pub struct Fifo6<T> {
     capacity: usize,
-    // The ring buffer of slots.
-    ring: Box<[Slot<T>]>,
+    // `capacity - 1` when the capacity is a power of two, so indexing is a single mask.
+    // `None` falls back to `%` for other sizes. Chosen once in `new` so the branch in the
+    // hot path always goes the same way.
+    mask: Option<usize>,
+    // The ring buffer of slots, see `Backing` for where it lives.
+    ring: NonNull<[Slot<T>]>,
+    backing: Backing,
+    // `(head, tail)` at caller-chosen addresses, from `from_raw_parts`. `None` uses the
+    // inline cursors below. Fixed at construction like `mask`, so the branch is predictable.
+    external_cursors: Option<(NonNull<AtomicUsize>, NonNull<AtomicUsize>)>,
     // Head: Consumer index.
     head: CachePadded<AtomicUsize>,
     // Tail: Producer index.
@@ -28,10 +74,25 @@ unsafe impl<T: Send> Sync for Fifo6<T> {}
 unsafe impl<T: Send> Send for Fifo6<T> {}
 
 impl<T> Fifo6<T> {
+    /// Any `capacity` of at least 2 is used as given, not rounded: powers of two index with
+    /// a mask, other sizes with `%`. Panics on a smaller one; see [`try_new`](Self::try_new).
     pub fn new(capacity: usize) -> Fifo6<T> {
-        // Prepare slots
+        Fifo6::try_new(capacity).unwrap_or_else(|err| panic!("Fifo6: {err}"))
+    }
+
+    /// [`new`](Self::new) for capacities that come from configuration or user input: fails
+    /// with [`FifoError::ZeroCapacity`] or [`FifoError::CapacityTooSmall`] instead of
+    /// panicking.
+    pub fn try_new(capacity: usize) -> Result<Fifo6<T>, FifoError> {
+        // With a single slot "filled this lap" (turn = pos + 1) and "free next lap"
+        // (turn = pos + capacity) are the same value, so the turn check can't tell them apart.
+        match capacity {
+            0 => return Err(FifoError::ZeroCapacity),
+            1 => return Err(FifoError::CapacityTooSmall { capacity, min: 2 }),
+            _ => {}
+        }
 
-        assert!(capacity.is_power_of_two(), "Size must be power of 2!");
+        // Prepare slots
         let mut ring = Vec::with_capacity(capacity);
         for i in 0..capacity {
             ring.push(Slot {
@@ -39,161 +100,344 @@ impl<T> Fifo6<T> {
                 data: UnsafeCell::new(MaybeUninit::uninit()),
             });
         }
-        let ring = ring.into_boxed_slice();
+        let ring = NonNull::from(Box::leak(ring.into_boxed_slice()));
+
+        Ok(Fifo6::from_ring(ring, Backing::Heap))
+    }
 
+    /// Builds a queue on top of caller-provided slots instead of the heap, e.g. a `static`
+    /// array in an embedded build. The capacity is `slots.len()`. Any values left in the
+    /// slots are forgotten, not dropped.
+    pub fn from_raw_slots(slots: &'static mut [Slot<T>]) -> Fifo6<T> {
+        assert!(slots.len() >= 2, "Fifo6 needs at least 2 slots!");
+        for (i, slot) in slots.iter_mut().enumerate() {
+            *slot.turn.get_mut() = i;
+        }
+        Fifo6::from_ring(NonNull::from(slots), Backing::Static)
+    }
+
+    /// Attaches to a queue whose slots and cursors live in memory the caller controls, e.g.
+    /// a shared-memory mapping used by two processes. Nothing is allocated, and dropping the
+    /// returned view neither drops queued items nor frees anything; every view over the
+    /// same memory is the same queue.
+    ///
+    /// Layout: `ring` points to `capacity` consecutive [`Slot<T>`]s (see its layout
+    /// note), aligned to `align_of::<Slot<T>>()`. `head` and `tail` each point to one
+    /// `AtomicUsize` aligned to `align_of::<AtomicUsize>()`; they may be anywhere, but
+    /// keep them on separate cache lines (see [`CACHE_LINE`](crate::cache_padded::CACHE_LINE))
+    /// or producers and consumers will contend on one line.
+    ///
+    /// # Safety
+    ///
+    /// - The memory must hold a valid queue state: written by
+    ///   [`init_raw_parts`](Self::init_raw_parts) or left by
+    ///   [`into_raw_parts`](Self::into_raw_parts), and changed since only through `Fifo6`
+    ///   views with the same `capacity`.
+    /// - `capacity` is at least 2, the pointers meet the layout above, and the memory stays
+    ///   valid and mapped for as long as any view exists.
+    /// - Values of `T` must be meaningful to every thread or process that pops them: across
+    ///   processes that rules out anything holding pointers, handles or other per-process
+    ///   state, and the processes must agree on `T`'s layout.
+    pub unsafe fn from_raw_parts(
+        ring: *mut Slot<T>,
+        capacity: usize,
+        head: *mut AtomicUsize,
+        tail: *mut AtomicUsize,
+    ) -> Fifo6<T> {
+        debug_assert!(capacity >= 2, "Fifo6 needs at least 2 slots!");
+        let ring = NonNull::new(ptr::slice_from_raw_parts_mut(ring, capacity))
+            .expect("Fifo6::from_raw_parts: null ring");
+        let cursors = NonNull::new(head)
+            .zip(NonNull::new(tail))
+            .expect("Fifo6::from_raw_parts: null cursor");
+        let mut queue = Fifo6::from_ring(ring, Backing::Raw);
+        queue.external_cursors = Some(cursors);
+        queue
+    }
+
+    /// [`from_raw_parts`](Self::from_raw_parts) for memory that doesn't hold a queue yet:
+    /// first writes an empty queue into it (each slot's turn and both cursors; the values
+    /// are left uninitialized). Call it once, before any other view attaches.
+    ///
+    /// # Safety
+    ///
+    /// As for `from_raw_parts`, except that the memory may hold anything (it is only
+    /// written), and no other view may be using it.
+    pub unsafe fn init_raw_parts(
+        ring: *mut Slot<T>,
+        capacity: usize,
+        head: *mut AtomicUsize,
+        tail: *mut AtomicUsize,
+    ) -> Fifo6<T> {
+        for i in 0..capacity {
+            unsafe { ptr::addr_of_mut!((*ring.add(i)).turn).write(AtomicUsize::new(i)) };
+        }
+        unsafe {
+            head.write(AtomicUsize::new(0));
+            tail.write(AtomicUsize::new(0));
+            Fifo6::from_raw_parts(ring, capacity, head, tail)
+        }
+    }
+
+    /// Takes the queue apart into `(ring, capacity, head, tail)` for
+    /// [`from_raw_parts`](Self::from_raw_parts), keeping whatever is queued.
+    ///
+    /// The caller takes over the memory and the queued items, which no view frees or drops.
+    /// The ring is the `Box<[Slot<T>]>` of `capacity` slots made by `new` (or the memory the
+    /// queue was built over), and unless the queue came from `from_raw_parts`, each cursor is
+    /// moved into its own `Box<AtomicUsize>`. Reclaim them with `Box::from_raw` once the last
+    /// view is gone, after popping anything that needs dropping. The two boxes can share a
+    /// cache line; place the cursors yourself where that matters.
+    pub fn into_raw_parts(self) -> (*mut Slot<T>, usize, *mut AtomicUsize, *mut AtomicUsize) {
+        // Nothing inline needs dropping; `Drop` would free or drain the ring.
+        let this = ManuallyDrop::new(self);
+        let (head, tail) = this.external_cursors.unwrap_or_else(|| {
+            let boxed = |cursor: &AtomicUsize| {
+                NonNull::from(Box::leak(Box::new(AtomicUsize::new(
+                    cursor.load(Ordering::Relaxed),
+                ))))
+            };
+            (boxed(&this.head.0), boxed(&this.tail.0))
+        });
+        (
+            this.ring.as_ptr().cast(),
+            this.capacity,
+            head.as_ptr(),
+            tail.as_ptr(),
+        )
+    }
+
+    fn from_ring(ring: NonNull<[Slot<T>]>, backing: Backing) -> Fifo6<T> {
+        let capacity = ring.len();
         Fifo6 {
             capacity,
+            mask: capacity.is_power_of_two().then(|| capacity - 1),
             ring,
+            backing,
+            external_cursors: None,
             head: CachePadded(AtomicUsize::new(0)),
             tail: CachePadded(AtomicUsize::new(0)),
         }
     }
 
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[inline]
+    fn ring(&self) -> &[Slot<T>] {
+        // SAFETY: The ring is our own leaked Box, 'static caller memory, or raw memory the
+        // caller keeps valid for as long as this view exists.
+        unsafe { self.ring.as_ref() }
+    }
+
+    #[inline]
+    fn head(&self) -> &AtomicUsize {
+        match self.external_cursors {
+            // SAFETY: Kept valid by the caller of `from_raw_parts`, like the ring.
+            Some((head, _)) => unsafe { head.as_ref() },
+            None => &self.head.0,
+        }
+    }
+
+    #[inline]
+    fn tail(&self) -> &AtomicUsize {
+        match self.external_cursors {
+            // SAFETY: As in `head`.
+            Some((_, tail)) => unsafe { tail.as_ref() },
+            None => &self.tail.0,
+        }
+    }
+
+    /// Maps a cursor position to its slot.
+    ///
+    /// The turn arithmetic is wrapping and doesn't depend on the mask. For non power of two
+    /// capacities the `%` mapping is only continuous until the cursors wrap at `usize::MAX`,
+    /// which is out of reach on 64-bit targets.
+    #[inline]
+    fn index(&self, pos: usize) -> usize {
+        match self.mask {
+            Some(mask) => pos & mask,
+            None => pos % self.capacity,
+        }
+    }
+
     pub fn pop(&self) -> Option<T> {
+        let mut head = self.head().load(Ordering::Relaxed);
         loop {
-            let head = self.head.0.load(Ordering::Relaxed);
-            let index = head & (self.capacity - 1);
-            let slot = &self.ring[index];
-            // let slot = &self.ring[head % self.capacity];
+            let slot = &self.ring()[self.index(head)];
             let turn = slot.turn.load(Ordering::Acquire);
 
             // Calculate the difference between the turn and the head + 1.
             // If turn == head + 1: The slot has data for this lap.
             // If turn == head: The slot is empty (producer hasn't filled it yet).
+            // If turn > head + 1: Another consumer already took this position.
             let diff = turn.wrapping_sub(head.wrapping_add(1));
 
             if diff == 0 {
-                // Try to claim this slot
-                if self
-                    .head
-                    .0
-                    .compare_exchange(
-                        head,
-                        head.wrapping_add(1),
-                        Ordering::SeqCst,
-                        Ordering::Relaxed,
-                    )
-                    .is_ok()
-                {
-                    // Success! Read the data.
-                    let data = unsafe { slot.data.get().read().assume_init() };
-                    // Update turn to next lap for producer
-                    // Current head was H. Turn becomes H + Capacity.
-                    slot.turn
-                        .store(head.wrapping_add(self.capacity), Ordering::Release);
-                    return Some(data);
+                // Try to claim this slot. The CAS only arbitrates between consumers; it never
+                // publishes or consumes data, so it can be Relaxed. The data read below is
+                // ordered by the Acquire load of `turn` above (pairing with the producer's
+                // Release store), and handed back by our own Release store on `turn`.
+                match self.head().compare_exchange_weak(
+                    head,
+                    head.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // Success! Read the data.
+                        let data = unsafe { slot.data.get().read().assume_init() };
+                        // Update turn to next lap for producer
+                        // Current head was H. Turn becomes H + Capacity.
+                        slot.turn
+                            .store(head.wrapping_add(self.capacity), Ordering::Release);
+                        return Some(data);
+                    }
+                    // Lost the race to another consumer: retry from the head it published.
+                    Err(current) => head = current,
                 }
             } else if (diff as isize) < 0 {
-                // Slot is empty. If calculating for MPMC, we might retry.
-                // For SPSC, if head catches up to tail logic (via turn), it means empty.
-                // diff < 0 means turn < head + 1.
-                // e.g. turn = head (0 vs 1). Empty.
-                return None;
+                // The slot hasn't been filled for this lap. That only means "empty" if no
+                // producer has claimed this position yet; otherwise a push is mid-write and
+                // we wait for it instead of reporting a spurious empty.
+                // Only compared, never used to read data, so Relaxed is enough.
+                if self.tail().load(Ordering::Relaxed) == head {
+                    return None;
+                }
+                core::hint::spin_loop();
+                head = self.head().load(Ordering::Relaxed);
             } else {
-                // diff > 0. Head fell behind? (MPMC race) or logic error.
-                // In SPSC buffer this shouldn't happen unless lap wrapped?
-                // Just retry.
+                // diff > 0: our head is stale (MPMC race). Reload and retry.
+                head = self.head().load(Ordering::Relaxed);
             }
         }
     }
 
-    pub fn push(&self, item: T) -> bool {
+    /// Returns `Err(item)` when the slot for this lap is still occupied (queue full).
+    pub fn push(&self, item: T) -> Result<(), T> {
+        let mut tail = self.tail().load(Ordering::Relaxed);
         loop {
-            let tail = self.tail.0.load(Ordering::Relaxed);
-            let slot = &self.ring[tail % self.capacity];
+            let slot = &self.ring()[self.index(tail)];
             let turn = slot.turn.load(Ordering::Acquire);
 
             // If turn == tail: The slot is free for this lap.
-            // If turn == tail + 1: The slot is full (consumer hasn't taken it).
+            // If turn == tail + 1 - capacity: The consumer hasn't freed it from the
+            // previous lap yet (turn lags behind tail, so the difference is negative).
+            // If turn > tail: Another producer already claimed this position.
             let diff = turn.wrapping_sub(tail);
 
             if diff == 0 {
-                // Try to claim
-                if self
-                    .tail
-                    .0
-                    .compare_exchange(
-                        tail,
-                        tail.wrapping_add(1),
-                        Ordering::SeqCst,
-                        Ordering::Relaxed,
-                    )
-                    .is_ok()
-                {
-                    // Success! Write data.
-                    unsafe { slot.data.get().write(MaybeUninit::new(item)) };
-                    // Update turn for consumer: becomes tail + 1
-                    slot.turn.store(tail.wrapping_add(1), Ordering::Release);
-                    return true;
+                // Try to claim. Relaxed for the same reason as in `pop`: the Acquire load of
+                // `turn` already ordered us after the consumer's read of last lap's value.
+                match self.tail().compare_exchange_weak(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // Success! Write data.
+                        unsafe { slot.data.get().write(MaybeUninit::new(item)) };
+                        // Update turn for consumer: becomes tail + 1
+                        slot.turn.store(tail.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    // Lost the race to another producer: retry from the tail it published.
+                    Err(current) => tail = current,
                 }
             } else if (diff as isize) < 0 {
-                // Slot is full.
-                // turn < tail. e.g. turn 0, tail 0 (ok). turn 1, tail 0 (diff 1).
-                // Wait, turn is tail + 1 when full.
-                // So diff would be 1.
-                // If diff < 0? This means tail Wrapped?
-                // Actually:
-                // Push: turn starts at `i`. we write to `i`. tail is `i`. turn==tail.
-                // Set turn to `i+1`.
-                // Next push (lap 2): tail `i + cap`. turn needs to be `i + cap`.
-                // If turn is still `i + 1`, then `i+1 - (i+cap)` is negative.
-                // So diff < 0 implies "Slot Full / Turn lagged".
-
-                // Correction:
-                // Full state: Producer wants to write to `tail`.
-                // Slot turn is from previous lap's write `tail_prev + 1`.
-                // `tail` is `tail_prev + capacity`.
-                // `turn` is way behind `tail`.
-                // So `turn - tail` is negative.
-                return false;
+                // The slot still holds last lap's value. That only means "full" if no
+                // consumer has claimed it yet; otherwise a pop is mid-read, so wait for it.
+                // Like the empty check in `pop`, this is a bare comparison.
+                let head = self.head().load(Ordering::Relaxed);
+                if head.wrapping_add(self.capacity) == tail {
+                    return Err(item);
+                }
+                core::hint::spin_loop();
+                tail = self.tail().load(Ordering::Relaxed);
             } else {
-                // diff > 0. Tail fell behind. Retry.
+                // diff > 0: our tail is stale (MPMC race). Reload and retry.
+                tail = self.tail().load(Ordering::Relaxed);
             }
         }
     }
 }
 
-pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
-    let queue = Arc::new(Fifo6::<usize>::new(capacity));
-    let done = Arc::new(AtomicBool::new(false));
-    let queue_consumer = queue.clone();
-    let done_consumer = done.clone();
-
-    let consumer = thread::spawn(move || {
-        let mut expected = 0;
-        loop {
-            if let Some(val) = queue_consumer.pop() {
-                assert_eq!(val, expected);
-                expected += 1;
-            } else {
-                if done_consumer.load(Ordering::Acquire) {
-                    if queue_consumer.pop().is_none() {
-                        break;
-                    }
-                } else {
-                    std::hint::spin_loop();
-                }
-            }
+// Drop glue: with `&mut self` no push or pop is in flight, so every position in
+// head..tail was written by a completed push and not yet read. A `from_raw_parts` view
+// owns neither the items nor the memory, so it leaves both alone.
+impl<T> Drop for Fifo6<T> {
+    fn drop(&mut self) {
+        if self.backing == Backing::Raw {
+            return;
         }
-    });
-
-    let start = Instant::now();
-
-    for i in 0..iters {
-        loop {
-            if queue.push(i) {
-                break;
+        if core::mem::needs_drop::<T>() {
+            let tail = *self.tail.0.get_mut();
+            let mut pos = *self.head.0.get_mut();
+            while pos != tail {
+                let index = self.index(pos);
+                let slot = unsafe { &mut self.ring.as_mut()[index] };
+                // An occupied slot carries the turn its push published: pos + 1.
+                debug_assert_eq!(*slot.turn.get_mut(), pos.wrapping_add(1));
+                unsafe { slot.data.get_mut().assume_init_drop() };
+                pos = pos.wrapping_add(1);
             }
-            std::hint::spin_loop();
         }
+        if self.backing == Backing::Heap {
+            // SAFETY: The ring came from `Box::leak` in `new`.
+            drop(unsafe { Box::from_raw(self.ring.as_ptr()) });
+        }
+    }
+}
+
+// head..tail are the pushed, unread positions once no push or pop is in flight (see Drop).
+impl<T> fmt::Debug for Fifo6<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = self.head().load(Ordering::Acquire);
+        let tail = self.tail().load(Ordering::Acquire);
+        debug_queue(f, "Fifo6", Some(self.capacity), head, tail)
     }
+}
 
-    done.store(true, Ordering::Release);
-    consumer.join().unwrap();
+#[cfg(feature = "bench")]
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    crate::bench::bench("Fifo6", Arc::new(Fifo6::<usize>::new(capacity)), iters)
+}
 
-    let duration = start.elapsed();
-    let secs = duration.as_secs_f64();
-    println!("Fifo6 Time: {:.4}s, Iters: {}", secs, iters);
+/// Multi-producer multi-consumer run, see [`crate::bench::bench_mpmc`].
+#[cfg(feature = "bench")]
+pub fn run_mpmc_benchmark(
+    iters: usize,
+    capacity: usize,
+    producers: usize,
+    consumers: usize,
+) -> f64 {
+    crate::bench::bench_mpmc(
+        "Fifo6",
+        Arc::new(Fifo6::<usize>::new(capacity)),
+        iters,
+        producers,
+        consumers,
+    )
+}
 
-    (iters as f64) / secs
+/// Per-consumer item counts under contention, for comparison with
+/// [`crate::fifo6fair::run_fairness_benchmark`].
+#[cfg(feature = "bench")]
+pub fn run_fairness_benchmark(
+    iters: usize,
+    capacity: usize,
+    producers: usize,
+    consumers: usize,
+) -> Vec<usize> {
+    crate::bench::bench_fairness(
+        "Fifo6",
+        Arc::new(Fifo6::<usize>::new(capacity)),
+        |queue, item| queue.push(item),
+        |queue| queue.pop(),
+        iters,
+        producers,
+        consumers,
+    )
 }
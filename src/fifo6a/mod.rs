@@ -1,20 +1,23 @@
-use std::cell::UnsafeCell;
-use std::mem::MaybeUninit;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem::MaybeUninit;
+#[cfg(feature = "bench")]
 use std::sync::Arc;
-use std::thread;
-use std::time::Instant;
 
-/// Wrapper to force alignment to 128 bytes.
-#[repr(align(128))]
-struct CachePadded<T>(T);
+use crate::backoff::CasBackoff;
+use crate::builder::turn_ring_capacity;
+use crate::cache_padded::CachePadded;
+use crate::error::FifoError;
+use crate::queue::debug_queue;
+use crate::sync::{AtomicUsize, Ordering, UnsafeCell, spin_loop};
 
 struct Slot<T> {
     turn: AtomicUsize,
     data: UnsafeCell<MaybeUninit<T>>,
 }
 
-pub struct Fifo6<T> {
+pub struct Fifo6a<T> {
     capacity: usize,
     // The ring buffer of slots.
     ring: Box<[Slot<T>]>,
@@ -22,16 +25,27 @@ pub struct Fifo6<T> {
     head: CachePadded<AtomicUsize>,
     // Tail: Producer index.
     tail: CachePadded<AtomicUsize>,
+    // Lost head/tail CASes, on its own line so counting doesn't add to the contention.
+    #[cfg(feature = "stats")]
+    cas_retries: CachePadded<core::sync::atomic::AtomicU64>,
 }
 
-unsafe impl<T: Send> Sync for Fifo6<T> {}
-unsafe impl<T: Send> Send for Fifo6<T> {}
+unsafe impl<T: Send> Sync for Fifo6a<T> {}
+unsafe impl<T: Send> Send for Fifo6a<T> {}
 
-impl<T> Fifo6<T> {
-    pub fn new(capacity: usize) -> Fifo6<T> {
-        // Prepare slots
+impl<T> Fifo6a<T> {
+    /// Rounds `capacity` up to the next power of two (slots are found with a mask); see
+    /// [`capacity`](Self::capacity) for the result. Panics if `capacity` is below 2, like
+    /// [`Fifo6::new`](crate::Fifo6::new).
+    pub fn new(capacity: usize) -> Fifo6a<T> {
+        Fifo6a::try_new(capacity).unwrap_or_else(|err| panic!("Fifo6a: {err}"))
+    }
 
-        assert!(capacity.is_power_of_two(), "Size must be power of 2!");
+    /// [`new`](Self::new) that fails with [`FifoError::ZeroCapacity`],
+    /// [`FifoError::CapacityTooSmall`] or [`FifoError::CapacityOverflow`] instead of
+    /// panicking.
+    pub fn try_new(capacity: usize) -> Result<Fifo6a<T>, FifoError> {
+        let capacity = turn_ring_capacity(capacity)?;
         let mut ring = Vec::with_capacity(capacity);
         for i in 0..capacity {
             ring.push(Slot {
@@ -41,17 +55,41 @@ impl<T> Fifo6<T> {
         }
         let ring = ring.into_boxed_slice();
 
-        Fifo6 {
+        Ok(Fifo6a {
             capacity,
             ring,
             head: CachePadded(AtomicUsize::new(0)),
             tail: CachePadded(AtomicUsize::new(0)),
-        }
+            #[cfg(feature = "stats")]
+            cas_retries: CachePadded(core::sync::atomic::AtomicU64::new(0)),
+        })
+    }
+
+    /// Total number of lost head/tail `compare_exchange_weak` races so far, i.e. how often a
+    /// producer or consumer lost a race to one of its peers. Requires the `stats` feature.
+    #[cfg(feature = "stats")]
+    pub fn cas_retries(&self) -> u64 {
+        self.cas_retries
+            .0
+            .load(core::sync::atomic::Ordering::Relaxed)
+    }
+
+    fn lost_cas(&self, backoff: &mut CasBackoff) {
+        #[cfg(feature = "stats")]
+        self.cas_retries
+            .0
+            .fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+        backoff.spin();
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
     }
 
     pub fn pop(&self) -> Option<T> {
+        let mut backoff = CasBackoff::new();
+        let mut head = self.head.0.load(Ordering::Relaxed);
         loop {
-            let head = self.head.0.load(Ordering::Relaxed);
             let index = head & (self.capacity - 1);
             let slot = &self.ring[index];
 
@@ -61,46 +99,61 @@ impl<T> Fifo6<T> {
             // Calculate the difference between the turn and the head + 1.
             // If turn == head + 1: The slot has data for this lap.
             // If turn == head: The slot is empty (producer hasn't filled it yet).
+            // If turn > head + 1: Another consumer already took this position.
             let diff = turn.wrapping_sub(head.wrapping_add(1));
 
             if diff == 0 {
-                // Try to claim this slot
-                if self
-                    .head
-                    .0
-                    .compare_exchange(
-                        head,
-                        head.wrapping_add(1),
-                        Ordering::SeqCst,
-                        Ordering::Relaxed,
-                    )
-                    .is_ok()
-                {
-                    // Success! Read the data.
-                    let data = unsafe { slot.data.get().read().assume_init() };
-                    // Update turn to next lap for producer
-                    // Current head was H. Turn becomes H + Capacity.
-                    slot.turn
-                        .store(head.wrapping_add(self.capacity), Ordering::Release);
-                    return Some(data);
+                // Try to claim this slot. The CAS only arbitrates between consumers; it never
+                // publishes or consumes data, so it can be Relaxed. The data read below is
+                // ordered by the Acquire load of `turn` above (pairing with the producer's
+                // Release store), and handed back by our own Release store on `turn`.
+                match self.head.0.compare_exchange_weak(
+                    head,
+                    head.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // Success! Read the data.
+                        let data = slot.data.with(|p| unsafe { p.read().assume_init() });
+                        // Update turn to next lap for producer
+                        // Current head was H. Turn becomes H + Capacity.
+                        slot.turn
+                            .store(head.wrapping_add(self.capacity), Ordering::Release);
+                        return Some(data);
+                    }
+                    // Lost the race to another consumer: back off so the winners can make
+                    // progress, then retry from the head it published. The weak CAS can also
+                    // fail spuriously with the head unchanged; that just retries right away.
+                    Err(current) => {
+                        if current != head {
+                            self.lost_cas(&mut backoff);
+                        }
+                        head = current;
+                    }
                 }
             } else if (diff as isize) < 0 {
-                // Slot is empty. If calculating for MPMC, we might retry.
-                // For SPSC, if head catches up to tail logic (via turn), it means empty.
-                // diff < 0 means turn < head + 1.
-                // e.g. turn = head (0 vs 1). Empty.
-                return None;
+                // The slot hasn't been filled for this lap. That only means "empty" if no
+                // producer has claimed this position yet; otherwise a push is mid-write and
+                // we wait for it instead of reporting a spurious empty.
+                // Only compared, never used to read data, so Relaxed is enough.
+                if self.tail.0.load(Ordering::Relaxed) == head {
+                    return None;
+                }
+                spin_loop();
+                head = self.head.0.load(Ordering::Relaxed);
             } else {
-                // diff > 0. Head fell behind? (MPMC race) or logic error.
-                // In SPSC buffer this shouldn't happen unless lap wrapped?
-                // Just retry.
+                // diff > 0: our head is stale (MPMC race). Reload and retry.
+                head = self.head.0.load(Ordering::Relaxed);
             }
         }
     }
 
-    pub fn push(&self, item: T) -> bool {
+    /// Returns `Err(item)` when the queue is full.
+    pub fn push(&self, item: T) -> Result<(), T> {
+        let mut backoff = CasBackoff::new();
+        let mut tail = self.tail.0.load(Ordering::Relaxed);
         loop {
-            let tail = self.tail.0.load(Ordering::Relaxed);
             let index = tail & (self.capacity - 1);
             let slot = &self.ring[index];
 
@@ -108,96 +161,101 @@ impl<T> Fifo6<T> {
             let turn = slot.turn.load(Ordering::Acquire);
 
             // If turn == tail: The slot is free for this lap.
-            // If turn == tail + 1: The slot is full (consumer hasn't taken it).
+            // If turn == tail + 1 - capacity: The consumer hasn't freed it from the
+            // previous lap yet (turn lags behind tail, so the difference is negative).
+            // If turn > tail: Another producer already claimed this position.
             let diff = turn.wrapping_sub(tail);
 
             if diff == 0 {
-                // Try to claim
-                if self
-                    .tail
-                    .0
-                    .compare_exchange(
-                        tail,
-                        tail.wrapping_add(1),
-                        Ordering::SeqCst,
-                        Ordering::Relaxed,
-                    )
-                    .is_ok()
-                {
-                    // Success! Write data.
-                    unsafe { slot.data.get().write(MaybeUninit::new(item)) };
-                    // Update turn for consumer: becomes tail + 1
-                    slot.turn.store(tail.wrapping_add(1), Ordering::Release);
-                    return true;
+                // Try to claim. Relaxed for the same reason as in `pop`: the Acquire load of
+                // `turn` already ordered us after the consumer's read of last lap's value.
+                match self.tail.0.compare_exchange_weak(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // Success! Write data.
+                        slot.data
+                            .with_mut(|p| unsafe { p.write(MaybeUninit::new(item)) });
+                        // Update turn for consumer: becomes tail + 1
+                        slot.turn.store(tail.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    // Lost the race to another producer (or failed spuriously, see `pop`):
+                    // back off, then retry from the tail it published.
+                    Err(current) => {
+                        if current != tail {
+                            self.lost_cas(&mut backoff);
+                        }
+                        tail = current;
+                    }
                 }
             } else if (diff as isize) < 0 {
-                // Slot is full.
-                // turn < tail. e.g. turn 0, tail 0 (ok). turn 1, tail 0 (diff 1).
-                // Wait, turn is tail + 1 when full.
-                // So diff would be 1.
-                // If diff < 0? This means tail Wrapped?
-                // Actually:
-                // Push: turn starts at `i`. we write to `i`. tail is `i`. turn==tail.
-                // Set turn to `i+1`.
-                // Next push (lap 2): tail `i + cap`. turn needs to be `i + cap`.
-                // If turn is still `i + 1`, then `i+1 - (i+cap)` is negative.
-                // So diff < 0 implies "Slot Full / Turn lagged".
-
-                // Correction:
-                // Full state: Producer wants to write to `tail`.
-                // Slot turn is from previous lap's write `tail_prev + 1`.
-                // `tail` is `tail_prev + capacity`.
-                // `turn` is way behind `tail`.
-                // So `turn - tail` is negative.
-                return false;
+                // The slot still holds last lap's value. That only means "full" if no
+                // consumer has claimed it yet; otherwise a pop is mid-read, so wait for it.
+                // Like the empty check in `pop`, this is a bare comparison.
+                let head = self.head.0.load(Ordering::Relaxed);
+                if head.wrapping_add(self.capacity) == tail {
+                    return Err(item);
+                }
+                spin_loop();
+                tail = self.tail.0.load(Ordering::Relaxed);
             } else {
-                // diff > 0. Tail fell behind. Retry.
+                // diff > 0: our tail is stale (MPMC race). Reload and retry.
+                tail = self.tail.0.load(Ordering::Relaxed);
             }
         }
     }
 }
 
-pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
-    let queue = Arc::new(Fifo6::<usize>::new(capacity));
-    let done = Arc::new(AtomicBool::new(false));
-    let queue_consumer = queue.clone();
-    let done_consumer = done.clone();
-
-    let consumer = thread::spawn(move || {
-        let mut expected = 0;
-        loop {
-            if let Some(val) = queue_consumer.pop() {
-                assert_eq!(val, expected);
-                expected += 1;
-            } else {
-                if done_consumer.load(Ordering::Acquire) {
-                    if queue_consumer.pop().is_none() {
-                        break;
-                    }
-                } else {
-                    std::hint::spin_loop();
-                }
-            }
-        }
-    });
-
-    let start = Instant::now();
-
-    for i in 0..iters {
-        loop {
-            if queue.push(i) {
-                break;
+// Drop glue: with `&mut self` no push or pop is in flight, so every position in
+// head..tail was written by a completed push and not yet read.
+impl<T> Drop for Fifo6a<T> {
+    fn drop(&mut self) {
+        if core::mem::needs_drop::<T>() {
+            let tail = self.tail.0.load(Ordering::Relaxed);
+            let mut pos = self.head.0.load(Ordering::Relaxed);
+            while pos != tail {
+                let index = pos & (self.capacity - 1);
+                let slot = &self.ring[index];
+                // An occupied slot carries the turn its push published: pos + 1.
+                debug_assert_eq!(slot.turn.load(Ordering::Relaxed), pos.wrapping_add(1));
+                slot.data.with_mut(|p| unsafe { (*p).assume_init_drop() });
+                pos = pos.wrapping_add(1);
             }
-            std::hint::spin_loop();
         }
     }
+}
 
-    done.store(true, Ordering::Release);
-    consumer.join().unwrap();
+impl<T> fmt::Debug for Fifo6a<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = self.head.0.load(Ordering::Acquire);
+        let tail = self.tail.0.load(Ordering::Acquire);
+        debug_queue(f, "Fifo6a", Some(self.capacity), head, tail)
+    }
+}
 
-    let duration = start.elapsed();
-    let secs = duration.as_secs_f64();
-    println!("Fifo6 Time: {:.4}s, Iters: {}", secs, iters);
+#[cfg(feature = "bench")]
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    crate::bench::bench("Fifo6", Arc::new(Fifo6a::<usize>::new(capacity)), iters)
+}
 
-    (iters as f64) / secs
+/// Multi-producer multi-consumer run, see [`crate::bench::bench_mpmc`]. Compare with
+/// [`crate::fifo6::run_mpmc_benchmark`] to see what backing off after a lost CAS buys.
+#[cfg(feature = "bench")]
+pub fn run_mpmc_benchmark(
+    iters: usize,
+    capacity: usize,
+    producers: usize,
+    consumers: usize,
+) -> f64 {
+    crate::bench::bench_mpmc(
+        "Fifo6a",
+        Arc::new(Fifo6a::<usize>::new(capacity)),
+        iters,
+        producers,
+        consumers,
+    )
 }
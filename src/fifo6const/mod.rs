@@ -0,0 +1,161 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "bench")]
+use std::sync::Arc;
+
+use crate::cache_padded::CachePadded;
+use crate::fifo6::Slot;
+use crate::queue::debug_queue;
+
+/// [`Fifo6`](crate::Fifo6) with the capacity as a const generic, so `index & (N - 1)`
+/// compiles down to an AND with an immediate. The algorithm is unchanged.
+pub struct Fifo6Const<T, const N: usize> {
+    ring: Box<[Slot<T>; N]>,
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+}
+
+unsafe impl<T: Send, const N: usize> Sync for Fifo6Const<T, N> {}
+unsafe impl<T: Send, const N: usize> Send for Fifo6Const<T, N> {}
+
+impl<T, const N: usize> Fifo6Const<T, N> {
+    /// A bad `N` (not a power of two, or below 2) is a compile error, reported where the
+    /// queue type is instantiated.
+    pub fn new() -> Fifo6Const<T, N> {
+        const {
+            assert!(
+                N.is_power_of_two() && N >= 2,
+                "Fifo6Const: N must be a power of two >= 2"
+            )
+        };
+        // Built on the heap through a Vec: a large [Slot<T>; N] would overflow the stack.
+        let mut ring = Vec::with_capacity(N);
+        for i in 0..N {
+            let slot = Slot::new();
+            slot.turn.store(i, Ordering::Relaxed);
+            ring.push(slot);
+        }
+        let ring: Box<[Slot<T>; N]> = match ring.into_boxed_slice().try_into() {
+            Ok(ring) => ring,
+            Err(_) => unreachable!("the ring has exactly N slots"),
+        };
+
+        Fifo6Const {
+            ring,
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(0)),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let mut head = self.head.0.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.ring[head & (N - 1)];
+            let turn = slot.turn.load(Ordering::Acquire);
+            let diff = turn.wrapping_sub(head.wrapping_add(1));
+
+            if diff == 0 {
+                // Orderings as in Fifo6::pop: `turn` carries the synchronization.
+                match self.head.0.compare_exchange_weak(
+                    head,
+                    head.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let data = unsafe { slot.data.get().read().assume_init() };
+                        slot.turn.store(head.wrapping_add(N), Ordering::Release);
+                        return Some(data);
+                    }
+                    Err(current) => head = current,
+                }
+            } else if (diff as isize) < 0 {
+                // Empty unless a producer has already claimed this position.
+                if self.tail.0.load(Ordering::Relaxed) == head {
+                    return None;
+                }
+                core::hint::spin_loop();
+                head = self.head.0.load(Ordering::Relaxed);
+            } else {
+                head = self.head.0.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Returns `Err(item)` when the queue is full.
+    pub fn push(&self, item: T) -> Result<(), T> {
+        let mut tail = self.tail.0.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.ring[tail & (N - 1)];
+            let turn = slot.turn.load(Ordering::Acquire);
+            let diff = turn.wrapping_sub(tail);
+
+            if diff == 0 {
+                match self.tail.0.compare_exchange_weak(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        unsafe { slot.data.get().write(MaybeUninit::new(item)) };
+                        slot.turn.store(tail.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => tail = current,
+                }
+            } else if (diff as isize) < 0 {
+                // Full unless a consumer has already claimed the oldest position.
+                let head = self.head.0.load(Ordering::Relaxed);
+                if head.wrapping_add(N) == tail {
+                    return Err(item);
+                }
+                core::hint::spin_loop();
+                tail = self.tail.0.load(Ordering::Relaxed);
+            } else {
+                tail = self.tail.0.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Default for Fifo6Const<T, N> {
+    fn default() -> Fifo6Const<T, N> {
+        Fifo6Const::new()
+    }
+}
+
+impl<T, const N: usize> Drop for Fifo6Const<T, N> {
+    fn drop(&mut self) {
+        if core::mem::needs_drop::<T>() {
+            let tail = *self.tail.0.get_mut();
+            let mut pos = *self.head.0.get_mut();
+            while pos != tail {
+                let slot = &mut self.ring[pos & (N - 1)];
+                debug_assert_eq!(*slot.turn.get_mut(), pos.wrapping_add(1));
+                unsafe { slot.data.get_mut().assume_init_drop() };
+                pos = pos.wrapping_add(1);
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> fmt::Debug for Fifo6Const<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = self.head.0.load(Ordering::Acquire);
+        let tail = self.tail.0.load(Ordering::Acquire);
+        debug_queue(f, "Fifo6Const", Some(N), head, tail)
+    }
+}
+
+#[cfg(feature = "bench")]
+pub fn run_benchmark<const N: usize>(iters: usize) -> f64 {
+    crate::bench::bench("Fifo6Const", Arc::new(Fifo6Const::<usize, N>::new()), iters)
+}
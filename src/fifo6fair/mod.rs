@@ -0,0 +1,206 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem::MaybeUninit;
+#[cfg(feature = "bench")]
+use std::sync::Arc;
+
+use crate::backoff::CasBackoff;
+use crate::builder::turn_ring_capacity;
+use crate::cache_padded::CachePadded;
+use crate::error::FifoError;
+use crate::queue::debug_queue;
+use crate::sync::{AtomicUsize, Ordering, UnsafeCell};
+
+struct Slot<T> {
+    turn: AtomicUsize,
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Vyukov-style MPMC ring where consumers take tickets instead of racing a CAS on `head`.
+///
+/// Producers claim positions exactly like [`Fifo6a`](crate::Fifo6a). A consumer instead does
+/// a single `fetch_add` on `head` and then waits for its slot's `turn`, so consumers are
+/// served strictly in the order they arrived: no consumer can keep winning the head cache
+/// line and starve the others.
+///
+/// The price is that a claimed ticket can't be handed back. If two consumers race for the
+/// last item, the loser waits in [`pop`](Self::pop) until a producer pushes again, so with
+/// several consumers make sure every consumer that may be waiting eventually gets an item
+/// (e.g. one shutdown sentinel per consumer).
+pub struct Fifo6Fair<T> {
+    capacity: usize,
+    ring: Box<[Slot<T>]>,
+    // Next consumer ticket. Can run ahead of `tail` while consumers wait for items.
+    head: CachePadded<AtomicUsize>,
+    // Producer index.
+    tail: CachePadded<AtomicUsize>,
+}
+
+unsafe impl<T: Send> Sync for Fifo6Fair<T> {}
+unsafe impl<T: Send> Send for Fifo6Fair<T> {}
+
+impl<T> Fifo6Fair<T> {
+    /// `capacity` is rounded up to the next power of two and must be at least 2, as in
+    /// [`Fifo6a::new`](crate::Fifo6a::new).
+    pub fn new(capacity: usize) -> Fifo6Fair<T> {
+        Fifo6Fair::try_new(capacity).unwrap_or_else(|err| panic!("Fifo6Fair: {err}"))
+    }
+
+    /// [`new`](Self::new) that reports a capacity below 2 (or one that can't be rounded up)
+    /// as a [`FifoError`] instead of panicking.
+    pub fn try_new(capacity: usize) -> Result<Fifo6Fair<T>, FifoError> {
+        let capacity = turn_ring_capacity(capacity)?;
+        let mut ring = Vec::with_capacity(capacity);
+        for i in 0..capacity {
+            ring.push(Slot {
+                turn: AtomicUsize::new(i),
+                data: UnsafeCell::new(MaybeUninit::uninit()),
+            });
+        }
+
+        Ok(Fifo6Fair {
+            capacity,
+            ring: ring.into_boxed_slice(),
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Takes the next ticket and waits until its item has been pushed.
+    pub fn pop(&self) -> T {
+        // Relaxed: the ticket only arbitrates between consumers; the data is ordered by the
+        // Acquire load of `turn` below, as in Fifo6a.
+        let ticket = self.head.0.fetch_add(1, Ordering::Relaxed);
+        let slot = &self.ring[ticket & (self.capacity - 1)];
+
+        // The slot may still hold the previous lap's item, or not be filled yet; either way
+        // its turn only moves forward, so wait for the value that belongs to our ticket.
+        let mut backoff = CasBackoff::new();
+        while slot.turn.load(Ordering::Acquire) != ticket.wrapping_add(1) {
+            backoff.spin();
+        }
+
+        let data = slot.data.with(|p| unsafe { p.read().assume_init() });
+        slot.turn
+            .store(ticket.wrapping_add(self.capacity), Ordering::Release);
+        data
+    }
+
+    /// Returns `None` without taking a ticket if the queue looks empty, otherwise pops.
+    ///
+    /// Never waits with a single consumer. With several, a consumer that sees the last item
+    /// may lose it to another one and then wait in [`pop`](Self::pop) for the next push.
+    pub fn try_pop(&self) -> Option<T> {
+        let head = self.head.0.load(Ordering::Relaxed);
+        let slot = &self.ring[head & (self.capacity - 1)];
+        if slot.turn.load(Ordering::Relaxed) != head.wrapping_add(1) {
+            return None;
+        }
+        Some(self.pop())
+    }
+
+    /// Returns `Err(item)` when the queue is full.
+    pub fn push(&self, item: T) -> Result<(), T> {
+        let mut backoff = CasBackoff::new();
+        let mut tail = self.tail.0.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.ring[tail & (self.capacity - 1)];
+            let turn = slot.turn.load(Ordering::Acquire);
+            let diff = turn.wrapping_sub(tail);
+
+            if diff == 0 {
+                match self.tail.0.compare_exchange_weak(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        slot.data
+                            .with_mut(|p| unsafe { p.write(MaybeUninit::new(item)) });
+                        slot.turn.store(tail.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => {
+                        // Only back off for a real race, not a spurious weak-CAS failure.
+                        if current != tail {
+                            backoff.spin();
+                        }
+                        tail = current;
+                    }
+                }
+            } else if (diff as isize) < 0 {
+                // Last lap's item is still in the slot. Full unless a consumer already holds
+                // its ticket; `head` may be ahead of `tail` here, hence the signed distance.
+                let head = self.head.0.load(Ordering::Relaxed);
+                if tail.wrapping_sub(head) as isize >= self.capacity as isize {
+                    return Err(item);
+                }
+                // The ticket holder may not even be running yet, so back off (and
+                // eventually yield) rather than spin until it is.
+                backoff.spin();
+                tail = self.tail.0.load(Ordering::Relaxed);
+            } else {
+                tail = self.tail.0.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+// With `&mut self` nobody is waiting on a ticket, so every pop that took one has finished
+// and head..tail are exactly the pushed, unread positions.
+impl<T> Drop for Fifo6Fair<T> {
+    fn drop(&mut self) {
+        if core::mem::needs_drop::<T>() {
+            let tail = self.tail.0.load(Ordering::Relaxed);
+            let mut pos = self.head.0.load(Ordering::Relaxed);
+            while pos != tail {
+                let slot = &self.ring[pos & (self.capacity - 1)];
+                slot.data.with_mut(|p| unsafe { (*p).assume_init_drop() });
+                pos = pos.wrapping_add(1);
+            }
+        }
+    }
+}
+
+// A consumer waiting on a ticket leaves `head` ahead of `tail`; that counts as empty here.
+impl<T> fmt::Debug for Fifo6Fair<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = self.head.0.load(Ordering::Acquire);
+        let tail = self.tail.0.load(Ordering::Acquire);
+        debug_queue(f, "Fifo6Fair", Some(self.capacity), head, tail)
+    }
+}
+
+#[cfg(feature = "bench")]
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    crate::bench::bench(
+        "Fifo6Fair",
+        Arc::new(Fifo6Fair::<usize>::new(capacity)),
+        iters,
+    )
+}
+
+/// Per-consumer item counts under contention, see [`crate::bench::bench_fairness`].
+#[cfg(feature = "bench")]
+pub fn run_fairness_benchmark(
+    iters: usize,
+    capacity: usize,
+    producers: usize,
+    consumers: usize,
+) -> Vec<usize> {
+    crate::bench::bench_fairness(
+        "Fifo6Fair",
+        Arc::new(Fifo6Fair::<usize>::new(capacity)),
+        |queue, item| queue.push(item),
+        |queue| Some(queue.pop()),
+        iters,
+        producers,
+        consumers,
+    )
+}
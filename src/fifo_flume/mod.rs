@@ -0,0 +1,9 @@
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    let (tx, rx) = flume::bounded::<usize>(capacity);
+    crate::bench::bench_fn::<_, _, crate::backoff::SpinBackoff>(
+        "flume",
+        iters,
+        move |i| tx.try_send(i).map_err(|err| err.into_inner()),
+        move || rx.try_recv().ok(),
+    )
+}
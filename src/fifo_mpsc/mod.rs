@@ -0,0 +1,184 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem::MaybeUninit;
+#[cfg(feature = "bench")]
+use std::sync::Arc;
+
+use crate::backoff::CasBackoff;
+use crate::builder::turn_ring_capacity;
+use crate::cache_padded::CachePadded;
+use crate::error::FifoError;
+use crate::queue::debug_queue;
+use crate::sync::{AtomicUsize, Ordering, UnsafeCell, spin_loop};
+
+struct Slot<T> {
+    turn: AtomicUsize,
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Many producers, one consumer.
+///
+/// Producers claim positions exactly like [`Fifo6a`](crate::Fifo6a), with a CAS on `tail`
+/// and a per-slot `turn`. The consumer owns `head` outright, so popping needs no CAS: it
+/// checks the slot's turn, reads, hands the slot to the next lap and bumps `head` with a
+/// plain store.
+///
+/// `pop` must only ever be called from one thread at a time; `push` from any number.
+pub struct FifoMpsc<T> {
+    capacity: usize,
+    ring: Box<[Slot<T>]>,
+    // Consumer index. Written only by the consumer; producers read it for the full check.
+    head: CachePadded<AtomicUsize>,
+    // Producer index.
+    tail: CachePadded<AtomicUsize>,
+}
+
+unsafe impl<T: Send> Sync for FifoMpsc<T> {}
+unsafe impl<T: Send> Send for FifoMpsc<T> {}
+
+impl<T> FifoMpsc<T> {
+    /// Rounds `capacity` up to the next power of two, like [`Fifo6a::new`](crate::Fifo6a::new),
+    /// and panics if it is below 2.
+    pub fn new(capacity: usize) -> FifoMpsc<T> {
+        FifoMpsc::try_new(capacity).unwrap_or_else(|err| panic!("FifoMpsc: {err}"))
+    }
+
+    /// [`new`](Self::new) that returns the [`FifoError`] instead of panicking.
+    pub fn try_new(capacity: usize) -> Result<FifoMpsc<T>, FifoError> {
+        let capacity = turn_ring_capacity(capacity)?;
+        let mut ring = Vec::with_capacity(capacity);
+        for i in 0..capacity {
+            ring.push(Slot {
+                turn: AtomicUsize::new(i),
+                data: UnsafeCell::new(MaybeUninit::uninit()),
+            });
+        }
+
+        Ok(FifoMpsc {
+            capacity,
+            ring: ring.into_boxed_slice(),
+            head: CachePadded(AtomicUsize::new(0)),
+            tail: CachePadded(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns `None` if the queue is empty. Consumer thread only.
+    pub fn pop(&self) -> Option<T> {
+        // We own head, so Relaxed reads back our own last store.
+        let head = self.head.0.load(Ordering::Relaxed);
+        let slot = &self.ring[head & (self.capacity - 1)];
+
+        // Acquire pairs with the producer's Release store of `turn`, as in Fifo6a.
+        while slot.turn.load(Ordering::Acquire) != head.wrapping_add(1) {
+            // Nobody has claimed this position: empty. Otherwise a producer won the CAS and
+            // is still writing; wait for it rather than report a spurious empty.
+            if self.tail.0.load(Ordering::Relaxed) == head {
+                return None;
+            }
+            spin_loop();
+        }
+
+        let data = slot.data.with(|p| unsafe { p.read().assume_init() });
+        slot.turn
+            .store(head.wrapping_add(self.capacity), Ordering::Release);
+        self.head.0.store(head.wrapping_add(1), Ordering::Release);
+        Some(data)
+    }
+
+    /// Returns `Err(item)` when the queue is full. Safe to call from any number of threads.
+    pub fn push(&self, item: T) -> Result<(), T> {
+        let mut backoff = CasBackoff::new();
+        let mut tail = self.tail.0.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.ring[tail & (self.capacity - 1)];
+            let turn = slot.turn.load(Ordering::Acquire);
+            let diff = turn.wrapping_sub(tail);
+
+            if diff == 0 {
+                match self.tail.0.compare_exchange_weak(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        slot.data
+                            .with_mut(|p| unsafe { p.write(MaybeUninit::new(item)) });
+                        slot.turn.store(tail.wrapping_add(1), Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => {
+                        // Only back off for a real race, not a spurious weak-CAS failure.
+                        if current != tail {
+                            backoff.spin();
+                        }
+                        tail = current;
+                    }
+                }
+            } else if (diff as isize) < 0 {
+                // Last lap's item is still there. Full unless the consumer is mid-pop; it
+                // hands the slot back before it moves head, so an unmoved head means full.
+                let head = self.head.0.load(Ordering::Relaxed);
+                if head.wrapping_add(self.capacity) == tail {
+                    return Err(item);
+                }
+                spin_loop();
+                tail = self.tail.0.load(Ordering::Relaxed);
+            } else {
+                // Another producer already took this position.
+                tail = self.tail.0.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+// With `&mut self` no push or pop is in flight, so head..tail are exactly the unread items.
+impl<T> Drop for FifoMpsc<T> {
+    fn drop(&mut self) {
+        if core::mem::needs_drop::<T>() {
+            let tail = self.tail.0.load(Ordering::Relaxed);
+            let mut pos = self.head.0.load(Ordering::Relaxed);
+            while pos != tail {
+                let slot = &self.ring[pos & (self.capacity - 1)];
+                slot.data.with_mut(|p| unsafe { (*p).assume_init_drop() });
+                pos = pos.wrapping_add(1);
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for FifoMpsc<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = self.head.0.load(Ordering::Acquire);
+        let tail = self.tail.0.load(Ordering::Acquire);
+        debug_queue(f, "FifoMpsc", Some(self.capacity), head, tail)
+    }
+}
+
+#[cfg(feature = "bench")]
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    crate::bench::bench(
+        "FifoMpsc",
+        Arc::new(FifoMpsc::<usize>::new(capacity)),
+        iters,
+    )
+}
+
+/// `producers` threads into the single consumer, see [`crate::bench::bench_mpmc`]. Compare
+/// with [`crate::fifo6::run_mpmc_benchmark`] at one consumer to see what dropping the
+/// consumer-side CAS buys.
+#[cfg(feature = "bench")]
+pub fn run_mpsc_benchmark(iters: usize, capacity: usize, producers: usize) -> f64 {
+    crate::bench::bench_mpmc(
+        "FifoMpsc",
+        Arc::new(FifoMpsc::<usize>::new(capacity)),
+        iters,
+        producers,
+        1,
+    )
+}
@@ -0,0 +1,17 @@
+use std::sync::mpsc::{self, TrySendError};
+
+pub fn run_benchmark(iters: usize, capacity: usize) -> f64 {
+    // A bounded `sync_channel`, so a full channel makes the producer back off like the
+    // queues do instead of growing without limit.
+    let (tx, rx) = mpsc::sync_channel::<usize>(capacity);
+    crate::bench::bench_fn::<_, _, crate::backoff::SpinBackoff>(
+        "std::sync::mpsc",
+        iters,
+        move |i| {
+            tx.try_send(i).map_err(|err| match err {
+                TrySendError::Full(i) | TrySendError::Disconnected(i) => i,
+            })
+        },
+        move || rx.try_recv().ok(),
+    )
+}
@@ -0,0 +1,181 @@
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+use crate::cache_padded::CachePadded;
+use crate::queue::debug_queue;
+
+// Slots per block. Big enough that the allocation and the pointer hop at a block boundary
+// are rare, small enough that an idle queue doesn't hold much memory.
+const BLOCK_CAP: usize = 64;
+
+struct Block<T> {
+    slots: [UnsafeCell<MaybeUninit<T>>; BLOCK_CAP],
+    // Set by the producer before it publishes the first item of the next block.
+    next: AtomicPtr<Block<T>>,
+}
+
+impl<T> Block<T> {
+    fn alloc() -> *mut Block<T> {
+        // Built in place on the heap: a block of large `T`s may not fit on the stack.
+        let mut block = Box::<Block<T>>::new_uninit();
+        // SAFETY: `next` is the only field that needs initializing; the slots are
+        // MaybeUninit.
+        unsafe {
+            ptr::addr_of_mut!((*block.as_mut_ptr()).next).write(AtomicPtr::new(ptr::null_mut()));
+            Box::into_raw(block.assume_init())
+        }
+    }
+
+    fn slot(&self, index: usize) -> *mut T {
+        self.slots[index].get().cast()
+    }
+}
+
+struct ProducerFields<T> {
+    push_cursor: AtomicUsize,
+    tail: UnsafeCell<*mut Block<T>>,
+    // Next free slot in `tail`; BLOCK_CAP means the block is full.
+    tail_index: UnsafeCell<usize>,
+}
+
+struct ConsumerFields<T> {
+    pop_cursor: AtomicUsize,
+    head: UnsafeCell<*mut Block<T>>,
+    // Next slot to read in `head`; BLOCK_CAP means move on to `head.next`.
+    head_index: UnsafeCell<usize>,
+}
+
+/// Unbounded SPSC queue: a linked list of fixed-size blocks that grows a block at a time,
+/// so `push` never fails and never moves the items already queued.
+///
+/// The cursors work as in [`Fifo5`](crate::Fifo5): each side owns one, the producer
+/// publishes with a `Release` store and the consumer compares against it. The producer
+/// allocates a new block every `BLOCK_CAP` (64) pushes and the consumer frees each block
+/// once it has read past it.
+///
+/// Compared with a bounded ring, there is no backpressure: a consumer that falls behind
+/// just makes the queue use more memory. Both sides also pay for the allocator, and the
+/// consumer for a pointer hop, at every block boundary.
+pub struct FifoUnbounded<T> {
+    producer: CachePadded<ProducerFields<T>>,
+    consumer: CachePadded<ConsumerFields<T>>,
+}
+
+unsafe impl<T: Send> Sync for FifoUnbounded<T> {}
+unsafe impl<T: Send> Send for FifoUnbounded<T> {}
+
+impl<T> FifoUnbounded<T> {
+    pub fn new() -> FifoUnbounded<T> {
+        let block = Block::alloc();
+        FifoUnbounded {
+            producer: CachePadded(ProducerFields {
+                push_cursor: AtomicUsize::new(0),
+                tail: UnsafeCell::new(block),
+                tail_index: UnsafeCell::new(0),
+            }),
+            consumer: CachePadded(ConsumerFields {
+                pop_cursor: AtomicUsize::new(0),
+                head: UnsafeCell::new(block),
+                head_index: UnsafeCell::new(0),
+            }),
+        }
+    }
+
+    /// Appends `item`, allocating a new block if the last one is full. Producer thread
+    /// only.
+    pub fn push(&self, item: T) {
+        let producer = &self.producer.0;
+        // SAFETY: `tail` and `tail_index` are only touched by the producer. The consumer
+        // never frees the tail block: it only frees a block after reading an item past it.
+        unsafe {
+            let mut tail = *producer.tail.get();
+            let mut index = *producer.tail_index.get();
+            if index == BLOCK_CAP {
+                let next = Block::alloc();
+                // Relaxed: the Release store on push_cursor below publishes it.
+                (*tail).next.store(next, Ordering::Relaxed);
+                tail = next;
+                index = 0;
+                *producer.tail.get() = tail;
+            }
+            (*tail).slot(index).write(item);
+            *producer.tail_index.get() = index + 1;
+        }
+
+        let push_val = producer.push_cursor.load(Ordering::Relaxed);
+        producer
+            .push_cursor
+            .store(push_val.wrapping_add(1), Ordering::Release);
+    }
+
+    /// Returns `None` if the queue is empty. Consumer thread only.
+    pub fn pop(&self) -> Option<T> {
+        let consumer = &self.consumer.0;
+        let pop_val = consumer.pop_cursor.load(Ordering::Relaxed);
+        if pop_val == self.producer.0.push_cursor.load(Ordering::Acquire) {
+            return None;
+        }
+
+        // SAFETY: `head` and `head_index` are only touched by the consumer. An item at
+        // pop_val is published, so if it lives in the next block, the producer linked that
+        // block before the Release store we just synchronized with, and has moved its tail
+        // on, so the old head is ours to free.
+        let value = unsafe {
+            let mut head = *consumer.head.get();
+            let mut index = *consumer.head_index.get();
+            if index == BLOCK_CAP {
+                let next = (*head).next.load(Ordering::Relaxed);
+                drop(Box::from_raw(head));
+                head = next;
+                index = 0;
+                *consumer.head.get() = head;
+            }
+            let value = (*head).slot(index).read();
+            *consumer.head_index.get() = index + 1;
+            value
+        };
+
+        consumer
+            .pop_cursor
+            .store(pop_val.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    /// Number of items queued; a snapshot, like [`Fifo5::len`](crate::Fifo5::len).
+    pub fn len(&self) -> usize {
+        let pop = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+        let push = self.producer.0.push_cursor.load(Ordering::Acquire);
+        push.wrapping_sub(pop)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Default for FifoUnbounded<T> {
+    fn default() -> FifoUnbounded<T> {
+        FifoUnbounded::new()
+    }
+}
+
+impl<T> Drop for FifoUnbounded<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+        // Everything is read, so the head block is also the tail: the last one left.
+        unsafe { drop(Box::from_raw(*self.consumer.0.head.get())) };
+    }
+}
+
+// Walks the blocks from the consumer's position; `push_cursor - pop_cursor` says how far.
+impl<T> fmt::Debug for FifoUnbounded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pop_val = self.consumer.0.pop_cursor.load(Ordering::Acquire);
+        let push_val = self.producer.0.push_cursor.load(Ordering::Acquire);
+        debug_queue(f, "FifoUnbounded", None, pop_val, push_val)
+    }
+}
@@ -1,8 +1,55 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod aligned;
+pub mod backoff;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod builder;
+pub mod cache_padded;
+#[cfg(feature = "std")]
+pub mod channel;
+pub mod error;
 pub mod fifo1;
 pub mod fifo2;
 pub mod fifo3;
 pub mod fifo4;
 pub mod fifo5;
+pub mod fifo5fetchadd;
 pub mod fifo6;
 pub mod fifo6a;
+pub mod fifo6const;
+pub mod fifo6fair;
+#[cfg(feature = "bench")]
 pub mod fifo_crossbeam;
+#[cfg(feature = "bench")]
+pub mod fifo_flume;
+pub mod fifo_mpsc;
+#[cfg(feature = "bench")]
+pub mod fifo_std_mpsc;
+pub mod fifo_unbounded;
+#[cfg(feature = "std")]
+mod parker;
+pub mod queue;
+pub mod ringlog;
+#[cfg(feature = "stats")]
+pub mod stats;
+mod sync;
+
+pub use builder::FifoBuilder;
+pub use error::FifoError;
+pub use fifo_mpsc::FifoMpsc;
+pub use fifo_unbounded::FifoUnbounded;
+pub use fifo1::Fifo1;
+pub use fifo2::Fifo2;
+pub use fifo3::Fifo3;
+pub use fifo4::Fifo4;
+pub use fifo5::Fifo5;
+pub use fifo5fetchadd::Fifo5FetchAdd;
+pub use fifo6::Fifo6;
+pub use fifo6a::Fifo6a;
+pub use fifo6const::Fifo6Const;
+pub use fifo6fair::Fifo6Fair;
+pub use queue::{Fifo, SpscQueue};
+pub use ringlog::{RingLog, RingLogReader};
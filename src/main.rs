@@ -1,69 +1,419 @@
-mod fifo1;
-mod fifo2;
-mod fifo3;
-mod fifo4;
-mod fifo5;
-mod fifo6;
-mod fifo6a;
-mod fifo_crossbeam;
+use crossbeam::queue::ArrayQueue;
+use lockfree_fifo::backoff::{SleepBackoff, SpinBackoff, YieldBackoff};
+use lockfree_fifo::bench::{bench_arrivals, bench_latency, bench_with};
+use lockfree_fifo::cache_padded::CACHE_LINE;
+use lockfree_fifo::{
+    Fifo2, Fifo3, Fifo4, Fifo5, Fifo6, Fifo6a, fifo_crossbeam, fifo_flume, fifo_mpsc,
+    fifo_std_mpsc, fifo1, fifo2, fifo3, fifo4, fifo5, fifo5fetchadd, fifo6, fifo6a, fifo6const,
+    fifo6fair,
+};
+use std::sync::Arc;
 
-fn main() {
-    let iters = 100_000_000;
-    let capacity = 131_072;
+// A const so the compile-time capacity queue can use the same value.
+const CAPACITY: usize = 131_072;
+const ITERS: usize = 100_000_000;
+
+const USAGE: &str = "usage: lock_free_fifo [--variant NAME]... [--iters N] [--capacity N] \
+[--backoff] [--pin] [--latency] [--arrivals] [--wakeup]";
 
-    println!("Running Fifo1 Benchmark...");
-    let ops_per_sec = fifo1::run_benchmark(iters, capacity);
+// Which capacities a variant can be benchmarked at as given.
+enum CapacityRule {
+    Any,
+    // The queue indexes with a mask and would silently round anything else up.
+    PowerOfTwo,
+    // Fifo6Const: the capacity is a const generic, fixed at build time.
+    Fixed(usize),
+}
+
+struct Variant {
+    name: &'static str,
+    capacity: CapacityRule,
+    run: fn(usize, usize),
+}
+
+impl Variant {
+    fn check_capacity(&self, capacity: usize) -> Result<(), String> {
+        match self.capacity {
+            CapacityRule::PowerOfTwo if !capacity.is_power_of_two() => Err(format!(
+                "{} needs a power-of-two capacity, got {} (try {})",
+                self.name,
+                capacity,
+                capacity.next_power_of_two()
+            )),
+            CapacityRule::Fixed(fixed) if capacity != fixed => Err(format!(
+                "{} is built for capacity {} only, got {}",
+                self.name, fixed, capacity
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn throughput(label: &str, ops_per_sec: f64) {
     println!(
-        "Fifo1 Throughput: {:.2} million ops/sec",
+        "{} Throughput: {:.2} million ops/sec",
+        label,
         ops_per_sec / 1_000_000.0
     );
+}
 
-    println!("\nRunning Fifo2 (Lock-Free) Benchmark...");
-    let ops_per_sec2 = fifo2::run_benchmark(iters, capacity);
-    println!(
-        "Fifo2 Throughput: {:.2} million ops/sec",
-        ops_per_sec2 / 1_000_000.0
-    );
+// In the order of the default run, which is all of them.
+const VARIANTS: &[Variant] = &[
+    Variant {
+        name: "fifo1",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Fifo1 Benchmark...");
+            throughput("Fifo1", fifo1::run_benchmark(iters, capacity));
+        },
+    },
+    Variant {
+        name: "fifo2",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Fifo2 (Lock-Free) Benchmark...");
+            throughput("Fifo2", fifo2::run_benchmark(iters, capacity));
+        },
+    },
+    Variant {
+        name: "fifo3",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Fifo3 (Cache Padded) Benchmark...");
+            throughput("Fifo3", fifo3::run_benchmark(iters, capacity));
+        },
+    },
+    Variant {
+        name: "fifo4",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Fifo4 (Shadow Cursors + Padding) Benchmark...");
+            throughput("Fifo4", fifo4::run_benchmark(iters, capacity));
+        },
+    },
+    Variant {
+        name: "fifo4-deferred",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Fifo4 with deferred publishing (flush every 16 items) Benchmark...");
+            throughput(
+                "Fifo4 deferred",
+                fifo4::run_deferred_benchmark(iters, capacity, 16),
+            );
+        },
+    },
+    Variant {
+        name: "fifo5",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Fifo5 (MaybeUninit + Shadow) Benchmark...");
+            throughput("Fifo5", fifo5::run_benchmark(iters, capacity));
+        },
+    },
+    Variant {
+        name: "fifo5fetchadd",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Fifo5FetchAdd (fetch_add cursor updates) Benchmark...");
+            throughput(
+                "Fifo5FetchAdd",
+                fifo5fetchadd::run_benchmark(iters, capacity),
+            );
+        },
+    },
+    // Memory-bound at the default capacity: the ring is 16 MiB of 128-byte items.
+    Variant {
+        name: "fifo5-large",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Fifo5 with 128-byte items Benchmark...");
+            throughput(
+                "Fifo5 128-byte items",
+                fifo5::run_large_payload_benchmark(iters / 4, capacity),
+            );
+        },
+    },
+    Variant {
+        name: "fifo6",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Fifo6 (Vyukov MPMC Prototype) Benchmark...");
+            throughput("Fifo6", fifo6::run_benchmark(iters, capacity));
+        },
+    },
+    Variant {
+        name: "fifo6-mpmc",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Fifo6 (Vyukov MPMC, 4 producers / 4 consumers) Benchmark...");
+            throughput(
+                "Fifo6 MPMC",
+                fifo6::run_mpmc_benchmark(iters, capacity, 4, 4),
+            );
+        },
+    },
+    Variant {
+        name: "fifo6a",
+        capacity: CapacityRule::PowerOfTwo,
+        run: |iters, capacity| {
+            println!("Running Fifo6a (Vyukov MPMC Prototype with bit mask) Benchmark...");
+            throughput("Fifo6a", fifo6a::run_benchmark(iters, capacity));
+        },
+    },
+    Variant {
+        name: "fifo6a-mpmc",
+        capacity: CapacityRule::PowerOfTwo,
+        run: |iters, capacity| {
+            println!(
+                "Running Fifo6a (Vyukov MPMC with CAS backoff, 4 producers / 4 consumers) Benchmark..."
+            );
+            throughput(
+                "Fifo6a MPMC",
+                fifo6a::run_mpmc_benchmark(iters, capacity, 4, 4),
+            );
+        },
+    },
+    Variant {
+        name: "mpsc",
+        capacity: CapacityRule::PowerOfTwo,
+        run: |iters, capacity| {
+            println!("Running FifoMpsc vs Fifo6 (4 producers / 1 consumer) Benchmark...");
+            let ops_per_sec_mpsc = fifo_mpsc::run_mpsc_benchmark(iters, capacity, 4);
+            let ops_per_sec6_mpsc = fifo6::run_mpmc_benchmark(iters, capacity, 4, 1);
+            println!(
+                "FifoMpsc Throughput: {:.2} million ops/sec, Fifo6: {:.2} million ops/sec",
+                ops_per_sec_mpsc / 1_000_000.0,
+                ops_per_sec6_mpsc / 1_000_000.0
+            );
+        },
+    },
+    Variant {
+        name: "fifo6fair",
+        capacity: CapacityRule::PowerOfTwo,
+        run: |iters, capacity| {
+            println!("Running Fifo6Fair (ticket-based consumers) Benchmark...");
+            throughput("Fifo6Fair", fifo6fair::run_benchmark(iters, capacity));
+        },
+    },
+    Variant {
+        name: "fairness",
+        capacity: CapacityRule::PowerOfTwo,
+        run: |iters, capacity| {
+            println!("Running Fifo6 vs Fifo6Fair consumer fairness (2 producers / 4 consumers)...");
+            fifo6::run_fairness_benchmark(iters, capacity, 2, 4);
+            fifo6fair::run_fairness_benchmark(iters, capacity, 2, 4);
+        },
+    },
+    Variant {
+        name: "fifo6const",
+        capacity: CapacityRule::Fixed(CAPACITY),
+        run: |iters, _| {
+            println!("Running Fifo6Const (Vyukov MPMC, compile-time capacity) Benchmark...");
+            throughput("Fifo6Const", fifo6const::run_benchmark::<CAPACITY>(iters));
+        },
+    },
+    Variant {
+        name: "crossbeam",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running Crossbeam ArrayQueue Benchmark...");
+            throughput("Crossbeam", fifo_crossbeam::run_benchmark(iters, capacity));
+        },
+    },
+    Variant {
+        name: "std-mpsc",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running std::sync::mpsc (sync_channel) Benchmark...");
+            throughput(
+                "std::sync::mpsc",
+                fifo_std_mpsc::run_benchmark(iters, capacity),
+            );
+        },
+    },
+    Variant {
+        name: "flume",
+        capacity: CapacityRule::Any,
+        run: |iters, capacity| {
+            println!("Running flume Benchmark...");
+            throughput("flume", fifo_flume::run_benchmark(iters, capacity));
+        },
+    },
+];
 
-    println!("\nRunning Fifo3 (Cache Padded) Benchmark...");
-    let ops_per_sec3 = fifo3::run_benchmark(iters, capacity);
-    println!(
-        "Fifo3 Throughput: {:.2} million ops/sec",
-        ops_per_sec3 / 1_000_000.0
-    );
+struct Options {
+    // Empty means every variant.
+    variants: Vec<&'static Variant>,
+    iters: usize,
+    capacity: usize,
+    flags: Vec<String>,
+}
 
-    println!("\nRunning Fifo4 (Shadow Cursors + Padding) Benchmark...");
-    let ops_per_sec4 = fifo4::run_benchmark(iters, capacity);
-    println!(
-        "Fifo4 Throughput: {:.2} million ops/sec",
-        ops_per_sec4 / 1_000_000.0
-    );
+impl Options {
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+}
 
-    println!("\nRunning Fifo5 (MaybeUninit + Shadow) Benchmark...");
-    let ops_per_sec5 = fifo5::run_benchmark(iters, capacity);
-    println!(
-        "Fifo5 Throughput: {:.2} million ops/sec",
-        ops_per_sec5 / 1_000_000.0
-    );
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        variants: Vec::new(),
+        iters: ITERS,
+        capacity: CAPACITY,
+        flags: Vec::new(),
+    };
+    let number = |flag: &str, value: Option<String>| {
+        let value = value.ok_or_else(|| format!("{flag} needs a value"))?;
+        match value.replace('_', "").parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(format!(
+                "{flag}: expected a positive integer, got {value:?}"
+            )),
+        }
+    };
 
-    println!("\nRunning Fifo6 (Vyukov MPMC Prototype) Benchmark...");
-    let ops_per_sec6_proto = fifo6::run_benchmark(iters, capacity);
-    println!(
-        "Fifo6 Throughput: {:.2} million ops/sec",
-        ops_per_sec6_proto / 1_000_000.0
-    );
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--variant" => {
+                let name = args.next().ok_or("--variant needs a value")?;
+                let variant = VARIANTS
+                    .iter()
+                    .find(|variant| variant.name.eq_ignore_ascii_case(&name))
+                    .ok_or_else(|| {
+                        let names: Vec<_> = VARIANTS.iter().map(|variant| variant.name).collect();
+                        format!(
+                            "unknown variant {name:?}, expected one of: {}",
+                            names.join(", ")
+                        )
+                    })?;
+                options.variants.push(variant);
+            }
+            "--iters" => options.iters = number("--iters", args.next())?,
+            "--capacity" => {
+                options.capacity = number("--capacity", args.next())?;
+                // The MPMC queues tell "full" from "empty" by lap, which needs two slots.
+                if options.capacity < 2 {
+                    return Err("--capacity must be at least 2".to_string());
+                }
+            }
+            "--backoff" | "--pin" | "--latency" | "--arrivals" | "--wakeup" => {
+                options.flags.push(arg)
+            }
+            _ => return Err(format!("unexpected argument {arg:?}")),
+        }
+    }
+    Ok(options)
+}
 
-    println!("\nRunning Fifo6a (Vyukov MPMC Prototype with bit mask) Benchmark...");
-    let ops_per_sec6_proto = fifo6a::run_benchmark(iters, capacity);
-    println!(
-        "Fifo6 Throughput: {:.2} million ops/sec",
-        ops_per_sec6_proto / 1_000_000.0
-    );
+fn main() {
+    let options = parse_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("error: {err}\n{USAGE}");
+        std::process::exit(2);
+    });
+    let (iters, capacity) = (options.iters, options.capacity);
+
+    // Asked-for variants must fit the capacity; the default run just skips the ones that
+    // don't.
+    let variants: Vec<&Variant> = if options.variants.is_empty() {
+        VARIANTS
+            .iter()
+            .filter(|variant| match variant.check_capacity(capacity) {
+                Ok(()) => true,
+                Err(err) => {
+                    println!("Skipping: {err}");
+                    false
+                }
+            })
+            .collect()
+    } else {
+        for variant in &options.variants {
+            if let Err(err) = variant.check_capacity(capacity) {
+                eprintln!("error: {err}");
+                std::process::exit(2);
+            }
+        }
+        options.variants.clone()
+    };
 
-    println!("\nRunning Crossbeam ArrayQueue Benchmark...");
-    let ops_per_sec6 = fifo_crossbeam::run_benchmark(iters, capacity);
     println!(
-        "Crossbeam Throughput: {:.2} million ops/sec",
-        ops_per_sec6 / 1_000_000.0
+        "Cache line padding: {} bytes (Fifo4 header: {} bytes)\n",
+        CACHE_LINE,
+        std::mem::size_of::<Fifo4<usize>>()
     );
+
+    for (i, variant) in variants.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        (variant.run)(iters, capacity);
+    }
+
+    // Waiting strategies compared on the same queue; opt-in since it repeats the Fifo4 run.
+    if options.flag("--backoff") {
+        println!("\nRunning Fifo4 backoff comparison...");
+        bench_with::<_, SpinBackoff>("Fifo4 spin", Arc::new(Fifo4::new(capacity)), iters);
+        bench_with::<_, YieldBackoff>("Fifo4 yield", Arc::new(Fifo4::new(capacity)), iters);
+        bench_with::<_, SleepBackoff>("Fifo4 sleep", Arc::new(Fifo4::new(capacity)), iters);
+    }
+
+    // Same queues with explicit placement, next to the unpinned numbers from above. False
+    // sharing only costs anything when the two sides are on different cores.
+    #[cfg(feature = "affinity")]
+    if options.flag("--pin") {
+        use lockfree_fifo::bench::{Pinning, bench_pinned};
+
+        let cores = core_affinity::get_core_ids().map_or(1, |ids| ids.len());
+        let placements = [
+            Pinning {
+                producer: 0,
+                consumer: 0,
+            },
+            Pinning {
+                producer: 0,
+                consumer: cores - 1,
+            },
+        ];
+        println!("\nRunning pinned benchmarks ({} cores)...", cores);
+        for pinning in placements {
+            bench_pinned("Fifo2", Arc::new(Fifo2::new(capacity)), iters, pinning);
+            bench_pinned("Fifo3", Arc::new(Fifo3::new(capacity)), iters, pinning);
+            bench_pinned("Fifo4", Arc::new(Fifo4::new(capacity)), iters, pinning);
+            bench_pinned("Fifo5", Arc::new(Fifo5::new(capacity)), iters, pinning);
+        }
+    }
+
+    // Round-trip latency is opt-in: it keeps one u64 sample per item, so it runs fewer iterations.
+    if options.flag("--latency") {
+        let latency_iters = 10_000_000;
+        println!("\nRunning latency benchmarks ({} iters)...", latency_iters);
+        bench_latency("Fifo2", Arc::new(Fifo2::new(capacity)), latency_iters);
+        bench_latency("Fifo3", Arc::new(Fifo3::new(capacity)), latency_iters);
+        bench_latency("Fifo4", Arc::new(Fifo4::new(capacity)), latency_iters);
+        bench_latency("Fifo5", Arc::new(Fifo5::new(capacity)), latency_iters);
+        bench_latency("Fifo6", Arc::new(Fifo6::new(capacity)), latency_iters);
+        bench_latency("Fifo6a", Arc::new(Fifo6a::new(capacity)), latency_iters);
+        bench_latency(
+            "Crossbeam ArrayQueue",
+            Arc::new(ArrayQueue::new(capacity)),
+            latency_iters,
+        );
+    }
+
+    // Consumer waiting strategies on a bursty, mostly idle queue: latency against CPU.
+    if options.flag("--wakeup") {
+        println!("\nRunning wakeup benchmarks...");
+        fifo5::run_wakeup_benchmark(capacity, 4_000);
+    }
+
+    // Inter-arrival gaps at the consumer, opt-in for the same reason as `--latency`.
+    if options.flag("--arrivals") {
+        let arrival_iters = 10_000_000;
+        println!(
+            "\nRunning arrival gap benchmarks ({} iters)...",
+            arrival_iters
+        );
+        bench_arrivals("Fifo4", Arc::new(Fifo4::new(capacity)), arrival_iters);
+        bench_arrivals("Fifo5", Arc::new(Fifo5::new(capacity)), arrival_iters);
+        bench_arrivals("Fifo6a", Arc::new(Fifo6a::new(capacity)), arrival_iters);
+    }
 }
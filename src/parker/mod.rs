@@ -0,0 +1,79 @@
+//! Park / unpark handshake for the blocking APIs, with the wakeups coalesced: the notifying
+//! side only calls `unpark` when the waiter has announced it is about to sleep, so a busy
+//! consumer (or producer) never gets woken for nothing.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering, fence};
+use std::sync::{Mutex, PoisonError};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+pub(crate) struct Parker {
+    // The thread that called `prepare` last. Rewritten on every wait, so waits may come from
+    // different threads one after another (never at the same time).
+    thread: Mutex<Option<Thread>>,
+    // Set by the waiter right before it re-checks and parks, cleared by whoever wakes it.
+    waiting: AtomicBool,
+    // Unparks actually issued. Only touched on the slow path.
+    unparks: AtomicUsize,
+}
+
+impl Parker {
+    pub(crate) const fn new() -> Parker {
+        Parker {
+            thread: Mutex::new(None),
+            waiting: AtomicBool::new(false),
+            unparks: AtomicUsize::new(0),
+        }
+    }
+
+    /// Waiter side: declares that the calling thread is about to park. The caller must
+    /// re-check its condition after this and only then call [`park`](Self::park) or
+    /// [`park_timeout`](Self::park_timeout), or [`cancel`](Self::cancel) if it no longer
+    /// needs to wait.
+    pub(crate) fn prepare(&self) {
+        // Stored before `waiting` is raised, so a notifier that sees the flag finds us here.
+        *self.thread.lock().unwrap_or_else(PoisonError::into_inner) = Some(thread::current());
+        self.waiting.store(true, Ordering::Relaxed);
+        // Pairs with the fence in `unpark`: either the notifier sees `waiting`, or our
+        // re-check sees what it published. Without it the wakeup can be lost.
+        fence(Ordering::SeqCst);
+    }
+
+    pub(crate) fn park(&self) {
+        thread::park();
+        self.cancel();
+    }
+
+    pub(crate) fn park_timeout(&self, dur: Duration) {
+        thread::park_timeout(dur);
+        self.cancel();
+    }
+
+    /// Withdraws a [`prepare`](Self::prepare). If a notifier already consumed it, the
+    /// thread keeps one pending unpark token, which only makes a later park return early.
+    pub(crate) fn cancel(&self) {
+        self.waiting.store(false, Ordering::Relaxed);
+    }
+
+    /// Notifier side, after publishing whatever the waiter is waiting for. Unparks the
+    /// waiter only if it is between `prepare` and waking up, and only once per wait.
+    pub(crate) fn unpark(&self) {
+        fence(Ordering::SeqCst);
+        // The plain load keeps the common no-waiter case to a read of a shared line.
+        if self.waiting.load(Ordering::Relaxed)
+            && self.waiting.swap(false, Ordering::Relaxed)
+            && let Some(thread) = self
+                .thread
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone()
+        {
+            self.unparks.fetch_add(1, Ordering::Relaxed);
+            thread.unpark();
+        }
+    }
+
+    pub(crate) fn unparks(&self) -> usize {
+        self.unparks.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,232 @@
+use core::fmt;
+
+use crate::backoff::Backoff;
+use crate::{
+    Fifo2, Fifo3, Fifo4, Fifo5, Fifo5FetchAdd, Fifo6, Fifo6Const, Fifo6Fair, Fifo6a, FifoMpsc,
+    FifoUnbounded,
+};
+
+/// Common interface over the single-producer / single-consumer queues.
+///
+/// `try_push` may only be called from one thread and `try_pop` from one (possibly different)
+/// thread, the same contract as the inherent `push` / `pop`. The MPMC queues implement it
+/// too, they just don't need the restriction.
+pub trait SpscQueue<T> {
+    /// Returns `Err(item)` if the queue is full.
+    fn try_push(&self, item: T) -> Result<(), T>;
+    /// Returns `None` if the queue is empty.
+    fn try_pop(&self) -> Option<T>;
+    fn capacity(&self) -> usize;
+
+    /// Pushes `item`, calling `backoff.snooze()` for as long as the queue is full.
+    fn push_spin<B: Backoff>(&self, mut item: T, backoff: &mut B)
+    where
+        Self: Sized,
+    {
+        loop {
+            match self.try_push(item) {
+                Ok(()) => break,
+                Err(rejected) => {
+                    item = rejected;
+                    backoff.snooze();
+                }
+            }
+        }
+        backoff.reset();
+    }
+
+    /// Pops the next item, calling `backoff.snooze()` for as long as the queue is empty.
+    fn pop_spin<B: Backoff>(&self, backoff: &mut B) -> T
+    where
+        Self: Sized,
+    {
+        loop {
+            if let Some(item) = self.try_pop() {
+                backoff.reset();
+                return item;
+            }
+            backoff.snooze();
+        }
+    }
+}
+
+macro_rules! impl_spsc_queue {
+    ($($fifo:ident),* $(,)?) => {
+        $(
+            impl<T> SpscQueue<T> for $fifo<T> {
+                fn try_push(&self, item: T) -> Result<(), T> {
+                    self.push(item)
+                }
+
+                fn try_pop(&self) -> Option<T> {
+                    self.pop()
+                }
+
+                fn capacity(&self) -> usize {
+                    $fifo::capacity(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_spsc_queue!(
+    Fifo2,
+    Fifo3,
+    Fifo4,
+    Fifo5,
+    Fifo5FetchAdd,
+    Fifo6,
+    Fifo6a,
+    FifoMpsc
+);
+
+// `VecDeque` names for the SPSC queues, so code written against a `VecDeque` ports over by
+// renaming the type. `front` lives next to each queue's own peek or pop, since how it finds
+// the front slot differs per queue.
+macro_rules! impl_vecdeque_names {
+    ($($fifo:ident),* $(,)?) => {
+        $(
+            impl<T> $fifo<T> {
+                /// `VecDeque`-style name for `push`, and the preferred one where it reads
+                /// better. Returns `Err(item)` when the queue is full.
+                ///
+                /// Producer thread only: the queue is single-producer, and calling this from
+                /// two threads at once races on the same slot.
+                #[inline]
+                pub fn push_back(&self, item: T) -> Result<(), T> {
+                    self.push(item)
+                }
+
+                /// `VecDeque`-style name for `pop`. Returns `None` when the queue is empty.
+                ///
+                /// Consumer thread only, for the same reason as `push_back`.
+                #[inline]
+                pub fn pop_front(&self) -> Option<T> {
+                    self.pop()
+                }
+            }
+        )*
+    };
+}
+
+impl_vecdeque_names!(Fifo2, Fifo3, Fifo4, Fifo5);
+
+// With the trait's single consumer, `try_pop` never loses the last item to another
+// consumer, so it never waits.
+impl<T> SpscQueue<T> for Fifo6Fair<T> {
+    fn try_push(&self, item: T) -> Result<(), T> {
+        self.push(item)
+    }
+
+    fn try_pop(&self) -> Option<T> {
+        Fifo6Fair::try_pop(self)
+    }
+
+    fn capacity(&self) -> usize {
+        Fifo6Fair::capacity(self)
+    }
+}
+
+impl<T, const N: usize> SpscQueue<T> for Fifo6Const<T, N> {
+    fn try_push(&self, item: T) -> Result<(), T> {
+        self.push(item)
+    }
+
+    fn try_pop(&self) -> Option<T> {
+        self.pop()
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+}
+
+/// A bounded [`Fifo5`] or an unbounded [`FifoUnbounded`], picked at construction, behind one
+/// `push` / `pop`. SPSC either way.
+///
+/// Bounded gives backpressure: `push` hands the item back when the ring is full, and memory
+/// use is fixed up front. Unbounded never rejects an item, but memory follows the backlog,
+/// and it allocates every few dozen pushes.
+// Both variants are mostly cache-line padding; boxing Fifo5 would only add a pointer hop
+// to every push and pop.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum Fifo<T> {
+    Bounded(Fifo5<T>),
+    Unbounded(FifoUnbounded<T>),
+}
+
+impl<T> Fifo<T> {
+    pub fn bounded(capacity: usize) -> Fifo<T> {
+        Fifo::Bounded(Fifo5::new(capacity))
+    }
+
+    pub fn unbounded() -> Fifo<T> {
+        Fifo::Unbounded(FifoUnbounded::new())
+    }
+
+    /// Returns `Err(item)` if a bounded queue is full; an unbounded one always accepts.
+    #[inline]
+    pub fn push(&self, item: T) -> Result<(), T> {
+        match self {
+            Fifo::Bounded(queue) => queue.push(item),
+            Fifo::Unbounded(queue) => {
+                queue.push(item);
+                Ok(())
+            }
+        }
+    }
+
+    #[inline]
+    pub fn pop(&self) -> Option<T> {
+        match self {
+            Fifo::Bounded(queue) => queue.pop(),
+            Fifo::Unbounded(queue) => queue.pop(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Fifo::Bounded(queue) => queue.len(),
+            Fifo::Unbounded(queue) => queue.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The bounded capacity, or `None` for an unbounded queue.
+    pub fn capacity(&self) -> Option<usize> {
+        match self {
+            Fifo::Bounded(queue) => Some(queue.capacity()),
+            Fifo::Unbounded(_) => None,
+        }
+    }
+}
+
+/// The shared body of the queues' `Debug` impls: `Name { capacity, len, pop_cursor,
+/// push_cursor }`, with `capacity` left out when there is none.
+///
+/// Only the cursors are read, never the slots: `fmt` takes `&self`, so a push or pop may be
+/// running on another thread, and copying an element out from under it would be a data race.
+/// `len` is whatever the two loads saw, so it can be momentarily off while the queue is in use.
+pub(crate) fn debug_queue(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    capacity: Option<usize>,
+    pop_cursor: usize,
+    push_cursor: usize,
+) -> fmt::Result {
+    let mut out = f.debug_struct(name);
+    if let Some(capacity) = capacity {
+        out.field("capacity", &capacity);
+    }
+    // Loaded one after the other, the pop cursor can already be past the push cursor we saw.
+    let len = (push_cursor.wrapping_sub(pop_cursor) as isize).max(0) as usize;
+    out.field("len", &len)
+        .field("pop_cursor", &pop_cursor)
+        .field("push_cursor", &push_cursor)
+        .finish()
+}
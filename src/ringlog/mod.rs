@@ -0,0 +1,255 @@
+//! A lossy ring for metrics and logging: writers never block and simply lap the reader,
+//! which takes whatever recent entries are still intact.
+//!
+//! Each slot is a small seqlock. Its sequence number is odd while a write is in progress and
+//! `2 * (pos + 1)` once position `pos` has been written, so a reader can tell both torn reads
+//! (the number changed while it was copying) and stale ones (the slot has since been reused
+//! for a later position) and skip them. Like the positions, sequence numbers wrap (after
+//! 2^31 writes on 32-bit targets), so they are only ever compared with `wrapping_sub`.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering, fence};
+
+use crate::builder::normalize_capacity;
+use crate::cache_padded::CachePadded;
+
+struct Slot<T> {
+    seq: AtomicUsize,
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Overwriting ring of `Copy` samples.
+///
+/// Readers copy a slot while a writer may be overwriting it and then throw the copy away if
+/// the sequence number moved; `T: Copy` is what makes reading a half-written value and
+/// discarding it harmless (there is no destructor to run and no ownership to lose).
+pub struct RingLog<T: Copy> {
+    capacity: usize,
+    ring: Box<[Slot<T>]>,
+    // Next position to write. Only ever grows (wrapping); `pos & (capacity - 1)` is its slot.
+    head: CachePadded<AtomicUsize>,
+    // Where `head` started: positions before it were never written.
+    start: usize,
+}
+
+unsafe impl<T: Copy + Send> Sync for RingLog<T> {}
+unsafe impl<T: Copy + Send> Send for RingLog<T> {}
+
+impl<T: Copy> RingLog<T> {
+    /// Keeps the latest `capacity` entries, rounded up to a power of two: `new(1000)` keeps
+    /// 1024.
+    pub fn new(capacity: usize) -> RingLog<T> {
+        RingLog::with_start_cursor(capacity, 0)
+    }
+
+    /// Starts the write position at `start`, for tests that cross the `usize::MAX` wrap.
+    #[doc(hidden)]
+    pub fn with_start_cursor(capacity: usize, start: usize) -> RingLog<T> {
+        let capacity = normalize_capacity(capacity, true);
+        let mut ring = Vec::with_capacity(capacity);
+        for i in 0..capacity {
+            // As if written a lap before the slot's first position: older than anything a
+            // writer will put there, and at a position no reader asks for.
+            let first = start.wrapping_add(i.wrapping_sub(start) & (capacity - 1));
+            ring.push(Slot {
+                seq: AtomicUsize::new(seq_written(first.wrapping_sub(capacity))),
+                data: UnsafeCell::new(MaybeUninit::uninit()),
+            });
+        }
+
+        RingLog {
+            capacity,
+            ring: ring.into_boxed_slice(),
+            head: CachePadded(AtomicUsize::new(start)),
+            start,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Total number of writes so far, including the ones that have since been overwritten.
+    pub fn written(&self) -> usize {
+        self.head.0.load(Ordering::Acquire).wrapping_sub(self.start)
+    }
+
+    /// Appends `item`, overwriting the oldest entry once the ring is full. Never fails and
+    /// never waits for readers.
+    ///
+    /// Safe to call from any number of threads: a writer takes the slot's sequence number
+    /// like a spinlock, which only contends when writers are a full lap apart. A single
+    /// writer can skip that with [`push_overwrite_unchecked`](Self::push_overwrite_unchecked).
+    pub fn write(&self, item: T) {
+        let pos = self.head.0.fetch_add(1, Ordering::Relaxed);
+        let slot = &self.ring[pos & (self.capacity - 1)];
+        let done = seq_written(pos);
+
+        let mut seq = slot.seq.load(Ordering::Relaxed);
+        loop {
+            if seq & 1 == 1 {
+                core::hint::spin_loop();
+                seq = slot.seq.load(Ordering::Relaxed);
+            } else if seq.wrapping_sub(done) as isize >= 0 {
+                // A writer a lap ahead of us already filled this slot with a newer entry;
+                // ours would have been overwritten anyway.
+                return;
+            } else {
+                // Acquire orders our data write after the previous writer's.
+                match slot.seq.compare_exchange_weak(
+                    seq,
+                    done.wrapping_sub(1),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(current) => seq = current,
+                }
+            }
+        }
+
+        unsafe { Self::publish(slot, item, done) };
+    }
+
+    /// [`write`](Self::write) without the per-slot locking.
+    ///
+    /// # Safety
+    ///
+    /// There must be only one writer: no other call to this method or to `write` may run
+    /// concurrently. Readers are fine.
+    pub unsafe fn push_overwrite_unchecked(&self, item: T) {
+        let pos = self.head.0.load(Ordering::Relaxed);
+        let slot = &self.ring[pos & (self.capacity - 1)];
+        let done = seq_written(pos);
+
+        slot.seq.store(done.wrapping_sub(1), Ordering::Relaxed);
+        unsafe { Self::publish(slot, item, done) };
+        self.head.0.store(pos.wrapping_add(1), Ordering::Release);
+    }
+
+    // The caller has already marked the slot odd (write in progress).
+    unsafe fn publish(slot: &Slot<T>, item: T, done: usize) {
+        // Keeps the data write below from being reordered before the odd marker: a reader
+        // that sees any of the new bytes also sees the sequence number change.
+        fence(Ordering::Release);
+        unsafe { ptr::write_volatile(slot.data.get(), MaybeUninit::new(item)) };
+        slot.seq.store(done, Ordering::Release);
+    }
+
+    /// Copies the most recent intact entries into `out`, oldest first, and returns how many
+    /// were written. Entries being overwritten while we read them are skipped, so the result
+    /// may have gaps and can be shorter than both `out` and the capacity.
+    pub fn read_latest_n(&self, out: &mut [T]) -> usize {
+        let head = self.head.0.load(Ordering::Acquire);
+        let mut n = 0;
+
+        // Walk back from the newest entry, filling `out` from the front, then flip.
+        let mut pos = head;
+        for _ in 0..head.wrapping_sub(self.oldest(head)) {
+            if n == out.len() {
+                break;
+            }
+            pos = pos.wrapping_sub(1);
+            if let Some(item) = self.read_at(pos) {
+                out[n] = item;
+                n += 1;
+            }
+        }
+        out[..n].reverse();
+        n
+    }
+
+    /// A reader that walks the log in order from the oldest entry still in the ring and
+    /// counts what the writers overwrote before it got there.
+    pub fn reader(&self) -> RingLogReader<'_, T> {
+        RingLogReader {
+            log: self,
+            next: self.oldest(self.head.0.load(Ordering::Acquire)),
+            missed: 0,
+        }
+    }
+
+    // The oldest position that can still be in the ring while `head` is the next to write.
+    fn oldest(&self, head: usize) -> usize {
+        head.wrapping_sub(head.wrapping_sub(self.start).min(self.capacity))
+    }
+
+    fn read_at(&self, pos: usize) -> Option<T> {
+        let slot = &self.ring[pos & (self.capacity - 1)];
+        let expected = seq_written(pos);
+
+        if slot.seq.load(Ordering::Acquire) != expected {
+            return None;
+        }
+        // This read can race with a writer; it is volatile so it isn't elided or cached, and
+        // kept as MaybeUninit until the recheck below says the bytes are one whole value.
+        let copy = unsafe { ptr::read_volatile(slot.data.get()) };
+        fence(Ordering::Acquire);
+        if slot.seq.load(Ordering::Relaxed) != expected {
+            return None;
+        }
+        Some(unsafe { copy.assume_init() })
+    }
+}
+
+/// Cursor over a [`RingLog`] that reads every entry once, in write order, and notices when
+/// writers have lapped it.
+///
+/// The slot sequence numbers double as generations: finding a slot's number past the one
+/// for the position we wanted means it was reused for a later lap, and everything older
+/// than the last `capacity` writes is gone. The reader skips to the oldest surviving entry
+/// and adds the skipped positions to [`missed_count`](Self::missed_count).
+pub struct RingLogReader<'a, T: Copy> {
+    log: &'a RingLog<T>,
+    next: usize,
+    missed: usize,
+}
+
+impl<T: Copy> RingLogReader<'_, T> {
+    /// The next entry, or `None` once the reader has caught up with the writers (or the
+    /// entry it is waiting for is still being written).
+    pub fn read_next(&mut self) -> Option<T> {
+        let log = self.log;
+        loop {
+            let head = log.head.0.load(Ordering::Acquire);
+            if self.next == head {
+                return None;
+            }
+            if let Some(item) = log.read_at(self.next) {
+                self.next = self.next.wrapping_add(1);
+                return Some(item);
+            }
+
+            let seq = log.ring[self.next & (log.capacity - 1)]
+                .seq
+                .load(Ordering::Relaxed);
+            if (seq.wrapping_sub(seq_written(self.next)) as isize) < 0 {
+                // Claimed by a writer that hasn't finished yet.
+                return None;
+            }
+            // Lapped (possibly mid-read): only the last `capacity` positions can still be
+            // intact, and a slot past ours means `head` is at least a lap beyond `next`.
+            let skip = head
+                .wrapping_sub(self.next)
+                .saturating_sub(log.capacity)
+                .max(1);
+            self.missed += skip;
+            self.next = self.next.wrapping_add(skip);
+        }
+    }
+
+    /// Entries that were overwritten before this reader got to them.
+    pub fn missed_count(&self) -> usize {
+        self.missed
+    }
+}
+
+// Sequence number of a slot once position `pos` has been written into it. Always even; one
+// less (wrapping) is the in-progress marker.
+fn seq_written(pos: usize) -> usize {
+    pos.wrapping_add(1).wrapping_mul(2)
+}
@@ -0,0 +1,35 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Snapshot of a queue's counters, returned by e.g. [`Fifo4::stats`](crate::Fifo4::stats).
+///
+/// Each counter is read independently with `Relaxed`, so while both sides are running the
+/// fields can be slightly out of step with each other.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FifoStats {
+    /// `push` calls that found the queue full.
+    pub push_full_count: u64,
+    /// `pop` calls that found the queue empty.
+    pub pop_empty_count: u64,
+    pub total_pushed: u64,
+    pub total_popped: u64,
+}
+
+// One side's counters. Each side gets its own padded copy so counting never bounces a
+// cache line between the producer and the consumer, or onto the cursor lines.
+#[derive(Default)]
+pub(crate) struct SideCounters {
+    pub(crate) done: AtomicU64,
+    pub(crate) failed: AtomicU64,
+}
+
+impl SideCounters {
+    #[inline]
+    pub(crate) fn record_done(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn record_failed(&self) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+}
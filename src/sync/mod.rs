@@ -0,0 +1,57 @@
+// Atomics and UnsafeCell for the queues that are model-checked with loom (see tests/loom.rs).
+// Under `--cfg loom` they come from loom so it can explore every interleaving; otherwise they
+// are the core types, with a zero-cost wrapper giving UnsafeCell loom's `with` / `with_mut` API.
+
+#[cfg(loom)]
+pub(crate) use loom::cell::UnsafeCell;
+#[cfg(loom)]
+pub(crate) use loom::hint::spin_loop;
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{AtomicUsize, Ordering};
+
+// loom's spin_loop yields to the scheduler, so spin-waits don't exhaust the model.
+#[cfg(not(loom))]
+pub(crate) use core::hint::spin_loop;
+#[cfg(not(loom))]
+pub(crate) use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(not(loom))]
+pub(crate) struct UnsafeCell<T>(core::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    pub(crate) const fn new(data: T) -> UnsafeCell<T> {
+        UnsafeCell(core::cell::UnsafeCell::new(data))
+    }
+
+    #[inline(always)]
+    pub(crate) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    #[inline(always)]
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
+
+// Orderings for the queues' synchronizing accesses. The `strict_ordering` feature turns them
+// all into SeqCst, which can't be too weak: if a failure goes away with it, one of the
+// optimized orderings is wrong. Only Fifo4 goes through these so far.
+pub(crate) mod order {
+    use super::Ordering;
+
+    #[cfg(not(feature = "strict_ordering"))]
+    pub(crate) const RELAXED: Ordering = Ordering::Relaxed;
+    #[cfg(not(feature = "strict_ordering"))]
+    pub(crate) const ACQUIRE: Ordering = Ordering::Acquire;
+    #[cfg(not(feature = "strict_ordering"))]
+    pub(crate) const RELEASE: Ordering = Ordering::Release;
+
+    #[cfg(feature = "strict_ordering")]
+    pub(crate) const RELAXED: Ordering = Ordering::SeqCst;
+    #[cfg(feature = "strict_ordering")]
+    pub(crate) const ACQUIRE: Ordering = Ordering::SeqCst;
+    #[cfg(feature = "strict_ordering")]
+    pub(crate) const RELEASE: Ordering = Ordering::SeqCst;
+}
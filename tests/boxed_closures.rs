@@ -0,0 +1,96 @@
+//! A work queue of boxed closures: every closure pushed must end up either called or
+//! dropped exactly once, whether it is popped or still queued when the queue is dropped.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use lockfree_fifo::{
+    Fifo5, Fifo5FetchAdd, Fifo6, Fifo6Const, Fifo6Fair, Fifo6a, FifoMpsc, SpscQueue,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct Counts {
+    called: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+// Captured by each job; its Drop runs whether the job is called (consuming the closure) or
+// dropped unrun.
+struct Token(Arc<Counts>);
+
+impl Drop for Token {
+    fn drop(&mut self) {
+        self.0.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn job(counts: &Arc<Counts>) -> Job {
+    let token = Token(counts.clone());
+    Box::new(move || {
+        token.0.called.fetch_add(1, Ordering::Relaxed);
+        drop(token);
+    })
+}
+
+// Pushes 8 jobs into a queue of capacity 8, runs 3, pushes 2 more (so the live region
+// wraps), runs 1, and drops the queue with 6 still inside.
+fn call_some_drop_the_rest<Q: SpscQueue<Job>>(queue: Q) {
+    let counts = Arc::new(Counts::default());
+    for _ in 0..8 {
+        assert!(queue.try_push(job(&counts)).is_ok());
+    }
+    for _ in 0..3 {
+        queue.try_pop().unwrap()();
+    }
+    for _ in 0..2 {
+        assert!(queue.try_push(job(&counts)).is_ok());
+    }
+    queue.try_pop().unwrap()();
+    // Popped but neither called nor pushed back: dropped here.
+    drop(queue.try_pop().unwrap());
+
+    assert_eq!(counts.called.load(Ordering::Relaxed), 4);
+    assert_eq!(counts.dropped.load(Ordering::Relaxed), 5);
+    drop(queue);
+    assert_eq!(counts.called.load(Ordering::Relaxed), 4);
+    assert_eq!(counts.dropped.load(Ordering::Relaxed), 10);
+    // Only our own handle is left: no job leaked its token.
+    assert_eq!(Arc::strong_count(&counts), 1);
+}
+
+#[test]
+fn fifo5_runs_or_drops_every_job_once() {
+    call_some_drop_the_rest(Fifo5::new(8));
+}
+
+#[test]
+fn fifo5_fetch_add_runs_or_drops_every_job_once() {
+    call_some_drop_the_rest(Fifo5FetchAdd::new(8));
+}
+
+#[test]
+fn fifo6_runs_or_drops_every_job_once() {
+    call_some_drop_the_rest(Fifo6::new(8));
+}
+
+#[test]
+fn fifo6a_runs_or_drops_every_job_once() {
+    call_some_drop_the_rest(Fifo6a::new(8));
+}
+
+#[test]
+fn fifo6_const_runs_or_drops_every_job_once() {
+    call_some_drop_the_rest(Fifo6Const::<Job, 8>::new());
+}
+
+#[test]
+fn fifo6_fair_runs_or_drops_every_job_once() {
+    call_some_drop_the_rest(Fifo6Fair::new(8));
+}
+
+#[test]
+fn fifo_mpsc_runs_or_drops_every_job_once() {
+    call_some_drop_the_rest(FifoMpsc::new(8));
+}
@@ -0,0 +1,43 @@
+use lockfree_fifo::builder::capacity_for_message_size;
+use lockfree_fifo::{Fifo5, FifoError};
+
+#[test]
+fn rounds_up_to_power_of_two() {
+    // 1 MiB / 40 bytes = 26214 slots.
+    assert_eq!(capacity_for_message_size(40, 1 << 20), Ok(32768));
+    assert_eq!(capacity_for_message_size(64, 1 << 20), Ok(16384));
+    assert_eq!(capacity_for_message_size(1, 1), Ok(1));
+}
+
+#[test]
+fn budget_smaller_than_one_message() {
+    assert_eq!(capacity_for_message_size(4096, 100), Ok(1));
+    assert_eq!(capacity_for_message_size(8, 0), Ok(1));
+}
+
+#[test]
+fn zero_message_size() {
+    assert_eq!(
+        capacity_for_message_size(0, 1 << 20),
+        Err(FifoError::ZeroMessageSize)
+    );
+    assert!(Fifo5::<()>::for_message_size(0, 1024).is_err());
+}
+
+#[test]
+fn overflow() {
+    assert_eq!(
+        capacity_for_message_size(1, usize::MAX),
+        Err(FifoError::CapacityOverflow(usize::MAX))
+    );
+    // Exactly the largest power of two still fits.
+    let top = 1usize << (usize::BITS - 1);
+    assert_eq!(capacity_for_message_size(1, top), Ok(top));
+}
+
+#[test]
+fn constructors() {
+    assert_eq!(Fifo5::<u64>::auto().capacity(), 1 << 16);
+    let queue = Fifo5::<[u8; 40]>::for_message_size(40, 4096).unwrap();
+    assert_eq!(queue.capacity(), 128);
+}
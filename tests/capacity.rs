@@ -0,0 +1,158 @@
+use lockfree_fifo::{Fifo2, Fifo5, Fifo6, Fifo6Fair, Fifo6a, FifoError, FifoMpsc, RingLog};
+
+#[test]
+fn mask_indexed_queues_round_up() {
+    assert_eq!(Fifo6a::<u32>::new(100).capacity(), 128);
+    assert_eq!(Fifo6Fair::<u32>::new(100).capacity(), 128);
+    assert_eq!(RingLog::<u32>::new(100).capacity(), 128);
+    assert_eq!(Fifo6a::<u32>::new(64).capacity(), 64);
+
+    // The rounded-up slots are really usable.
+    let queue = Fifo6a::new(100);
+    for i in 0..128 {
+        queue.push(i).unwrap();
+    }
+    assert_eq!(queue.push(128), Err(128));
+}
+
+#[test]
+fn other_queues_keep_the_requested_capacity() {
+    assert_eq!(Fifo2::<u32>::new(100).capacity(), 100);
+    assert_eq!(Fifo5::<u32>::new(100).capacity(), 100);
+
+    // Fifo6 takes any size, falling back to `%` indexing instead of rounding.
+    let queue = Fifo6::new(100);
+    assert_eq!(queue.capacity(), 100);
+    for lap in 0..3 {
+        for i in 0..100 {
+            queue.push(lap * 100 + i).unwrap();
+        }
+        assert_eq!(queue.push(0), Err(0));
+        for i in 0..100 {
+            assert_eq!(queue.pop(), Some(lap * 100 + i));
+        }
+    }
+}
+
+#[test]
+fn fifo6_capacity_three_survives_many_laps() {
+    // 3 never divides the wrapping cursors evenly, so every lap starts on a different slot.
+    let queue = Fifo6::new(3);
+    assert_eq!(queue.capacity(), 3);
+    let (mut next_in, mut next_out) = (0, 0);
+    for round in 0..40 {
+        // Alternate between filling up and leaving one or two behind.
+        for _ in 0..(round % 3) + 1 {
+            if queue.push(next_in).is_ok() {
+                next_in += 1;
+            }
+        }
+        if round % 4 == 0 {
+            while queue.push(next_in).is_ok() {
+                next_in += 1;
+            }
+            assert_eq!(next_in - next_out, 3);
+        }
+        for _ in 0..(round % 2) + 1 {
+            if let Some(value) = queue.pop() {
+                assert_eq!(value, next_out);
+                next_out += 1;
+            }
+        }
+    }
+    while let Some(value) = queue.pop() {
+        assert_eq!(value, next_out);
+        next_out += 1;
+    }
+    assert_eq!(next_out, next_in);
+    // Many laps, not just one.
+    assert!(next_in > 10 * 3);
+}
+
+#[test]
+#[should_panic(expected = "power of two")]
+fn rounding_overflow_panics() {
+    Fifo6a::<u8>::new(usize::MAX / 2 + 2);
+}
+
+#[test]
+fn fifo6_try_new_reports_bad_capacities() {
+    assert_eq!(Fifo6::<u8>::try_new(0).err(), Some(FifoError::ZeroCapacity));
+    assert_eq!(
+        Fifo6::<u8>::try_new(1).err(),
+        Some(FifoError::CapacityTooSmall {
+            capacity: 1,
+            min: 2
+        })
+    );
+    assert_eq!(Fifo6::<u8>::try_new(2).unwrap().capacity(), 2);
+    assert_eq!(Fifo6::<u8>::try_new(100).unwrap().capacity(), 100);
+}
+
+#[test]
+fn fifo6a_rejects_single_slot_rings() {
+    // 0 and 1 would both round to one slot, where a push can't tell full from empty.
+    assert_eq!(
+        Fifo6a::<u8>::try_new(0).err(),
+        Some(FifoError::ZeroCapacity)
+    );
+    assert_eq!(
+        Fifo6a::<u8>::try_new(1).err(),
+        Some(FifoError::CapacityTooSmall {
+            capacity: 1,
+            min: 2
+        })
+    );
+    assert_eq!(Fifo6a::<u8>::try_new(2).unwrap().capacity(), 2);
+    assert_eq!(Fifo6a::<u8>::try_new(3).unwrap().capacity(), 4);
+    assert!(std::panic::catch_unwind(|| Fifo6a::<String>::new(0)).is_err());
+    assert!(std::panic::catch_unwind(|| Fifo6a::<String>::new(1)).is_err());
+}
+
+#[test]
+fn fifo6fair_rejects_single_slot_rings() {
+    assert_eq!(
+        Fifo6Fair::<u32>::try_new(0).err(),
+        Some(FifoError::ZeroCapacity)
+    );
+    assert_eq!(
+        Fifo6Fair::<u32>::try_new(1).err(),
+        Some(FifoError::CapacityTooSmall {
+            capacity: 1,
+            min: 2
+        })
+    );
+    assert!(std::panic::catch_unwind(|| Fifo6Fair::<u32>::new(1)).is_err());
+
+    // The smallest accepted ring keeps both items.
+    let queue = Fifo6Fair::try_new(2).unwrap();
+    queue.push(1).unwrap();
+    queue.push(2).unwrap();
+    assert_eq!(queue.push(3), Err(3));
+    assert_eq!(queue.pop(), 1);
+    assert_eq!(queue.pop(), 2);
+}
+
+#[test]
+fn fifo_mpsc_rejects_single_slot_rings() {
+    assert_eq!(
+        FifoMpsc::<u32>::try_new(0).err(),
+        Some(FifoError::ZeroCapacity)
+    );
+    assert_eq!(
+        FifoMpsc::<u32>::try_new(1).err(),
+        Some(FifoError::CapacityTooSmall {
+            capacity: 1,
+            min: 2
+        })
+    );
+    assert!(std::panic::catch_unwind(|| FifoMpsc::<u32>::new(0)).is_err());
+    assert!(std::panic::catch_unwind(|| FifoMpsc::<u32>::new(1)).is_err());
+    assert_eq!(FifoMpsc::<u32>::try_new(2).unwrap().capacity(), 2);
+}
+
+#[test]
+#[should_panic(expected = "capacity 1 is below the minimum of 2")]
+fn fifo6_new_panics_with_the_error() {
+    Fifo6::<u8>::new(1);
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "std")]
+
+use std::thread;
+
+use lockfree_fifo::channel::{RecvError, TryRecvError, channel};
+
+#[test]
+fn recv_delivers_in_order_then_ends_once_the_sender_is_dropped() {
+    let (tx, rx) = channel(4);
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+    let sender = thread::spawn(move || {
+        for i in 0..100 {
+            let mut item = i;
+            while let Err(err) = tx.send(item) {
+                item = err.0;
+                thread::yield_now();
+            }
+        }
+        // Dropping `tx` disconnects; what is still queued is delivered first.
+    });
+
+    for i in 0..100 {
+        assert_eq!(rx.recv(), Ok(i));
+    }
+    sender.join().unwrap();
+    assert_eq!(rx.recv(), Err(RecvError));
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+}
+
+#[test]
+fn send_fails_once_the_receiver_is_dropped() {
+    let (tx, rx) = channel(4);
+    tx.send(1).unwrap();
+    drop(rx);
+    assert_eq!(tx.send(2).map_err(|err| err.0), Err(2));
+}
+
+#[test]
+fn receiver_iterator_ends_when_the_sender_is_dropped() {
+    const N: usize = 10_000;
+    // Smaller than N, so the sender has to wait for the receiver along the way.
+    let (tx, rx) = channel(64);
+
+    let sender = thread::spawn(move || {
+        for i in 0..N {
+            let mut item = i;
+            while let Err(err) = tx.send(item) {
+                item = err.0;
+                thread::yield_now();
+            }
+        }
+        // `tx` is dropped here, which ends the loop below.
+    });
+
+    let mut count = 0;
+    for item in rx {
+        assert_eq!(item, count);
+        count += 1;
+    }
+    assert_eq!(count, N);
+    sender.join().unwrap();
+}
+
+#[test]
+fn borrowed_iterator_drains_what_is_left() {
+    let (tx, rx) = channel(8);
+    for i in 0..5 {
+        tx.send(i).unwrap();
+    }
+    drop(tx);
+
+    assert_eq!(rx.iter().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    assert!(rx.recv().is_err());
+    assert_eq!((&rx).into_iter().next(), None);
+}
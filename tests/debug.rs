@@ -0,0 +1,139 @@
+//! `{:?}` on the queues: capacity, length and the cursors. Only `Fifo1`, which can't be
+//! touched through `&self`, prints the queued items.
+
+use lockfree_fifo::{
+    Fifo, Fifo1, Fifo2, Fifo3, Fifo4, Fifo5, Fifo5FetchAdd, Fifo6, Fifo6Const, Fifo6Fair, Fifo6a,
+    FifoMpsc, FifoUnbounded,
+};
+
+#[test]
+fn debug_shows_the_cursors_and_follows_pops() {
+    let queue = Fifo2::new(4);
+    assert_eq!(
+        format!("{queue:?}"),
+        "Fifo2 { capacity: 4, len: 0, pop_cursor: 0, push_cursor: 0 }"
+    );
+    queue.push(1).unwrap();
+    queue.push(2).unwrap();
+    assert_eq!(
+        format!("{queue:?}"),
+        "Fifo2 { capacity: 4, len: 2, pop_cursor: 0, push_cursor: 2 }"
+    );
+    queue.pop();
+    assert_eq!(
+        format!("{queue:?}"),
+        "Fifo2 { capacity: 4, len: 1, pop_cursor: 1, push_cursor: 2 }"
+    );
+}
+
+// Every queue, each with the front past slot 0 so the items wrap around the ring.
+macro_rules! check_wrapped {
+    ($name:literal, $queue:expr, $capacity:expr) => {{
+        let queue = $queue;
+        for i in 0..3 {
+            queue.push(i.to_string()).unwrap();
+        }
+        for _ in 0..3 {
+            queue.pop().unwrap();
+        }
+        for i in 3..6 {
+            queue.push(i.to_string()).unwrap();
+        }
+        assert_eq!(
+            format!("{queue:?}"),
+            format!(
+                "{} {{ capacity: {}, len: 3, pop_cursor: 3, push_cursor: 6 }}",
+                $name, $capacity
+            )
+        );
+        queue.pop().unwrap();
+        assert!(format!("{queue:?}").ends_with("len: 2, pop_cursor: 4, push_cursor: 6 }"));
+    }};
+}
+
+#[test]
+fn debug_reports_wrapped_cursors() {
+    check_wrapped!("Fifo2", Fifo2::new(4), 4);
+    check_wrapped!("Fifo3", Fifo3::new(4), 4);
+    check_wrapped!("Fifo4", Fifo4::new(4), 4);
+    check_wrapped!("Fifo5", Fifo5::new(4), 4);
+    check_wrapped!("Fifo5FetchAdd", Fifo5FetchAdd::new(4), 4);
+    check_wrapped!("Fifo6", Fifo6::new(4), 4);
+    check_wrapped!("Fifo6a", Fifo6a::new(4), 4);
+    check_wrapped!("Fifo6Const", Fifo6Const::<_, 4>::new(), 4);
+    check_wrapped!("FifoMpsc", FifoMpsc::new(4), 4);
+
+    // Different pop signature.
+    let fair = Fifo6Fair::new(4);
+    for i in 0..3 {
+        fair.push(i.to_string()).unwrap();
+    }
+    fair.pop();
+    assert_eq!(
+        format!("{fair:?}"),
+        "Fifo6Fair { capacity: 4, len: 2, pop_cursor: 1, push_cursor: 3 }"
+    );
+
+    let mut fifo1 = Fifo1::new(2);
+    fifo1.push('a').unwrap();
+    fifo1.pop();
+    fifo1.push('b').unwrap();
+    fifo1.push('c').unwrap();
+    assert_eq!(
+        format!("{fifo1:?}"),
+        "Fifo1 { capacity: 2, len: 2, items: ['b', 'c'] }"
+    );
+}
+
+#[test]
+fn debug_follows_unbounded_cursors_across_blocks() {
+    let queue = FifoUnbounded::new();
+    for i in 0..70 {
+        queue.push(i);
+    }
+    // Leaves the consumer at the end of the first 64-slot block.
+    for _ in 0..64 {
+        queue.pop();
+    }
+    assert_eq!(
+        format!("{queue:?}"),
+        "FifoUnbounded { len: 6, pop_cursor: 64, push_cursor: 70 }"
+    );
+    queue.pop();
+    assert_eq!(
+        format!("{queue:?}"),
+        "FifoUnbounded { len: 5, pop_cursor: 65, push_cursor: 70 }"
+    );
+
+    let fifo = Fifo::bounded(2);
+    fifo.push(7).unwrap();
+    assert_eq!(
+        format!("{fifo:?}"),
+        "Bounded(Fifo5 { capacity: 2, len: 1, pop_cursor: 0, push_cursor: 1 })"
+    );
+}
+
+#[test]
+fn debug_does_not_read_claimed_but_unwritten_slots() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use lockfree_fifo::fifo6::Slot;
+
+    let mut ring: Vec<Slot<String>> = (0..4).map(|_| Slot::new()).collect();
+    let mut head = AtomicUsize::new(0);
+    let mut tail = AtomicUsize::new(0);
+    let (ring, head, tail) = (ring.as_mut_ptr(), &raw mut head, &raw mut tail);
+    let queue = unsafe { Fifo6::init_raw_parts(ring, 4, head, tail) };
+    queue.push("a".to_string()).unwrap();
+
+    // A producer that has claimed position 1 but not written it yet: the slot is still
+    // uninitialized, and formatting must not look at it (Miri flags it if it does).
+    unsafe { (*tail).fetch_add(1, Ordering::Relaxed) };
+    assert_eq!(
+        format!("{queue:?}"),
+        "Fifo6 { capacity: 4, len: 2, pop_cursor: 0, push_cursor: 2 }"
+    );
+
+    unsafe { (*tail).fetch_sub(1, Ordering::Relaxed) };
+    assert_eq!(queue.pop().as_deref(), Some("a"));
+}
@@ -0,0 +1,74 @@
+use lockfree_fifo::Fifo2;
+
+#[test]
+fn peek_at_looks_ahead_without_popping() {
+    let queue = Fifo2::new(16);
+    for i in 0..10 {
+        queue.push(i).unwrap();
+    }
+
+    assert_eq!(queue.peek_at(0), Some(&0));
+    assert_eq!(queue.peek_at(3), Some(&3));
+    assert_eq!(queue.peek_at(9), Some(&9));
+    assert_eq!(queue.peek_at(10), None);
+
+    assert_eq!(queue.size(), 10);
+    assert_eq!(queue.pop(), Some(0));
+    assert_eq!(queue.peek_at(0), Some(&1));
+}
+
+#[test]
+fn pop_if_leaves_rejected_front_in_place() {
+    let queue = Fifo2::new(8);
+    for i in [2, 4, 5, 6] {
+        queue.push(i).unwrap();
+    }
+
+    let is_even = |v: &i32| v % 2 == 0;
+    assert_eq!(queue.pop_if(is_even), Some(2));
+    assert_eq!(queue.pop_if(is_even), Some(4));
+    assert_eq!(queue.pop_if(is_even), None);
+    assert_eq!(queue.pop_if(is_even), None);
+    assert_eq!(queue.peek(), Some(&5));
+    assert_eq!(queue.size(), 2);
+
+    assert_eq!(queue.pop(), Some(5));
+    assert_eq!(queue.pop_if(is_even), Some(6));
+    assert_eq!(queue.pop_if(|_| true), None);
+}
+
+#[test]
+fn front_replace_swaps_front_in_place() {
+    let queue = Fifo2::new(4);
+    assert_eq!(queue.front_replace(9), None);
+    assert_eq!(queue.size(), 0);
+
+    queue.push(1).unwrap();
+    queue.push(2).unwrap();
+    assert_eq!(queue.front_replace(10), Some(1));
+    assert_eq!(queue.size(), 2);
+    assert_eq!(queue.pop(), Some(10));
+    assert_eq!(queue.pop(), Some(2));
+    assert_eq!(queue.pop(), None);
+}
+
+#[test]
+fn snapshot_clones_without_popping() {
+    let queue = Fifo2::new(4);
+    assert!(queue.snapshot().is_empty());
+
+    // Front at slot 3, so the live items wrap.
+    for i in 0..3 {
+        queue.push(i.to_string()).unwrap();
+        queue.pop();
+    }
+    for i in 3..7 {
+        queue.push(i.to_string()).unwrap();
+    }
+    assert_eq!(queue.snapshot(), ["3", "4", "5", "6"]);
+    // Taking it again gives the same answer: nothing was consumed.
+    assert_eq!(queue.snapshot(), ["3", "4", "5", "6"]);
+
+    queue.pop();
+    assert_eq!(queue.snapshot(), ["4", "5", "6"]);
+}
@@ -0,0 +1,349 @@
+use lockfree_fifo::Fifo4;
+use lockfree_fifo::fifo4::{Backpressure, RoundRobin};
+
+#[test]
+fn push_deferred_publishes_at_threshold_or_flush() {
+    let queue = Fifo4::new(8).with_flush_threshold(3);
+
+    queue.push_deferred(1).unwrap();
+    queue.push_deferred(2).unwrap();
+    assert_eq!(queue.pop(), None);
+    assert_eq!(queue.len(), 0);
+
+    queue.push_deferred(3).unwrap();
+    assert_eq!(queue.len(), 3);
+
+    queue.push_deferred(4).unwrap();
+    assert_eq!(queue.len(), 3);
+    queue.flush();
+    assert_eq!(queue.len(), 4);
+
+    // A plain push publishes whatever was pending along with its own item.
+    queue.push_deferred(5).unwrap();
+    queue.push(6).unwrap();
+    let popped: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+    assert_eq!(popped, [1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn push_deferred_publishes_when_full() {
+    let queue = Fifo4::new(4).with_flush_threshold(16);
+    for i in 0..4 {
+        queue.push_deferred(i).unwrap();
+    }
+    assert_eq!(queue.len(), 0);
+
+    // The ring is full of unpublished items; failing must hand them to the consumer.
+    assert_eq!(queue.push_deferred(4), Err(4));
+    assert_eq!(queue.len(), 4);
+    assert_eq!(queue.pop(), Some(0));
+    queue.push_deferred(4).unwrap();
+}
+
+#[test]
+fn round_robin_interleaves_until_all_are_empty() {
+    let queues = [Fifo4::new(8), Fifo4::new(8), Fifo4::new(8)];
+    for (q, len) in queues.iter().zip([4, 1, 2]) {
+        for i in 0..len {
+            q.push(i).unwrap();
+        }
+    }
+    let refs: Vec<_> = queues.iter().collect();
+
+    let mut rr = RoundRobin::new(&refs);
+    let popped: Vec<_> = rr.by_ref().collect();
+    assert_eq!(
+        popped,
+        [(0, 0), (1, 0), (2, 0), (0, 1), (2, 1), (0, 2), (0, 3)]
+    );
+
+    // Refilled queues are picked up again, starting after the last one popped from.
+    queues[0].push(10).unwrap();
+    queues[2].push(20).unwrap();
+    assert_eq!(rr.next(), Some((2, 20)));
+    assert_eq!(rr.next(), Some((0, 10)));
+    assert_eq!(rr.next(), None);
+}
+
+#[cfg(all(debug_assertions, feature = "std"))]
+#[test]
+#[should_panic(expected = "overlapping pushes")]
+fn overlapping_pushes_trip_the_debug_check() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::{Duration, Instant};
+
+    // Stops the other pusher once one of them has panicked.
+    struct StopOnDrop<'a>(&'a AtomicBool);
+    impl Drop for StopOnDrop<'_> {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Capacity 1 and no consumer: after the first item every push fails fast, but it still
+    // counts as a push in progress, so two threads hammering it overlap sooner or later.
+    let queue = Fifo4::new(1);
+    let stop = AtomicBool::new(false);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    std::thread::scope(|s| {
+        let pushers: Vec<_> = (0..2)
+            .map(|_| {
+                s.spawn(|| {
+                    let _stop = StopOnDrop(&stop);
+                    while !stop.load(Ordering::Relaxed) && Instant::now() < deadline {
+                        let _ = queue.push(0);
+                    }
+                })
+            })
+            .collect();
+        for pusher in pushers {
+            if let Err(panic) = pusher.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+    });
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn split_halves_run_on_their_own_threads() {
+    use std::thread;
+
+    const N: u32 = 10_000;
+    let (producer, consumer) = Fifo4::new(16).split();
+
+    let producer = thread::spawn(move || {
+        for i in 0..N {
+            let mut item = i;
+            while let Err(rejected) = producer.push(item) {
+                item = rejected;
+                thread::yield_now();
+            }
+        }
+    });
+    let consumer = thread::spawn(move || {
+        let mut received = Vec::with_capacity(N as usize);
+        while received.len() < N as usize {
+            match consumer.pop() {
+                Some(item) => received.push(item),
+                None => thread::yield_now(),
+            }
+        }
+        assert_eq!(consumer.pop(), None);
+        received
+    });
+
+    producer.join().unwrap();
+    assert_eq!(consumer.join().unwrap(), (0..N).collect::<Vec<_>>());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn producer_can_move_between_threads_between_pushes() {
+    let (producer, consumer) = Fifo4::new(8).split();
+    producer.push(0).unwrap();
+    let producer = std::thread::spawn(move || {
+        producer.push(1).unwrap();
+        producer
+    })
+    .join()
+    .unwrap();
+    let producer = std::thread::spawn(move || {
+        producer.push(2).unwrap();
+        producer
+    })
+    .join()
+    .unwrap();
+    producer.push(3).unwrap();
+    let popped: Vec<_> = std::iter::from_fn(|| consumer.pop()).collect();
+    assert_eq!(popped, [0, 1, 2, 3]);
+}
+
+#[test]
+fn lap_count_counts_full_trips_around_the_ring() {
+    let queue = Fifo4::new(4);
+    assert_eq!(queue.lap_count(), 0);
+    // 3.5 laps, keeping the queue short so every push fits.
+    for i in 0..14 {
+        queue.push(i).unwrap();
+        assert_eq!(queue.pop(), Some(i));
+    }
+    assert_eq!(queue.lap_count(), 3);
+    queue.push(14).unwrap();
+    queue.push(15).unwrap();
+    assert_eq!(queue.lap_count(), 4);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn consumer_stops_once_the_last_producer_guard_drops() {
+    use std::sync::Arc;
+    use std::thread;
+
+    let queue = Arc::new(Fifo4::new(4).with_flush_threshold(8));
+    let guard = queue.producer();
+    assert_eq!(queue.producers_alive(), 1);
+
+    let producer = thread::spawn(move || {
+        for i in 0..1000 {
+            // Deferred, so the last few are only published when the guard drops.
+            let mut item = i;
+            while let Err(rejected) = guard.push_deferred(item) {
+                item = rejected;
+                thread::yield_now();
+            }
+        }
+    });
+
+    let mut received = Vec::new();
+    loop {
+        if let Some(item) = queue.pop() {
+            received.push(item);
+        } else if queue.producers_alive() == 0 {
+            match queue.pop() {
+                Some(item) => received.push(item),
+                None => break,
+            }
+        } else {
+            thread::yield_now();
+        }
+    }
+    producer.join().unwrap();
+    assert_eq!(received, (0..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn backpressure_has_hysteresis_between_watermarks() {
+    let mut queue = Fifo4::new(16);
+    queue.set_watermarks(12, 4);
+    assert_eq!(queue.backpressure_state(), Backpressure::Relieved);
+
+    for i in 0..12 {
+        queue.push(i).unwrap();
+    }
+    assert_eq!(queue.backpressure_state(), Backpressure::Engaged);
+
+    // Between the watermarks: still engaged on the way down.
+    for _ in 0..6 {
+        queue.pop().unwrap();
+    }
+    assert_eq!(queue.backpressure_state(), Backpressure::Engaged);
+
+    for _ in 0..2 {
+        queue.pop().unwrap();
+    }
+    assert_eq!(queue.backpressure_state(), Backpressure::Relieved);
+
+    // ...and relieved on the way back up until `high` is reached again.
+    for i in 0..7 {
+        queue.push(i).unwrap();
+    }
+    assert_eq!(queue.len(), 11);
+    assert_eq!(queue.backpressure_state(), Backpressure::Relieved);
+    queue.push(7).unwrap();
+    assert_eq!(queue.backpressure_state(), Backpressure::Engaged);
+}
+
+#[test]
+fn force_push_keeps_the_consumer_in_step_after_evictions() {
+    let queue = Fifo4::new(4);
+    for i in 0..14u32 {
+        // Safety: this thread is the only consumer and isn't inside `pop`.
+        let evicted = unsafe { queue.force_push(i) };
+        assert_eq!(evicted, i.checked_sub(4));
+    }
+    let drained: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+    assert_eq!(drained, [10, 11, 12, 13]);
+    assert_eq!(queue.pop(), None);
+
+    // The cursors must still agree afterwards.
+    for i in 100..110 {
+        queue.push(i).unwrap();
+        assert_eq!(queue.pop(), Some(i));
+    }
+    assert!(queue.is_empty());
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn stats_count_a_known_sequence() {
+    use lockfree_fifo::stats::FifoStats;
+
+    let queue = Fifo4::new(4);
+    assert_eq!(queue.stats(), FifoStats::default());
+
+    assert_eq!(queue.pop(), None);
+    for i in 0..4 {
+        queue.push(i).unwrap();
+    }
+    assert_eq!(queue.push(4), Err(4));
+    assert_eq!(queue.push(5), Err(5));
+    assert_eq!(queue.pop(), Some(0));
+    assert_eq!(
+        queue.stats(),
+        FifoStats {
+            push_full_count: 2,
+            pop_empty_count: 1,
+            total_pushed: 4,
+            total_popped: 1,
+        }
+    );
+
+    // Refill, then force two more in: each first finds the ring full, evicts the oldest
+    // (counted as a pop, so pushed - popped still equals len) and then pushes.
+    queue.push(6).unwrap();
+    // Safety: this thread is the only consumer and isn't inside `pop`.
+    assert_eq!(unsafe { queue.force_push(7) }, Some(1));
+    assert_eq!(unsafe { queue.force_push(8) }, Some(2));
+    let stats = queue.stats();
+    assert_eq!(
+        stats,
+        FifoStats {
+            push_full_count: 4,
+            pop_empty_count: 1,
+            total_pushed: 7,
+            total_popped: 3,
+        }
+    );
+    assert_eq!(
+        (stats.total_pushed - stats.total_popped) as usize,
+        queue.len()
+    );
+
+    while queue.pop().is_some() {}
+    let stats = queue.stats();
+    assert_eq!(stats.total_popped, stats.total_pushed);
+    assert_eq!(stats.pop_empty_count, 2);
+}
+
+#[cfg(feature = "progress")]
+#[test]
+fn progress_epochs_follow_successful_pushes_and_pops() {
+    let queue = Fifo4::new(2);
+    assert_eq!(queue.progress(), (0, 0));
+
+    queue.push(1).unwrap();
+    queue.push(2).unwrap();
+    // Failed calls leave the epochs alone.
+    assert_eq!(queue.push(3), Err(3));
+    assert_eq!(queue.progress(), (2, 0));
+
+    queue.pop().unwrap();
+    queue.pop().unwrap();
+    assert_eq!(queue.pop(), None);
+    assert_eq!(queue.progress(), (2, 2));
+
+    // Evictions move only the producer's epoch.
+    queue.push(4).unwrap();
+    queue.push(5).unwrap();
+    // Safety: this thread is the only consumer and isn't inside `pop`.
+    assert_eq!(unsafe { queue.force_push(6) }, Some(4));
+    assert_eq!(queue.progress(), (5, 2));
+
+    // A watchdog compares samples with wrapping_sub.
+    let (before, _) = queue.progress();
+    queue.pop().unwrap();
+    queue.push(7).unwrap();
+    let (after, _) = queue.progress();
+    assert_eq!(after.wrapping_sub(before), 1);
+}
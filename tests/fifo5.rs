@@ -0,0 +1,694 @@
+use lockfree_fifo::{Fifo5, FifoError};
+
+#[test]
+fn pop_while_stops_at_first_rejected_item() {
+    // Capacity 64 with a head start of 40, so the run wraps around the end of the ring.
+    let queue = Fifo5::new(64);
+    for i in 0..40 {
+        queue.push(i).unwrap();
+    }
+    queue.consume(40);
+    for i in 0..60 {
+        queue.push(i).unwrap();
+    }
+
+    let mut out = vec![-1];
+    assert_eq!(queue.pop_while(|&x| x < 50, &mut out), 50);
+    assert_eq!(out[0], -1);
+    assert!(out[1..].iter().copied().eq(0..50));
+
+    assert_eq!(queue.len(), 10);
+    assert_eq!(queue.front(), Some(&50));
+    assert_eq!(queue.pop_while(|&x| x < 50, &mut out), 0);
+    assert_eq!(out.len(), 51);
+
+    assert_eq!(queue.pop_while(|_| true, &mut out), 10);
+    assert_eq!(queue.pop_while(|_| true, &mut out), 0);
+    assert!(queue.is_empty());
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn high_water_mark_keeps_the_peak() {
+    let queue = Fifo5::new(100);
+    assert_eq!(queue.high_water_mark(), 0);
+
+    for i in 0..30 {
+        queue.push(i).unwrap();
+    }
+    queue.consume(25);
+    queue.push_batch(&mut vec![0; 10]);
+    assert_eq!(queue.len(), 15);
+    assert_eq!(queue.high_water_mark(), 30);
+    assert_eq!(queue.occupancy_ratio(), 0.3);
+
+    queue.try_extend(0..40);
+    assert_eq!(queue.high_water_mark(), 55);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn blocking_calls_only_unpark_a_waiting_thread() {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    let queue = Arc::new(Fifo5::new(128));
+
+    // Neither side ever has to wait, so nobody gets unparked.
+    for i in 0..100 {
+        queue.push_blocking(i);
+    }
+    for i in 0..100 {
+        assert_eq!(queue.pop_blocking(), i);
+    }
+    assert_eq!(queue.unpark_counts(), (0, 0));
+
+    // A consumer that really blocks is woken (at most) once.
+    let consumer = {
+        let queue = queue.clone();
+        thread::spawn(move || queue.pop_blocking())
+    };
+    thread::sleep(Duration::from_millis(50));
+    queue.push_blocking(7);
+    assert_eq!(consumer.join().unwrap(), 7);
+    let (_, woken) = queue.unpark_counts();
+    assert!(woken <= 1);
+
+    // Once it is running again, further traffic doesn't unpark it.
+    for i in 0..100 {
+        queue.push_blocking(i);
+        assert_eq!(queue.pop_blocking(), i);
+    }
+    assert_eq!(queue.unpark_counts(), (0, woken));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn blocking_pop_wakes_whichever_thread_is_waiting() {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    let queue = Arc::new(Fifo5::new(4));
+    // One consumer after another, each on a fresh thread: the second must be woken too,
+    // not the thread that happened to block first.
+    for i in 0..2 {
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.pop_blocking())
+        };
+        thread::sleep(Duration::from_millis(50));
+        queue.push_blocking(i);
+        assert_eq!(consumer.join().unwrap(), i);
+    }
+}
+
+#[test]
+fn free_slots_tracks_room_for_the_producer() {
+    let queue = Fifo5::new(16);
+    assert_eq!(queue.free_slots(), 16);
+
+    let mut batch: Vec<_> = (0..10).collect();
+    assert_eq!(queue.push_batch(&mut batch), 10);
+    assert_eq!(queue.free_slots(), 6);
+
+    let mut batch: Vec<_> = (10..30).collect();
+    let room = queue.free_slots();
+    let mut fits: Vec<_> = batch.drain(..room.min(batch.len())).collect();
+    assert_eq!(queue.push_batch(&mut fits), 6);
+    assert!(fits.is_empty());
+    assert_eq!(queue.free_slots(), 0);
+
+    queue.consume(4);
+    assert_eq!(queue.free_slots(), 4);
+}
+
+#[test]
+fn as_slices_cover_a_wrapped_queue_in_order() {
+    let mut queue = Fifo5::new(8);
+    for i in 0..6 {
+        queue.push(i).unwrap();
+    }
+    queue.consume(5);
+    for i in 6..12 {
+        queue.push(i).unwrap();
+    }
+
+    let (front, back) = queue.as_slices();
+    assert_eq!(front, [5, 6, 7]);
+    assert_eq!(back, [8, 9, 10, 11]);
+    assert_eq!([front, back].concat(), (5..12).collect::<Vec<_>>());
+
+    let (front, back) = queue.as_mut_slices();
+    front.iter_mut().chain(back).for_each(|x| *x *= 10);
+    let popped: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+    assert_eq!(popped, [50, 60, 70, 80, 90, 100, 110]);
+
+    let (front, back) = queue.as_slices();
+    assert!(front.is_empty() && back.is_empty());
+}
+
+#[test]
+fn peek_contiguous_and_consume_walk_a_wrapped_queue() {
+    let queue = Fifo5::new(8);
+    for i in 0..6 {
+        queue.push(i).unwrap();
+    }
+    queue.consume(6);
+    for i in 6..13 {
+        queue.push(i).unwrap();
+    }
+
+    // The run stops at the end of the ring buffer; the rest comes after consuming it.
+    assert_eq!(queue.peek_contiguous(), [6, 7]);
+    queue.consume(2);
+    assert_eq!(queue.peek_contiguous(), [8, 9, 10, 11, 12]);
+    queue.consume(5);
+
+    assert!(queue.peek_contiguous().is_empty());
+    assert_eq!(queue.pop(), None);
+}
+
+#[test]
+fn aligned_elements_put_every_slot_on_the_boundary() {
+    use lockfree_fifo::aligned::{A32, Aligned};
+
+    let queue: Fifo5<Aligned<[f32; 8], A32>> = Fifo5::new(7);
+    assert_eq!(core::mem::size_of::<Aligned<[f32; 8], A32>>(), 32);
+    for i in 0..7 {
+        queue.push(Aligned::new([i as f32; 8])).unwrap();
+    }
+
+    // A full queue pushed from cursor 0 covers every slot in one run.
+    let slots = queue.peek_contiguous();
+    assert_eq!(slots.len(), 7);
+    for (i, slot) in slots.iter().enumerate() {
+        assert_eq!(slot as *const _ as usize % 32, 0);
+        assert_eq!(**slot, [i as f32; 8]);
+    }
+
+    // Padding: 12 bytes of payload take a full 32-byte slot.
+    assert_eq!(core::mem::size_of::<Aligned<[f32; 3], A32>>(), 32);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn recv_many_waits_for_one_then_drains_the_burst() {
+    use std::thread;
+    use std::time::Duration;
+
+    let queue = Fifo5::new(16);
+    let mut out = Vec::new();
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            queue.push_blocking(1);
+        });
+        assert_eq!(queue.recv_many(&mut out, 8), 1);
+    });
+    assert_eq!(out, [1]);
+
+    for i in 2..12 {
+        queue.push(i).unwrap();
+    }
+    assert_eq!(queue.recv_many(&mut out, 8), 8);
+    assert_eq!(queue.recv_many(&mut out, 8), 2);
+    assert_eq!(out, (1..12).collect::<Vec<_>>());
+    assert_eq!(queue.recv_many(&mut out, 0), 0);
+}
+
+#[test]
+fn pop_batch_into_reports_wrapped_batches() {
+    use std::mem::MaybeUninit;
+
+    let queue = Fifo5::new(8);
+    let mut out = [const { MaybeUninit::<String>::uninit() }; 8];
+
+    for i in 0..6 {
+        queue.push(i.to_string()).unwrap();
+    }
+    // Slots 0..4: a single run.
+    assert_eq!(queue.pop_batch_into(&mut out[..4]), (4, false));
+    let first: Vec<String> = out[..4]
+        .iter()
+        .map(|s| unsafe { s.assume_init_read() })
+        .collect();
+    assert_eq!(first, ["0", "1", "2", "3"]);
+
+    // 4 and 5 sit in slots 4..6, 6..10 fill slots 6, 7, 0 and 1: the batch wraps.
+    for i in 6..10 {
+        queue.push(i.to_string()).unwrap();
+    }
+    assert_eq!(queue.pop_batch_into(&mut out), (6, true));
+    let second: Vec<String> = out[..6]
+        .iter()
+        .map(|s| unsafe { s.assume_init_read() })
+        .collect();
+    assert_eq!(second, ["4", "5", "6", "7", "8", "9"]);
+
+    assert_eq!(queue.pop_batch_into(&mut out), (0, false));
+    queue.push("10".to_string()).unwrap();
+    assert_eq!(queue.pop_batch_into(&mut out), (1, false));
+    assert_eq!(unsafe { out[0].assume_init_read() }, "10");
+}
+
+#[test]
+fn pop_map_transforms_in_order() {
+    let queue = Fifo5::new(4);
+    let mut doubled = Vec::new();
+    for round in 0..3 {
+        for i in 0..3 {
+            queue.push(round * 3 + i).unwrap();
+        }
+        while let Some(x) = queue.pop_map(|x| x * 2) {
+            doubled.push(x);
+        }
+    }
+    assert_eq!(doubled, (0..9).map(|x| x * 2).collect::<Vec<_>>());
+    assert_eq!(queue.pop_map(|x| x * 2), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn recv_adaptive_receives_while_spinning_or_parked() {
+    use std::thread;
+    use std::time::Duration;
+
+    let queue = Fifo5::new(4);
+
+    // Already queued: taken in the spin phase, nobody parks.
+    queue.push(1).unwrap();
+    assert_eq!(queue.recv_adaptive(100), 1);
+    assert_eq!(queue.unpark_counts(), (0, 0));
+
+    // Arrives long after a short spin window has run out: the consumer has parked and
+    // `push_blocking` wakes it.
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(50));
+            queue.push_blocking(2);
+        });
+        assert_eq!(queue.recv_adaptive(10), 2);
+    });
+    assert_eq!(queue.unpark_counts().1, 1);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn wait_not_empty_returns_once_pushed() {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    let queue = Fifo5::new(2);
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            queue.push_blocking(7);
+        });
+        let start = Instant::now();
+        queue.wait_not_empty();
+        assert!(start.elapsed() < Duration::from_secs(5));
+    });
+    // Waiting doesn't consume anything.
+    assert_eq!(queue.len(), 1);
+
+    queue.push(8).unwrap();
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            assert_eq!(queue.pop_blocking(), 7);
+        });
+        queue.wait_not_full();
+    });
+    assert_eq!(queue.free_slots(), 1);
+    assert_eq!(queue.pop(), Some(8));
+}
+
+#[test]
+fn drain_into_moves_everything_in_order() {
+    let src = Fifo5::new(1024);
+    let dst = Fifo5::new(1024);
+    // Start both off the slot-0 boundary so the copy wraps on each side.
+    for i in 0..900 {
+        src.push(i).unwrap();
+        dst.push(i).unwrap();
+    }
+    src.consume(800);
+    dst.consume(900);
+    for i in 900..1300 {
+        src.push(i).unwrap();
+    }
+
+    assert_eq!(src.drain_into(&dst), 500);
+    assert!(src.is_empty());
+    assert_eq!(dst.len(), 500);
+    let moved: Vec<_> = std::iter::from_fn(|| dst.pop()).collect();
+    assert_eq!(moved, (800..1300).collect::<Vec<_>>());
+}
+
+#[test]
+fn drain_into_leaves_what_does_not_fit() {
+    let src = Fifo5::new(8);
+    let dst = Fifo5::new(4);
+    for i in 0..6 {
+        src.push(i.to_string()).unwrap();
+    }
+    dst.push("x".to_string()).unwrap();
+
+    assert_eq!(src.drain_into(&dst), 3);
+    assert_eq!(src.drain_into(&dst), 0);
+    let rest: Vec<_> = std::iter::from_fn(|| src.pop()).collect();
+    assert_eq!(rest, ["3", "4", "5"]);
+    let moved: Vec<_> = std::iter::from_fn(|| dst.pop()).collect();
+    assert_eq!(moved, ["x", "0", "1", "2"]);
+}
+
+#[test]
+fn shrink_to_keeps_a_wrapped_queue_intact() {
+    let mut queue = Fifo5::new(16);
+    for i in 0..16 {
+        queue.push(i.to_string()).unwrap();
+    }
+    for i in 0..12 {
+        assert_eq!(queue.pop(), Some(i.to_string()));
+    }
+    // Wrap the live region around the end of the ring.
+    for i in 16..20 {
+        queue.push(i.to_string()).unwrap();
+    }
+
+    assert_eq!(
+        queue.shrink_to(7),
+        Err(FifoError::BelowLen {
+            capacity: 7,
+            len: 8
+        })
+    );
+    assert_eq!(queue.capacity(), 16);
+
+    queue.shrink_to(32).unwrap();
+    assert_eq!(queue.capacity(), 16);
+
+    queue.shrink_to(8).unwrap();
+    assert_eq!(queue.capacity(), 8);
+    assert_eq!(queue.push("full".to_string()), Err("full".to_string()));
+    let rest: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+    assert_eq!(rest, (12..20).map(|i| i.to_string()).collect::<Vec<_>>());
+}
+
+#[test]
+fn slot_closures_mutate_items_in_place() {
+    let queue: Fifo5<Vec<u8>> = Fifo5::new(2);
+
+    for round in 0..2u8 {
+        let len = unsafe {
+            queue.with_next_slot(|slot| {
+                let buf = slot.write(Vec::with_capacity(64));
+                buf.extend_from_slice(&[round; 4]);
+                buf.len()
+            })
+        };
+        assert_eq!(len, Some(4));
+    }
+    // Full: the closure isn't called.
+    assert_eq!(
+        unsafe { queue.with_next_slot(|_| unreachable!()) },
+        None::<()>
+    );
+
+    // Reset the front buffer where it sits; pop then returns the modified one.
+    let old = unsafe {
+        queue.with_front(|buf| {
+            let old = buf.clone();
+            buf.clear();
+            buf.push(42);
+            old
+        })
+    };
+    assert_eq!(old, Some(vec![0; 4]));
+    assert_eq!(queue.pop(), Some(vec![42]));
+    assert_eq!(queue.pop(), Some(vec![1; 4]));
+    assert_eq!(unsafe { queue.with_front(|buf| buf.len()) }, None);
+}
+
+#[test]
+fn warmup_leaves_the_queue_unchanged() {
+    fn run(warm: bool) -> Vec<Option<String>> {
+        let queue = Fifo5::new(1000);
+        for i in 0..700 {
+            queue.push(i.to_string()).unwrap();
+        }
+        queue.consume(600);
+        if warm {
+            // Free space wraps: slots 700..1000 and 0..600 are free, 600..700 are queued.
+            queue.warmup();
+        }
+        assert_eq!(queue.len(), 100);
+        for i in 700..1600 {
+            queue.push(i.to_string()).unwrap();
+        }
+        std::iter::from_fn(|| Some(queue.pop()))
+            .take(1001)
+            .collect()
+    }
+
+    let warm = run(true);
+    assert_eq!(warm, run(false));
+    assert_eq!(warm[0].as_deref(), Some("600"));
+    assert_eq!(warm[999].as_deref(), Some("1599"));
+    assert_eq!(warm[1000], None);
+
+    // From the producer side of a live queue: the consumer keeps popping meanwhile.
+    let queue = Fifo5::new(256);
+    for i in 0..200 {
+        queue.push(i).unwrap();
+    }
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            for i in 0..200 {
+                assert_eq!(queue.pop(), Some(i));
+            }
+        });
+        queue.warmup();
+    });
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn drain_yields_everything_queued_in_order() {
+    let queue = Fifo5::new(1024);
+    for i in 0..1000 {
+        queue.push(i).unwrap();
+    }
+
+    let drain = queue.drain();
+    assert_eq!(drain.len(), 1000);
+    assert_eq!(drain.collect::<Vec<_>>(), (0..1000).collect::<Vec<_>>());
+    assert!(queue.is_empty());
+
+    // Items pushed after the drain started are left for the next one.
+    queue.push(1000).unwrap();
+    let mut drain = queue.drain();
+    queue.push(1001).unwrap();
+    assert_eq!(drain.next(), Some(1000));
+    assert_eq!(drain.next(), None);
+    assert_eq!(queue.pop(), Some(1001));
+}
+
+#[test]
+fn reserve_publishes_only_committed_slots() {
+    let queue = Fifo5::<u64>::new(4);
+    // Six rounds over four slots, so reservations land on both sides of the wrap.
+    for round in 0..6u64 {
+        let mut slot = queue.reserve().unwrap();
+        unsafe { slot.as_mut_ptr().write(round * 10) };
+        unsafe { slot.commit() };
+
+        // Reserved and written but dropped: never published, and the slot is reused.
+        {
+            let mut abandoned = queue.reserve().unwrap();
+            unsafe { abandoned.as_mut_ptr().write(u64::MAX) };
+        }
+        assert_eq!(queue.len(), 1);
+
+        let mut slot = queue.reserve().unwrap();
+        unsafe { slot.as_mut_ptr().write(round * 10 + 1) };
+        unsafe { slot.commit() };
+
+        assert_eq!(queue.pop(), Some(round * 10));
+        assert_eq!(queue.pop(), Some(round * 10 + 1));
+        assert_eq!(queue.pop(), None);
+    }
+
+    // A full ring has nothing to reserve until the consumer makes room.
+    for i in 0..4 {
+        queue.push(i).unwrap();
+    }
+    assert!(queue.reserve().is_none());
+    queue.pop();
+    let mut slot = queue.reserve().unwrap();
+    unsafe { slot.as_mut_ptr().write(4) };
+    unsafe { slot.commit() };
+    assert_eq!(queue.drain().collect::<Vec<_>>(), [1, 2, 3, 4]);
+}
+
+#[test]
+fn clear_drops_everything_queued_and_frees_the_slots() {
+    use std::rc::Rc;
+
+    let token = Rc::new(());
+    let queue = Fifo5::new(512);
+    for _ in 0..500 {
+        queue.push(token.clone()).unwrap();
+    }
+    assert_eq!(Rc::strong_count(&token), 501);
+
+    queue.clear();
+    assert_eq!(Rc::strong_count(&token), 1, "exactly 500 destructors ran");
+    assert_eq!(queue.len(), 0);
+    assert_eq!(queue.pop(), None);
+
+    // The freed slots (wrapping past the end this time) are reused normally.
+    for _ in 0..512 {
+        queue.push(token.clone()).unwrap();
+    }
+    assert!(queue.push(token.clone()).is_err());
+    assert_eq!(queue.drain().count(), 512);
+    assert_eq!(Rc::strong_count(&token), 1);
+}
+
+#[test]
+fn collect_and_new_filled_build_populated_queues() {
+    let queue: Fifo5<u32> = (0..1000).collect();
+    assert_eq!(queue.capacity(), 1024);
+    assert_eq!(queue.len(), 1000);
+    assert_eq!(
+        queue.drain().collect::<Vec<_>>(),
+        (0..1000).collect::<Vec<_>>()
+    );
+
+    let empty: Fifo5<u32> = std::iter::empty().collect();
+    assert_eq!(empty.capacity(), 1);
+    assert!(empty.is_empty());
+
+    let queue = Fifo5::new_filled(8, 0..5).unwrap();
+    assert_eq!(queue.len(), 5);
+    queue.push(5).unwrap();
+    assert_eq!(queue.drain().collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+
+    // Overflow hands back the full queue and the items that didn't fit.
+    let Err((queue, rest)) = Fifo5::new_filled(4, (0..7).map(|i| i.to_string())) else {
+        panic!("seven items can't fit in four slots");
+    };
+    assert_eq!(rest.collect::<Vec<_>>(), ["4", "5", "6"]);
+    assert_eq!(queue.len(), 4);
+    assert_eq!(queue.pop().as_deref(), Some("0"));
+    // Whatever is left is dropped with the queue.
+}
+
+#[test]
+fn try_pop_n_reads_across_the_wrap() {
+    // Queued items sit at slots 6, 7, 0, 1, 2: the run straddles the end of the ring.
+    let queue = Fifo5::new(8);
+    for i in 0..6u32 {
+        queue.push(i).unwrap();
+    }
+    assert_eq!(queue.try_pop_n(&mut [0; 6]), 6);
+    for i in 6..11 {
+        queue.push(i).unwrap();
+    }
+
+    let mut out = [0u32; 4];
+    assert_eq!(queue.try_pop_n(&mut out), 4);
+    assert_eq!(out, [6, 7, 8, 9]);
+    // Fewer queued than `out` holds: only the front is written.
+    let mut out = [u32::MAX; 4];
+    assert_eq!(queue.try_pop_n(&mut out), 1);
+    assert_eq!(out, [10, u32::MAX, u32::MAX, u32::MAX]);
+    assert_eq!(queue.try_pop_n(&mut out), 0);
+
+    // The same with a payload that needs dropping, which is swapped out instead of copied.
+    let queue = Fifo5::new(8);
+    for i in 0..6 {
+        queue.push(i.to_string()).unwrap();
+    }
+    queue.consume(6);
+    for i in 6..11 {
+        queue.push(i.to_string()).unwrap();
+    }
+    let mut out: [String; 4] = Default::default();
+    assert_eq!(queue.try_pop_n(&mut out), 4);
+    assert_eq!(out, ["6", "7", "8", "9"]);
+    assert_eq!(queue.pop().as_deref(), Some("10"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn pop_timeout_waits_for_an_item_or_gives_up() {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use lockfree_fifo::error::PopTimeout;
+
+    let queue = Arc::new(Fifo5::new(4));
+
+    // Nothing arrives: the call returns once the timeout has passed, not before.
+    let start = Instant::now();
+    assert_eq!(
+        queue.pop_timeout(Duration::from_millis(50)),
+        Err(PopTimeout::Timeout)
+    );
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    // Already queued: returned straight away.
+    queue.push(1).unwrap();
+    assert_eq!(queue.pop_timeout(Duration::ZERO), Ok(1));
+
+    // Pushed from another thread well within the timeout: delivered.
+    let producer = {
+        let queue = queue.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            queue.push_blocking(2);
+        })
+    };
+    assert_eq!(queue.pop_timeout(Duration::from_secs(10)), Ok(2));
+    producer.join().unwrap();
+}
+
+#[test]
+fn resize_grows_a_wrapped_half_full_queue() {
+    use std::rc::Rc;
+
+    let token = Rc::new(());
+    let mut queue = Fifo5::new(8);
+    for i in 0..6 {
+        queue.push((i, token.clone())).unwrap();
+    }
+    for i in 0..6 {
+        assert_eq!(queue.pop().unwrap().0, i);
+    }
+    // Half full, straddling the end of the ring: slots 6, 7, 0, 1.
+    for i in 6..10 {
+        queue.push((i, token.clone())).unwrap();
+    }
+
+    assert_eq!(queue.resize(0), Err(FifoError::ZeroCapacity));
+    queue.resize(32).unwrap();
+    assert_eq!(queue.capacity(), 32);
+    assert_eq!(queue.len(), 4);
+    // Nothing dropped or duplicated by the move.
+    assert_eq!(Rc::strong_count(&token), 5);
+
+    // The new ring is usable to its full size, and the order survives.
+    for i in 10..38 {
+        queue.push((i, token.clone())).unwrap();
+    }
+    assert!(queue.push((38, token.clone())).is_err());
+    let order: Vec<_> = std::iter::from_fn(|| queue.pop()).map(|(i, _)| i).collect();
+    assert_eq!(order, (6..38).collect::<Vec<_>>());
+    assert_eq!(Rc::strong_count(&token), 1);
+}
@@ -0,0 +1,129 @@
+//! `push_async` / `pop_async` on Fifo5 (`--features futures`), polled by hand with a waker
+//! that counts its wakes, so no executor is needed.
+#![cfg(feature = "futures")]
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+
+use lockfree_fifo::Fifo5;
+
+#[derive(Default)]
+struct CountingWaker(AtomicUsize);
+
+impl Wake for CountingWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn counting_waker() -> (Arc<CountingWaker>, Waker) {
+    let counter = Arc::new(CountingWaker::default());
+    (counter.clone(), Waker::from(counter))
+}
+
+// Polls `future` once; for futures that are known to complete without waiting.
+fn ready<F: Future>(future: F) -> F::Output {
+    let (_, waker) = counting_waker();
+    match pin!(future).poll(&mut Context::from_waker(&waker)) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future was expected to be ready"),
+    }
+}
+
+#[test]
+fn pending_pop_is_woken_by_a_later_push() {
+    let queue = Fifo5::new(4);
+    let (wakes, waker) = counting_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut pop = pin!(queue.pop_async());
+    assert_eq!(pop.as_mut().poll(&mut cx), Poll::Pending);
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 0);
+
+    ready(queue.push_async(7));
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+    assert_eq!(pop.as_mut().poll(&mut cx), Poll::Ready(7));
+}
+
+#[test]
+fn pending_push_is_woken_by_a_later_pop() {
+    let queue = Fifo5::new(2);
+    ready(queue.push_async(1));
+    ready(queue.push_async(2));
+
+    let (wakes, waker) = counting_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut push = pin!(queue.push_async(3));
+    assert_eq!(push.as_mut().poll(&mut cx), Poll::Pending);
+
+    assert_eq!(ready(queue.pop_async()), 1);
+    assert_eq!(wakes.0.load(Ordering::Relaxed), 1);
+    assert_eq!(push.as_mut().poll(&mut cx), Poll::Ready(()));
+    assert_eq!(ready(queue.pop_async()), 2);
+    assert_eq!(ready(queue.pop_async()), 3);
+}
+
+#[test]
+fn dropping_a_pending_pop_loses_nothing() {
+    let queue = Fifo5::new(4);
+    let (_, waker) = counting_waker();
+    {
+        let mut pop = pin!(queue.pop_async());
+        assert_eq!(
+            pop.as_mut().poll(&mut Context::from_waker(&waker)),
+            Poll::Pending
+        );
+    }
+    queue.push(5).unwrap();
+    assert_eq!(queue.pop(), Some(5));
+}
+
+#[test]
+fn producer_and_consumer_tasks_on_their_own_threads() {
+    const N: u32 = 2000;
+
+    // Parks the thread until the future's waker fires: a minimal block_on.
+    struct Unparker(std::thread::Thread);
+    impl Wake for Unparker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(Unparker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    let queue = Fifo5::new(8);
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            block_on(async {
+                for i in 0..N {
+                    queue.push_async(i).await;
+                }
+            })
+        });
+        let received = block_on(async {
+            let mut received = Vec::new();
+            for _ in 0..N {
+                received.push(queue.pop_async().await);
+            }
+            received
+        });
+        assert_eq!(received, (0..N).collect::<Vec<_>>());
+    });
+}
@@ -0,0 +1,218 @@
+//! Model checks for the queue orderings. Run with:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//! ```
+#![cfg(loom)]
+
+use lockfree_fifo::{Fifo2, Fifo4, Fifo6Fair, Fifo6a, FifoMpsc, SpscQueue};
+use loom::sync::Arc;
+use loom::thread;
+
+// Capacity 2 with three items, so every interleaving also covers the full ring, the wrap
+// around, and (for Fifo4) the shadow cursor refreshes on both sides.
+fn producer_consumer_in_order<Q>(queue: Q)
+where
+    Q: SpscQueue<usize> + Send + Sync + 'static,
+{
+    let queue = Arc::new(queue);
+    let producer = {
+        let queue = queue.clone();
+        thread::spawn(move || {
+            for i in 1..=3 {
+                while queue.try_push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        })
+    };
+
+    let mut seen = Vec::new();
+    while seen.len() < 3 {
+        match queue.try_pop() {
+            Some(val) => seen.push(val),
+            None => thread::yield_now(),
+        }
+    }
+    producer.join().unwrap();
+
+    assert_eq!(seen, [1, 2, 3]);
+    assert!(queue.try_pop().is_none());
+}
+
+#[test]
+fn fifo2_spsc_in_order() {
+    loom::model(|| producer_consumer_in_order(Fifo2::new(2)));
+}
+
+#[test]
+fn fifo4_spsc_in_order() {
+    loom::model(|| producer_consumer_in_order(Fifo4::new(2)));
+}
+
+// Deferred publishing: the consumer must see each batch only as a whole, and in order.
+#[test]
+fn fifo4_push_deferred() {
+    loom::model(|| {
+        let queue = Arc::new(Fifo4::new(2).with_flush_threshold(2));
+        let producer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                for i in 1..=3 {
+                    while queue.push_deferred(i).is_err() {
+                        thread::yield_now();
+                    }
+                }
+                queue.flush();
+            })
+        };
+
+        let mut seen = Vec::new();
+        while seen.len() < 3 {
+            match queue.pop() {
+                Some(val) => seen.push(val),
+                None => thread::yield_now(),
+            }
+        }
+        producer.join().unwrap();
+
+        assert_eq!(seen, [1, 2, 3]);
+    });
+}
+
+// Dropping the last guard publishes its deferred items before the count reaches 0, so a
+// consumer that sees 0 and then an empty queue hasn't missed anything.
+#[test]
+fn fifo4_producer_guard_shutdown() {
+    loom::model(|| {
+        // `Fifo4::producer` takes a std Arc; the model only needs loom's atomics inside.
+        let queue = std::sync::Arc::new(Fifo4::new(2).with_flush_threshold(2));
+        let guard = queue.producer();
+        let producer = thread::spawn(move || {
+            guard.push_deferred(1).unwrap();
+        });
+
+        let mut seen = Vec::new();
+        loop {
+            if let Some(val) = queue.pop() {
+                seen.push(val);
+            } else if queue.producers_alive() == 0 {
+                if let Some(val) = queue.pop() {
+                    seen.push(val);
+                }
+                break;
+            } else {
+                thread::yield_now();
+            }
+        }
+        producer.join().unwrap();
+
+        assert_eq!(seen, [1]);
+    });
+}
+
+#[test]
+fn fifo6a_spsc_in_order() {
+    loom::model(|| producer_consumer_in_order(Fifo6a::new(2)));
+}
+
+// Two producers racing on the tail CAS, which is Relaxed: the slot's `turn` has to carry
+// the synchronization on its own.
+#[test]
+fn fifo6a_mpmc_two_producers() {
+    loom::model(|| {
+        let queue = Arc::new(Fifo6a::new(2));
+        let producers: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|i| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    while queue.push(i).is_err() {
+                        thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+
+        let mut seen = Vec::new();
+        while seen.len() < 2 {
+            match queue.pop() {
+                Some(val) => seen.push(val),
+                None => thread::yield_now(),
+            }
+        }
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        seen.sort_unstable();
+        assert_eq!(seen, [1, 2]);
+    });
+}
+
+#[test]
+fn fifo6fair_spsc_in_order() {
+    loom::model(|| producer_consumer_in_order(Fifo6Fair::new(2)));
+}
+
+// Two consumers each take a ticket and wait on it; each ticket must get its own item.
+#[test]
+fn fifo6fair_two_consumers() {
+    loom::model(|| {
+        let queue = Arc::new(Fifo6Fair::new(2));
+        let consumers: Vec<_> = (0..2)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || queue.pop())
+            })
+            .collect();
+
+        for i in [1, 2] {
+            while queue.push(i).is_err() {
+                thread::yield_now();
+            }
+        }
+
+        let mut seen: Vec<_> = consumers.into_iter().map(|c| c.join().unwrap()).collect();
+        seen.sort_unstable();
+        assert_eq!(seen, [1, 2]);
+    });
+}
+
+#[test]
+fn fifo_mpsc_spsc_in_order() {
+    loom::model(|| producer_consumer_in_order(FifoMpsc::new(2)));
+}
+
+// Two producers race on the tail CAS while the consumer pops without one.
+#[test]
+fn fifo_mpsc_two_producers() {
+    loom::model(|| {
+        let queue = Arc::new(FifoMpsc::new(2));
+        let producers: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|i| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    while queue.push(i).is_err() {
+                        thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+
+        let mut seen = Vec::new();
+        while seen.len() < 2 {
+            match queue.pop() {
+                Some(val) => seen.push(val),
+                None => thread::yield_now(),
+            }
+        }
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        seen.sort_unstable();
+        assert_eq!(seen, [1, 2]);
+    });
+}
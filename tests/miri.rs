@@ -0,0 +1,240 @@
+//! Small enough to run under Miri, which checks the unsafe ring accesses for undefined
+//! behaviour and aliasing violations:
+//!
+//! ```text
+//! cargo +nightly miri test --test miri
+//! ```
+#![cfg(not(loom))]
+
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use lockfree_fifo::{Fifo2, Fifo3, Fifo4, Fifo5, Fifo6, Fifo6Const, Fifo6Fair, Fifo6a, SpscQueue};
+
+#[test]
+fn fifo5_single_thread_wraps() {
+    let queue = Fifo5::new(8);
+    let mut expected = 0;
+    for i in 0..300 {
+        queue.push(i.to_string()).unwrap();
+        if i % 3 != 0 {
+            assert_eq!(queue.pop(), Some(expected.to_string()));
+            expected += 1;
+        }
+        if queue.len() == queue.capacity() {
+            while let Some(value) = queue.pop() {
+                assert_eq!(value, expected.to_string());
+                expected += 1;
+            }
+        }
+    }
+    // Whatever is left is dropped by the queue.
+}
+
+#[test]
+fn fifo5_batches_wrap() {
+    let queue = Fifo5::new(8);
+    let mut out = Vec::new();
+    for round in 0..20 {
+        let mut items: Vec<String> = (0..5).map(|i| format!("{round}-{i}")).collect();
+        assert_eq!(queue.push_batch(&mut items), 5);
+        out.clear();
+        assert_eq!(queue.pop_batch(&mut out, 5), 5);
+        assert_eq!(out[4], format!("{round}-4"));
+    }
+}
+
+#[test]
+fn fifo5_try_extend_returns_overflow() {
+    let queue = Fifo5::new(1024);
+    let mut rest = queue.try_extend((0..2000).map(Box::new));
+    assert_eq!(queue.len(), 1024);
+    assert_eq!(rest.next().map(|b| *b), Some(1024));
+    assert_eq!(rest.count(), 2000 - 1024 - 1);
+    for i in 0..1024 {
+        assert_eq!(queue.pop().map(|b| *b), Some(i));
+    }
+}
+
+#[test]
+fn fifo5_into_vec_moves_live_items() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    #[derive(Debug)]
+    struct Counted(usize);
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let queue = Fifo5::new(4);
+    // Wrap the live run around the end of the ring: 6 pushed, 3 popped.
+    for i in 0..4 {
+        queue.push(Counted(i)).unwrap();
+    }
+    for _ in 0..3 {
+        drop(queue.pop());
+    }
+    for i in 4..6 {
+        queue.push(Counted(i)).unwrap();
+    }
+    assert_eq!(DROPS.load(Ordering::Relaxed), 3);
+
+    let items = queue.into_vec();
+    assert_eq!(DROPS.load(Ordering::Relaxed), 3);
+    assert_eq!(items.iter().map(|c| c.0).collect::<Vec<_>>(), [3, 4, 5]);
+    drop(items);
+    assert_eq!(DROPS.load(Ordering::Relaxed), 6);
+}
+
+#[test]
+fn fifo5_two_threads() {
+    let queue = Arc::new(Fifo5::new(4));
+    let producer = {
+        let queue = queue.clone();
+        thread::spawn(move || {
+            for i in 0..200 {
+                let mut item = Box::new(i);
+                while let Err(rejected) = queue.push(item) {
+                    item = rejected;
+                    thread::yield_now();
+                }
+            }
+        })
+    };
+
+    let mut expected = 0;
+    while expected < 200 {
+        match queue.pop() {
+            Some(value) => {
+                assert_eq!(*value, expected);
+                expected += 1;
+            }
+            None => thread::yield_now(),
+        }
+    }
+    producer.join().unwrap();
+}
+
+// Zero-sized payloads: every slot has the same address and copies move no bytes, so only the
+// cursors keep count.
+#[test]
+fn zst_counting() {
+    let queue = Fifo5::new(4);
+    for _ in 0..4 {
+        queue.push(()).unwrap();
+    }
+    assert_eq!(queue.push(()), Err(()));
+    assert_eq!(queue.len(), 4);
+
+    let mut out = Vec::new();
+    assert_eq!(queue.pop_batch(&mut out, 3), 3);
+    assert_eq!(queue.push_batch(&mut vec![(); 5]), 3);
+    assert_eq!(queue.drain().count(), 4);
+    assert_eq!(queue.pop(), None);
+
+    let queue = Fifo6::new(4);
+    for _ in 0..4 {
+        queue.push(()).unwrap();
+    }
+    assert_eq!(queue.push(()), Err(()));
+    for _ in 0..4 {
+        assert_eq!(queue.pop(), Some(()));
+    }
+    assert_eq!(queue.pop(), None);
+
+    fn fill_and_drain<Q: SpscQueue<()>>(queue: Q) {
+        for _ in 0..2 {
+            for _ in 0..queue.capacity() {
+                queue.try_push(()).unwrap();
+            }
+            assert_eq!(queue.try_push(()), Err(()));
+            for _ in 0..queue.capacity() {
+                assert_eq!(queue.try_pop(), Some(()));
+            }
+            assert_eq!(queue.try_pop(), None);
+        }
+    }
+    fill_and_drain(Fifo2::new(4));
+    fill_and_drain(Fifo3::new(4));
+    fill_and_drain(Fifo4::new(4));
+    fill_and_drain(Fifo6a::new(4));
+    fill_and_drain(Fifo6Fair::new(4));
+    fill_and_drain(Fifo6Const::<(), 4>::new());
+}
+
+#[test]
+fn zst_with_drop() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    #[derive(Debug)]
+    struct Signal;
+    impl Drop for Signal {
+        fn drop(&mut self) {
+            DROPS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let queue = Fifo5::new(4);
+    for _ in 0..6 {
+        let _ = queue.push(Signal);
+    }
+    // The two rejected pushes were handed back and dropped by the caller.
+    assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+    drop(queue.pop());
+    assert_eq!(DROPS.load(Ordering::Relaxed), 3);
+    queue.consume(1);
+    assert_eq!(DROPS.load(Ordering::Relaxed), 4);
+    drop(queue);
+    assert_eq!(DROPS.load(Ordering::Relaxed), 6);
+
+    let queue = Fifo6::new(4);
+    for _ in 0..3 {
+        queue.push(Signal).unwrap();
+    }
+    drop(queue.pop());
+    drop(queue);
+    assert_eq!(DROPS.load(Ordering::Relaxed), 9);
+}
+
+#[test]
+fn fifo6_drops_every_leftover_exactly_once() {
+    // One counter per element, so a double drop can't hide behind a missed one.
+    struct Tracked<'a>(&'a AtomicUsize);
+    impl Drop for Tracked<'_> {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    let drops: Vec<AtomicUsize> = (0..10).map(|_| AtomicUsize::new(0)).collect();
+    let queue = Fifo6::new(8);
+    for count in &drops[..6] {
+        assert!(queue.push(Tracked(count)).is_ok());
+    }
+    drop(queue.pop());
+    drop(queue.pop());
+    // Past the end of the ring, so the leftovers wrap around slot 0.
+    for count in &drops[6..] {
+        assert!(queue.push(Tracked(count)).is_ok());
+    }
+    drop(queue);
+
+    for (i, count) in drops.iter().enumerate() {
+        assert_eq!(count.load(Ordering::Relaxed), 1, "element {i}");
+    }
+}
+
+#[test]
+fn fifo5_pop_into_uninit_destination() {
+    let queue = Fifo5::new(4);
+    let mut dst = MaybeUninit::<String>::uninit();
+    assert!(!unsafe { queue.pop_into(dst.as_mut_ptr()) });
+
+    queue.push("first".to_string()).unwrap();
+    queue.push("second".to_string()).unwrap();
+    assert!(unsafe { queue.pop_into(dst.as_mut_ptr()) });
+    assert_eq!(unsafe { dst.assume_init() }, "first");
+    assert_eq!(queue.pop().as_deref(), Some("second"));
+}
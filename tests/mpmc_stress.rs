@@ -0,0 +1,238 @@
+//! Randomized MPMC stress: every pushed value must be popped exactly once. A duplicate
+//! means two consumers claimed the same slot (an ABA / double-claim bug), a missing value
+//! means a push was lost or overwritten.
+//!
+//! Runs for about two seconds per queue by default; set `STRESS_SECS` for longer runs and
+//! `STRESS_SEED` to replay a failure (the seed is printed on every run).
+#![cfg(all(feature = "std", not(loom)))]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use lockfree_fifo::{Fifo6, Fifo6Const, Fifo6Fair, Fifo6a, FifoMpsc, SpscQueue};
+
+const PER_PRODUCER: u64 = 20_000;
+
+// xorshift64*, so the test needs no extra dependency.
+struct Rng(u64);
+
+impl Rng {
+    fn from_env() -> Rng {
+        let seed = std::env::var("STRESS_SEED")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    .as_nanos() as u64
+            });
+        println!("STRESS_SEED={seed}");
+        Rng(seed | 1)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) % n
+    }
+}
+
+fn duration() -> Duration {
+    let secs = std::env::var("STRESS_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(2.0);
+    Duration::from_secs_f64(secs)
+}
+
+// Producer `p` pushes `p << 32 | 0..PER_PRODUCER`.
+fn tag(producer: u64, seq: u64) -> u64 {
+    (producer << 32) | seq
+}
+
+fn check_exactly_once(label: &str, producers: u64, consumed: Vec<Vec<u64>>) {
+    let mut seen: HashMap<u64, usize> = HashMap::new();
+    for value in consumed.into_iter().flatten() {
+        *seen.entry(value).or_default() += 1;
+    }
+    let duplicates: Vec<_> = seen.iter().filter(|&(_, &n)| n > 1).collect();
+    assert!(
+        duplicates.is_empty(),
+        "{label}: {} values popped more than once, e.g. {:?}",
+        duplicates.len(),
+        &duplicates[..duplicates.len().min(5)]
+    );
+    let missing: Vec<_> = (0..producers)
+        .flat_map(|p| (0..PER_PRODUCER).map(move |s| tag(p, s)))
+        .filter(|v| !seen.contains_key(v))
+        .collect();
+    assert!(
+        missing.is_empty(),
+        "{label}: {} values never popped, e.g. {:?}",
+        missing.len(),
+        &missing[..missing.len().min(5)]
+    );
+    assert_eq!(
+        seen.len() as u64,
+        producers * PER_PRODUCER,
+        "{label}: stray values"
+    );
+}
+
+// One round: `producers` threads push their tagged ranges while `consumers` threads pop
+// until the producers are done and a pop after that comes back empty.
+fn round<Q>(label: &str, queue: Q, producers: u64, consumers: u64)
+where
+    Q: SpscQueue<u64> + Send + Sync + 'static,
+{
+    let queue = Arc::new(queue);
+    let done = Arc::new(AtomicBool::new(false));
+
+    let consumer_handles: Vec<_> = (0..consumers)
+        .map(|_| {
+            let queue = queue.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                let mut got = Vec::new();
+                loop {
+                    if let Some(value) = queue.try_pop() {
+                        got.push(value);
+                    } else if done.load(Ordering::Acquire) {
+                        match queue.try_pop() {
+                            Some(value) => got.push(value),
+                            None => break,
+                        }
+                    } else {
+                        thread::yield_now();
+                    }
+                }
+                got
+            })
+        })
+        .collect();
+
+    let producer_handles: Vec<_> = (0..producers)
+        .map(|p| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                for seq in 0..PER_PRODUCER {
+                    let mut value = tag(p, seq);
+                    while let Err(rejected) = queue.try_push(value) {
+                        value = rejected;
+                        thread::yield_now();
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for handle in producer_handles {
+        handle.join().unwrap();
+    }
+    done.store(true, Ordering::Release);
+    let consumed = consumer_handles
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .collect();
+    check_exactly_once(label, producers, consumed);
+}
+
+// Repeats rounds with random thread counts and small capacities (so the ring wraps and
+// fills constantly) until the time budget is spent.
+fn stress<Q, F>(label: &str, max_consumers: u64, new: F)
+where
+    Q: SpscQueue<u64> + Send + Sync + 'static,
+    F: Fn(usize) -> Q,
+{
+    let mut rng = Rng::from_env();
+    let deadline = Instant::now() + duration();
+    let mut rounds = 0;
+    while rounds == 0 || Instant::now() < deadline {
+        let producers = 1 + rng.below(4);
+        let consumers = 1 + rng.below(max_consumers);
+        let capacity = 2 << rng.below(5);
+        round(label, new(capacity), producers, consumers);
+        rounds += 1;
+    }
+}
+
+#[test]
+fn fifo6_stress() {
+    stress("Fifo6", 4, Fifo6::new);
+}
+
+#[test]
+fn fifo6a_stress() {
+    stress("Fifo6a", 4, Fifo6a::new);
+}
+
+#[test]
+fn fifo6const_stress() {
+    stress("Fifo6Const", 4, |_| Fifo6Const::<u64, 8>::new());
+}
+
+#[test]
+fn fifo_mpsc_stress() {
+    stress("FifoMpsc", 1, FifoMpsc::new);
+}
+
+// Fifo6Fair consumers wait on their ticket, so instead of a done flag every consumer gets
+// one `u64::MAX` sentinel after the producers finish.
+#[test]
+fn fifo6fair_stress() {
+    let mut rng = Rng::from_env();
+    let deadline = Instant::now() + duration();
+    let mut rounds = 0;
+    while rounds == 0 || Instant::now() < deadline {
+        let producers = 1 + rng.below(4);
+        let consumers = 1 + rng.below(4);
+        let queue = Arc::new(Fifo6Fair::new(2 << rng.below(5)));
+
+        let consumer_handles: Vec<_> = (0..consumers)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    let mut got = Vec::new();
+                    loop {
+                        match queue.pop() {
+                            u64::MAX => break got,
+                            value => got.push(value),
+                        }
+                    }
+                })
+            })
+            .collect();
+        let producer_handles: Vec<_> = (0..producers)
+            .map(|p| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for seq in 0..PER_PRODUCER {
+                        while queue.push(tag(p, seq)).is_err() {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for handle in producer_handles {
+            handle.join().unwrap();
+        }
+        for _ in 0..consumers {
+            while queue.push(u64::MAX).is_err() {
+                thread::yield_now();
+            }
+        }
+        let consumed = consumer_handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .collect();
+        check_exactly_once("Fifo6Fair", producers, consumed);
+        rounds += 1;
+    }
+}
@@ -0,0 +1,119 @@
+//! Data visibility across threads. The benchmarks only send `usize`, which travels in the
+//! slot itself; here each item owns a heap buffer the producer fills right before pushing,
+//! so a missing Release / Acquire pair shows up as the consumer reading a stale or
+//! half-written buffer (a bad checksum, or a wild pointer).
+//!
+//! x86 is strongly ordered and hides most such bugs; this is meant to catch them on weakly
+//! ordered targets such as aarch64, and under Miri.
+#![cfg(all(feature = "std", not(loom)))]
+
+use std::sync::Arc;
+use std::thread;
+
+use lockfree_fifo::{
+    Fifo2, Fifo3, Fifo4, Fifo5, Fifo5FetchAdd, Fifo6, Fifo6a, FifoMpsc, SpscQueue,
+};
+
+const ITEMS: u64 = if cfg!(miri) { 200 } else { 100_000 };
+// Small, so the ring wraps constantly and slots are reused while the other side is active.
+const CAPACITY: usize = 8;
+
+struct Payload {
+    seq: u64,
+    data: Box<[u8; 64]>,
+    checksum: u32,
+}
+
+impl Payload {
+    fn new(seq: u64) -> Payload {
+        let mut data = Box::new([0u8; 64]);
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = (seq as u8).wrapping_mul(31).wrapping_add(i as u8);
+        }
+        let checksum = checksum(&data);
+        Payload {
+            seq,
+            data,
+            checksum,
+        }
+    }
+}
+
+fn checksum(data: &[u8; 64]) -> u32 {
+    data.iter().fold(0u32, |sum, &byte| {
+        sum.wrapping_mul(31).wrapping_add(byte as u32)
+    })
+}
+
+fn send_boxed_payloads<Q>(queue: Q)
+where
+    Q: SpscQueue<Payload> + Send + Sync + 'static,
+{
+    let queue = Arc::new(queue);
+    let producer = {
+        let queue = queue.clone();
+        thread::spawn(move || {
+            for seq in 0..ITEMS {
+                let mut item = Payload::new(seq);
+                while let Err(rejected) = queue.try_push(item) {
+                    item = rejected;
+                    thread::yield_now();
+                }
+            }
+        })
+    };
+
+    for seq in 0..ITEMS {
+        let item = loop {
+            match queue.try_pop() {
+                Some(item) => break item,
+                None => thread::yield_now(),
+            }
+        };
+        assert_eq!(item.seq, seq);
+        assert_eq!(checksum(&item.data), item.checksum, "torn payload {seq}");
+        assert_eq!(*item.data, *Payload::new(seq).data);
+    }
+    producer.join().unwrap();
+    assert!(queue.try_pop().is_none());
+}
+
+#[test]
+fn fifo2_publishes_payload_contents() {
+    send_boxed_payloads(Fifo2::new(CAPACITY));
+}
+
+#[test]
+fn fifo3_publishes_payload_contents() {
+    send_boxed_payloads(Fifo3::new(CAPACITY));
+}
+
+#[test]
+fn fifo4_publishes_payload_contents() {
+    send_boxed_payloads(Fifo4::new(CAPACITY));
+}
+
+#[test]
+fn fifo5_publishes_payload_contents() {
+    send_boxed_payloads(Fifo5::new(CAPACITY));
+}
+
+#[test]
+fn fifo5_fetch_add_publishes_payload_contents() {
+    send_boxed_payloads(Fifo5FetchAdd::new(CAPACITY));
+}
+
+#[test]
+fn fifo6_publishes_payload_contents() {
+    send_boxed_payloads(Fifo6::new(CAPACITY));
+}
+
+#[test]
+fn fifo6a_publishes_payload_contents() {
+    send_boxed_payloads(Fifo6a::new(CAPACITY));
+}
+
+#[test]
+fn fifo_mpsc_publishes_payload_contents() {
+    send_boxed_payloads(FifoMpsc::new(CAPACITY));
+}
@@ -0,0 +1,71 @@
+//! `Fifo6` views over memory the caller owns, as two processes sharing a mapping would use
+//! them, exercised within one process.
+
+use std::sync::atomic::AtomicUsize;
+use std::thread;
+
+use lockfree_fifo::Fifo6;
+use lockfree_fifo::fifo6::Slot;
+
+#[test]
+fn into_raw_parts_round_trips_through_views() {
+    let queue = Fifo6::new(8);
+    for i in 0..3u64 {
+        queue.push(i).unwrap();
+    }
+    let (ring, capacity, head, tail) = queue.into_raw_parts();
+    assert_eq!(capacity, 8);
+
+    // One view per side, like a producer and a consumer process attaching to the mapping.
+    let producer = unsafe { Fifo6::from_raw_parts(ring, capacity, head, tail) };
+    let consumer = unsafe { Fifo6::<u64>::from_raw_parts(ring, capacity, head, tail) };
+    thread::scope(|s| {
+        s.spawn(|| {
+            for i in 3..10_000 {
+                while producer.push(i).is_err() {
+                    thread::yield_now();
+                }
+            }
+        });
+        for i in 0..10_000 {
+            let value = loop {
+                if let Some(value) = consumer.pop() {
+                    break value;
+                }
+                thread::yield_now();
+            };
+            assert_eq!(value, i);
+        }
+    });
+    assert_eq!(consumer.pop(), None);
+    drop((producer, consumer));
+
+    unsafe {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            ring, capacity,
+        )));
+        drop(Box::from_raw(head));
+        drop(Box::from_raw(tail));
+    }
+}
+
+#[test]
+fn init_raw_parts_sets_up_fresh_memory() {
+    let mut ring: Vec<Slot<String>> = (0..4).map(|_| Slot::new()).collect();
+    let mut head = AtomicUsize::new(usize::MAX);
+    let mut tail = AtomicUsize::new(usize::MAX);
+    let (ring, head, tail) = (ring.as_mut_ptr(), &raw mut head, &raw mut tail);
+
+    let first = unsafe { Fifo6::init_raw_parts(ring, 4, head, tail) };
+    let second = unsafe { Fifo6::from_raw_parts(ring, 4, head, tail) };
+    for word in ["a", "b", "c", "d"] {
+        first.push(word.to_string()).unwrap();
+    }
+    assert!(first.push("e".to_string()).is_err());
+    assert_eq!(second.pop().as_deref(), Some("a"));
+
+    // Dropping a view leaves the queue as it was for the other one.
+    drop(first);
+    let rest: Vec<_> = std::iter::from_fn(|| second.pop()).collect();
+    assert_eq!(rest, ["b", "c", "d"]);
+}
@@ -0,0 +1,206 @@
+//! Concurrent overwrite-and-read stress for `RingLog`. The reads race with the writers by
+//! design (that's what the sequence numbers are for), so this isn't run under Miri or loom.
+#![cfg(not(loom))]
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use lockfree_fifo::RingLog;
+
+// Every word is derived from the same counter, so a torn copy can't pass the check.
+fn sample(i: u64) -> [u64; 4] {
+    [i, !i, i.wrapping_mul(31), i ^ 0x5555_5555_5555_5555]
+}
+
+fn check(samples: &[[u64; 4]]) {
+    for s in samples {
+        assert_eq!(*s, sample(s[0]), "torn read");
+    }
+}
+
+#[test]
+fn keeps_only_the_latest() {
+    let log = RingLog::new(4);
+    let mut out = [[0; 4]; 8];
+    assert_eq!(log.read_latest_n(&mut out), 0);
+
+    for i in 0..10 {
+        log.write(sample(i));
+    }
+    let n = log.read_latest_n(&mut out);
+    assert_eq!(n, 4);
+    assert_eq!(out[..n], [sample(6), sample(7), sample(8), sample(9)]);
+
+    let n = log.read_latest_n(&mut out[..2]);
+    assert_eq!(out[..n], [sample(8), sample(9)]);
+}
+
+#[test]
+fn keeps_only_the_latest_across_the_cursor_wrap() {
+    let log = RingLog::with_start_cursor(4, usize::MAX - 5);
+    let mut out = [[0; 4]; 8];
+    assert_eq!(log.read_latest_n(&mut out), 0);
+
+    for i in 0..3 {
+        log.write(sample(i));
+    }
+    let n = log.read_latest_n(&mut out);
+    assert_eq!(out[..n], [sample(0), sample(1), sample(2)]);
+
+    // Positions run past usize::MAX here, and the sequence numbers wrap with them.
+    for i in 3..12 {
+        log.write(sample(i));
+    }
+    assert_eq!(log.written(), 12);
+    let n = log.read_latest_n(&mut out);
+    assert_eq!(out[..n], [sample(8), sample(9), sample(10), sample(11)]);
+}
+
+#[test]
+fn reader_counts_entries_lost_to_a_lap() {
+    let log = RingLog::new(4);
+    let mut reader = log.reader();
+    assert_eq!(reader.read_next(), None);
+
+    log.write(sample(0));
+    log.write(sample(1));
+    assert_eq!(reader.read_next(), Some(sample(0)));
+
+    // The writer laps the reader: 1..=5 are overwritten by 6..=9.
+    for i in 2..10 {
+        log.write(sample(i));
+    }
+    let rest: Vec<_> = std::iter::from_fn(|| reader.read_next()).collect();
+    assert_eq!(rest, [sample(6), sample(7), sample(8), sample(9)]);
+    assert_eq!(reader.missed_count(), 5);
+
+    log.write(sample(10));
+    assert_eq!(reader.read_next(), Some(sample(10)));
+    assert_eq!(reader.missed_count(), 5);
+
+    // A late reader starts at the oldest entry still in the ring.
+    let mut late = log.reader();
+    assert_eq!(late.read_next(), Some(sample(7)));
+    assert_eq!(late.missed_count(), 0);
+}
+
+#[test]
+fn reader_follows_the_log_across_the_cursor_wrap() {
+    let log = RingLog::with_start_cursor(4, usize::MAX - 2);
+    let mut reader = log.reader();
+    assert_eq!(reader.read_next(), None);
+
+    for i in 0..3 {
+        log.write(sample(i));
+    }
+    let read: Vec<_> = std::iter::from_fn(|| reader.read_next()).collect();
+    assert_eq!(read, [sample(0), sample(1), sample(2)]);
+
+    // The single-writer path moves the head past usize::MAX, then laps the reader.
+    for i in 3..12 {
+        unsafe { log.push_overwrite_unchecked(sample(i)) };
+    }
+    let rest: Vec<_> = std::iter::from_fn(|| reader.read_next()).collect();
+    assert_eq!(rest, [sample(8), sample(9), sample(10), sample(11)]);
+    assert_eq!(reader.missed_count(), 5);
+
+    let mut late = log.reader();
+    assert_eq!(late.read_next(), Some(sample(8)));
+    assert_eq!(late.missed_count(), 0);
+}
+
+#[test]
+fn reader_accounts_for_every_write() {
+    const WRITES: u64 = 200_000;
+    let log = Arc::new(RingLog::new(64));
+
+    let writer = {
+        let log = Arc::clone(&log);
+        thread::spawn(move || {
+            for i in 0..WRITES {
+                log.write(sample(i));
+            }
+        })
+    };
+
+    let mut reader = log.reader();
+    let mut read = 0;
+    let mut last = None;
+    loop {
+        // Checked before reading, so a `None` after it means every write has been seen.
+        let done = writer.is_finished();
+        match reader.read_next() {
+            Some(s) => {
+                check(&[s]);
+                assert!(last < Some(s[0]), "out of order");
+                last = Some(s[0]);
+                read += 1;
+            }
+            None if done => break,
+            None => thread::yield_now(),
+        }
+    }
+    writer.join().unwrap();
+    assert_eq!(read + reader.missed_count(), WRITES as usize);
+}
+
+#[test]
+fn concurrent_overwrite_and_read() {
+    let log = Arc::new(RingLog::new(8));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let writer = {
+        let log = log.clone();
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let mut i = 0;
+            while !stop.load(Ordering::Relaxed) {
+                // SAFETY: this is the only writer.
+                unsafe { log.push_overwrite_unchecked(sample(i)) };
+                i += 1;
+            }
+        })
+    };
+
+    let mut out = [[0; 4]; 8];
+    let mut reads = 0;
+    while log.written() < 200_000 {
+        let n = log.read_latest_n(&mut out);
+        check(&out[..n]);
+        // Single writer, so whatever survives is in write order.
+        assert!(out[..n].windows(2).all(|w| w[0][0] < w[1][0]));
+        reads += 1;
+    }
+    stop.store(true, Ordering::Relaxed);
+    writer.join().unwrap();
+    assert!(reads > 0);
+}
+
+#[test]
+fn concurrent_writers_and_reader() {
+    let log = Arc::new(RingLog::new(4));
+    let writers: Vec<_> = (0..3u64)
+        .map(|w| {
+            let log = log.clone();
+            thread::spawn(move || {
+                for i in 0..50_000 {
+                    log.write(sample(w << 32 | i));
+                }
+            })
+        })
+        .collect();
+
+    let mut out = [[0; 4]; 4];
+    while log.written() < 150_000 {
+        let n = log.read_latest_n(&mut out);
+        check(&out[..n]);
+    }
+    for writer in writers {
+        writer.join().unwrap();
+    }
+
+    let n = log.read_latest_n(&mut out);
+    assert_eq!(n, 4);
+    check(&out[..n]);
+}
@@ -0,0 +1,147 @@
+//! Deterministic replays of push / pop sequences against `Fifo5` on a single thread.
+//!
+//! The fuzz target (`fuzz/fuzz_targets/fifo5_model.rs`) prints a failing input as
+//! `Input { capacity, start_below_max, ops }`; paste those three fields into
+//! `ScriptedFifo::new` / `run` below to turn a minimized crash into a regression test.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+use lockfree_fifo::Fifo5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Push(u64),
+    Pop,
+}
+
+use Op::{Pop, Push};
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Pushed,
+    Full(u64),
+    Popped(u64),
+    Empty,
+}
+
+/// A `Fifo5<u64>` run in lockstep with a `VecDeque` model, logging every step.
+struct ScriptedFifo {
+    queue: Fifo5<u64>,
+    model: VecDeque<u64>,
+    capacity: usize,
+    log: Vec<(Op, Outcome)>,
+}
+
+impl ScriptedFifo {
+    // Same capacity and cursor setup as the fuzz target, so its inputs replay as is.
+    fn new(capacity: u8, start_below_max: Option<u8>) -> ScriptedFifo {
+        let mut capacity = usize::from(capacity).max(1);
+        let queue = match start_below_max {
+            Some(offset) => {
+                capacity = capacity.next_power_of_two();
+                Fifo5::with_start_cursor(capacity, usize::MAX - usize::from(offset))
+            }
+            None => Fifo5::new(capacity),
+        };
+        ScriptedFifo {
+            queue,
+            model: VecDeque::with_capacity(capacity),
+            capacity,
+            log: Vec::new(),
+        }
+    }
+
+    /// Runs `ops` in order, panicking with the log so far at the first step where the
+    /// queue and the model disagree.
+    fn run(&mut self, ops: &[Op]) -> &[(Op, Outcome)] {
+        for &op in ops {
+            let (actual, expected) = match op {
+                Push(value) => {
+                    let actual = match self.queue.push(value) {
+                        Ok(()) => Outcome::Pushed,
+                        Err(value) => Outcome::Full(value),
+                    };
+                    let expected = if self.model.len() < self.capacity {
+                        self.model.push_back(value);
+                        Outcome::Pushed
+                    } else {
+                        Outcome::Full(value)
+                    };
+                    (actual, expected)
+                }
+                Pop => {
+                    let outcome =
+                        |popped: Option<u64>| popped.map_or(Outcome::Empty, Outcome::Popped);
+                    (outcome(self.queue.pop()), outcome(self.model.pop_front()))
+                }
+            };
+            if actual != expected || self.queue.len() != self.model.len() {
+                panic!(
+                    "step {} {op:?}: got {actual:?} (len {}), model {expected:?} (len {})\n{}",
+                    self.log.len(),
+                    self.queue.len(),
+                    self.model.len(),
+                    self.history(),
+                );
+            }
+            self.log.push((op, actual));
+        }
+        &self.log
+    }
+
+    fn history(&self) -> String {
+        let mut out = String::new();
+        for (step, (op, outcome)) in self.log.iter().enumerate() {
+            writeln!(out, "  {step}: {op:?} -> {outcome:?}").unwrap();
+        }
+        out
+    }
+}
+
+#[test]
+fn capacity_one_alternating_push_pop() {
+    let mut script = ScriptedFifo::new(1, None);
+    let log = script.run(&[
+        Push(1),
+        Push(2),
+        Pop,
+        Pop,
+        Push(3),
+        Pop,
+        Push(4),
+        Push(5),
+        Pop,
+    ]);
+    assert_eq!(
+        log.iter().map(|(_, outcome)| outcome).collect::<Vec<_>>(),
+        [
+            &Outcome::Pushed,
+            &Outcome::Full(2),
+            &Outcome::Popped(1),
+            &Outcome::Empty,
+            &Outcome::Pushed,
+            &Outcome::Popped(3),
+            &Outcome::Pushed,
+            &Outcome::Full(5),
+            &Outcome::Popped(4),
+        ]
+    );
+}
+
+#[test]
+fn capacity_one_across_the_cursor_wrap() {
+    let mut script = ScriptedFifo::new(1, Some(2));
+    let ops: Vec<_> = (0..6).flat_map(|i| [Push(i), Push(100 + i), Pop]).collect();
+    script.run(&ops);
+}
+
+#[test]
+#[should_panic(expected = "step 1 Pop: got Empty")]
+fn mismatch_reports_the_step() {
+    let mut script = ScriptedFifo::new(2, None);
+    script.run(&[Push(1)]);
+    // Pop behind the script's back, so the next scripted pop disagrees with the model.
+    script.queue.pop();
+    script.run(&[Pop]);
+}
@@ -0,0 +1,27 @@
+//! The queues are `Send + Sync` exactly when their items are `Send`. The negative side is
+//! covered by the compile-fail cases in `tests/ui`.
+
+use std::sync::Arc;
+
+use lockfree_fifo::{
+    Fifo2, Fifo3, Fifo4, Fifo5, Fifo5FetchAdd, Fifo6, Fifo6Const, Fifo6Fair, Fifo6a, FifoMpsc,
+    FifoUnbounded, RingLog,
+};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn queues_of_send_items_are_send_and_sync() {
+    assert_send_sync::<Fifo2<Arc<u32>>>();
+    assert_send_sync::<Fifo3<Arc<u32>>>();
+    assert_send_sync::<Fifo4<Arc<u32>>>();
+    assert_send_sync::<Fifo5<Arc<u32>>>();
+    assert_send_sync::<Fifo5FetchAdd<Arc<u32>>>();
+    assert_send_sync::<Fifo6<Arc<u32>>>();
+    assert_send_sync::<Fifo6a<Arc<u32>>>();
+    assert_send_sync::<Fifo6Const<Arc<u32>, 8>>();
+    assert_send_sync::<Fifo6Fair<Arc<u32>>>();
+    assert_send_sync::<FifoMpsc<Arc<u32>>>();
+    assert_send_sync::<FifoUnbounded<Arc<u32>>>();
+    assert_send_sync::<RingLog<u64>>();
+}
@@ -0,0 +1,35 @@
+//! Fifo2 contents persisted and restored through JSON (`--features serde`).
+#![cfg(feature = "serde")]
+
+use lockfree_fifo::Fifo2;
+
+#[test]
+fn json_round_trip_keeps_the_queued_order() {
+    let queue = Fifo2::new(64);
+    // Start the front mid-ring so the queued items wrap around slot 0.
+    for i in 0..40u32 {
+        queue.push(i).unwrap();
+    }
+    for _ in 0..40 {
+        queue.pop().unwrap();
+    }
+    for i in 0..50 {
+        queue.push(i).unwrap();
+    }
+
+    let json = serde_json::to_string(&queue).unwrap();
+    assert_eq!(
+        json,
+        serde_json::to_string(&(0..50).collect::<Vec<_>>()).unwrap()
+    );
+    // Serializing only reads the queue.
+    assert_eq!(queue.peek(), Some(&0));
+
+    let restored: Fifo2<u32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.capacity(), 64);
+    let drained: Vec<_> = std::iter::from_fn(|| restored.pop()).collect();
+    assert_eq!(drained, (0..50).collect::<Vec<_>>());
+
+    let empty: Fifo2<u32> = serde_json::from_str("[]").unwrap();
+    assert_eq!(empty.pop(), None);
+}
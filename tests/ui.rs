@@ -0,0 +1,15 @@
+//! Compile-time checks. Refresh the expected output after a compiler upgrade with:
+//!
+//! ```text
+//! TRYBUILD=overwrite cargo test --test ui
+//! ```
+#![cfg(not(loom))]
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/fifo6const_pow2.rs");
+    t.compile_fail("tests/ui/fifo6const_not_pow2.rs");
+    t.pass("tests/ui/fifo5_send_sync.rs");
+    t.compile_fail("tests/ui/fifo5_rc_not_sync.rs");
+}
@@ -0,0 +1,12 @@
+use std::rc::Rc;
+use std::thread;
+
+use lockfree_fifo::Fifo5;
+
+fn main() {
+    // Rc isn't Send, so a queue of them must not be shared with another thread.
+    let queue = Fifo5::<Rc<u32>>::new(4);
+    thread::scope(|s| {
+        s.spawn(|| queue.len());
+    });
+}
@@ -0,0 +1,18 @@
+use std::sync::Arc;
+use std::thread;
+
+use lockfree_fifo::Fifo5;
+
+fn main() {
+    let queue = Arc::new(Fifo5::<Arc<u32>>::new(4));
+    let producer = {
+        let queue = queue.clone();
+        thread::spawn(move || queue.push(Arc::new(1)).unwrap())
+    };
+    producer.join().unwrap();
+    assert_eq!(queue.pop().as_deref(), Some(&1));
+
+    // The queue itself can move to another thread too.
+    let queue = Fifo5::<Arc<u32>>::new(4);
+    thread::spawn(move || drop(queue)).join().unwrap();
+}
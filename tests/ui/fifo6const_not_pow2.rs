@@ -0,0 +1,5 @@
+use lockfree_fifo::Fifo6Const;
+
+fn main() {
+    let _queue = Fifo6Const::<u8, 100>::new();
+}
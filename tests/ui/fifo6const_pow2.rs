@@ -0,0 +1,7 @@
+use lockfree_fifo::Fifo6Const;
+
+fn main() {
+    let queue = Fifo6Const::<u8, 128>::new();
+    queue.push(1).unwrap();
+    assert_eq!(queue.pop(), Some(1));
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use lockfree_fifo::{Fifo, FifoUnbounded};
+
+#[test]
+fn bounded_mode_rejects_when_full() {
+    let queue = Fifo::bounded(4);
+    assert_eq!(queue.capacity(), Some(4));
+    for i in 0..4 {
+        queue.push(i).unwrap();
+    }
+    assert_eq!(queue.push(4), Err(4));
+    assert_eq!(queue.pop(), Some(0));
+    queue.push(4).unwrap();
+    assert_eq!(queue.len(), 4);
+}
+
+#[test]
+fn unbounded_mode_grows_across_blocks() {
+    let queue = Fifo::unbounded();
+    assert_eq!(queue.capacity(), None);
+    // Several 64-slot blocks, popping part way through to free some.
+    for i in 0..300 {
+        queue.push(i).unwrap();
+    }
+    for i in 0..100 {
+        assert_eq!(queue.pop(), Some(i));
+    }
+    for i in 300..1000 {
+        queue.push(i).unwrap();
+    }
+    assert_eq!(queue.len(), 900);
+    let rest: Vec<_> = std::iter::from_fn(|| queue.pop()).collect();
+    assert_eq!(rest, (100..1000).collect::<Vec<_>>());
+    assert!(queue.is_empty());
+}
+
+#[test]
+fn unbounded_drop_releases_items_in_every_block() {
+    let item = Arc::new(());
+    let queue = FifoUnbounded::new();
+    for _ in 0..200 {
+        queue.push(item.clone());
+    }
+    for _ in 0..70 {
+        queue.pop();
+    }
+    assert_eq!(Arc::strong_count(&item), 131);
+    drop(queue);
+    assert_eq!(Arc::strong_count(&item), 1);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn unbounded_streams_between_threads() {
+    const N: usize = if cfg!(miri) { 500 } else { 100_000 };
+    let queue = Arc::new(FifoUnbounded::new());
+
+    let producer = {
+        let queue = queue.clone();
+        std::thread::spawn(move || {
+            for i in 0..N {
+                queue.push(i);
+            }
+        })
+    };
+
+    let mut next = 0;
+    while next < N {
+        match queue.pop() {
+            Some(value) => {
+                assert_eq!(value, next);
+                next += 1;
+            }
+            None => std::thread::yield_now(),
+        }
+    }
+    producer.join().unwrap();
+    assert!(queue.pop().is_none());
+}
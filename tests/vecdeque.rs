@@ -0,0 +1,49 @@
+//! The `VecDeque` names on the SPSC queues: `push_back`, `pop_front` and `front`.
+
+use lockfree_fifo::{Fifo2, Fifo3, Fifo4, Fifo5};
+
+// The same script for every queue, with the front crossing the end of the ring.
+macro_rules! check_vecdeque_names {
+    ($queue:expr) => {{
+        let queue = $queue;
+        assert_eq!(queue.front(), None);
+        for i in 0..3 {
+            queue.push_back(i.to_string()).unwrap();
+        }
+        assert_eq!(queue.front().map(String::as_str), Some("0"));
+        // Looking doesn't pop.
+        assert_eq!(queue.front().map(String::as_str), Some("0"));
+        assert_eq!(queue.pop_front().as_deref(), Some("0"));
+        assert_eq!(queue.front().map(String::as_str), Some("1"));
+
+        for i in 3..5 {
+            queue.push_back(i.to_string()).unwrap();
+        }
+        assert_eq!(queue.push_back("full".to_string()), Err("full".to_string()));
+        for i in 1..5 {
+            assert_eq!(queue.front().map(String::as_str), Some(&*i.to_string()));
+            assert_eq!(queue.pop_front(), Some(i.to_string()));
+        }
+        assert_eq!(queue.front(), None);
+        assert_eq!(queue.pop_front(), None);
+    }};
+}
+
+#[test]
+fn every_spsc_queue_has_the_vecdeque_names() {
+    check_vecdeque_names!(Fifo2::new(4));
+    check_vecdeque_names!(Fifo3::new(4));
+    check_vecdeque_names!(Fifo4::new(4));
+    check_vecdeque_names!(Fifo5::new(4));
+}
+
+#[test]
+fn fifo4_front_only_sees_published_items() {
+    let queue = Fifo4::new(4).with_flush_threshold(8);
+    queue.push_deferred(1).unwrap();
+    assert_eq!(queue.front(), None);
+    queue.flush();
+    assert_eq!(queue.front(), Some(&1));
+    assert_eq!(queue.pop(), Some(1));
+    assert_eq!(queue.front(), None);
+}
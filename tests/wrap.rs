@@ -0,0 +1,85 @@
+//! Cursors are free-running and wrap at `usize::MAX`; these start them just below it and
+//! push / pop across the boundary.
+
+use lockfree_fifo::{Fifo2, Fifo3, Fifo4, Fifo5, SpscQueue};
+
+const CAPACITY: usize = 8;
+const START: usize = usize::MAX - 5;
+
+// Fill to full, drain half, refill, and so on, so the wrap is crossed both while the queue
+// is full and while it is partially drained.
+fn across_the_wrap<Q: SpscQueue<u32>>(queue: Q) {
+    let (mut next_in, mut next_out) = (0u32, 0u32);
+    for _ in 0..10 {
+        while queue.try_push(next_in).is_ok() {
+            next_in += 1;
+        }
+        assert_eq!(
+            next_in - next_out,
+            CAPACITY as u32,
+            "full at the wrong size"
+        );
+        for _ in 0..CAPACITY / 2 + 1 {
+            assert_eq!(queue.try_pop(), Some(next_out));
+            next_out += 1;
+        }
+    }
+    while let Some(value) = queue.try_pop() {
+        assert_eq!(value, next_out);
+        next_out += 1;
+    }
+    assert_eq!(next_out, next_in);
+}
+
+#[test]
+fn fifo2() {
+    let queue = Fifo2::with_start_cursor(CAPACITY, START);
+    queue.push(1).unwrap();
+    queue.push(2).unwrap();
+    assert_eq!(queue.size(), 2);
+    assert_eq!(queue.pop(), Some(1));
+    assert_eq!(queue.pop(), Some(2));
+    across_the_wrap(Fifo2::with_start_cursor(CAPACITY, START));
+}
+
+#[test]
+fn fifo3() {
+    across_the_wrap(Fifo3::with_start_cursor(CAPACITY, START));
+}
+
+#[test]
+fn fifo4() {
+    let queue = Fifo4::with_start_cursor(CAPACITY, START);
+    for i in 0..CAPACITY as u32 {
+        queue.push(i).unwrap();
+    }
+    assert_eq!(queue.len(), CAPACITY);
+    assert_eq!(queue.push(99), Err(99));
+    across_the_wrap(Fifo4::with_start_cursor(CAPACITY, START));
+}
+
+#[test]
+fn fifo5() {
+    across_the_wrap(Fifo5::with_start_cursor(CAPACITY, START));
+}
+
+#[test]
+fn fifo5_batches() {
+    let queue = Fifo5::with_start_cursor(CAPACITY, START);
+    let mut items: Vec<String> = (0..CAPACITY + 2).map(|i| i.to_string()).collect();
+    assert_eq!(queue.push_batch(&mut items), CAPACITY);
+    assert_eq!(queue.len(), CAPACITY);
+
+    let mut out = Vec::new();
+    assert_eq!(queue.pop_batch(&mut out, 3), 3);
+    assert_eq!(out, ["0", "1", "2"]);
+    assert_eq!(queue.push_batch(&mut items), 2);
+
+    queue.consume(1);
+    assert_eq!(queue.drain().count(), CAPACITY - 2);
+    assert!(queue.is_empty());
+
+    let rest = queue.try_extend((0..20).map(|i| i.to_string()));
+    assert_eq!(rest.count(), 20 - CAPACITY);
+    assert_eq!(queue.into_vec().len(), CAPACITY);
+}